@@ -3,7 +3,7 @@ extern crate proc_macro;
 use lazy_static::lazy_static;
 use proc_macro2::TokenStream;
 use proc_macro_error::{abort_call_site, proc_macro_error};
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{spanned::Spanned, *};
 
 mod ty;
@@ -43,6 +43,8 @@ fn impl_cborize_struct(input: &DeriveInput, crate_local: bool) -> TokenStream {
     let name = &input.ident;
     let generics = no_default_generics(input);
 
+    let lifetime = lifetime_param(&generics);
+
     let mut ts = TokenStream::new();
     match &input.data {
         Data::Struct(ast) => {
@@ -51,24 +53,50 @@ fn impl_cborize_struct(input: &DeriveInput, crate_local: bool) -> TokenStream {
                 &generics,
                 &ast.fields,
                 crate_local,
+                false,
+            ));
+            ts.extend(from_struct_to_cbor(
+                name,
+                &generics,
+                &ast.fields,
+                crate_local,
+                true,
             ));
             ts.extend(from_cbor_to_struct(
                 name,
                 &generics,
                 &ast.fields,
                 crate_local,
+                lifetime.as_ref(),
+            ));
+            ts.extend(from_struct_to_encode(
+                name,
+                &generics,
+                &ast.fields,
+                crate_local,
             ));
+            // borrowed fields can't be decoded from `io::Read`.
+            if lifetime.is_none() {
+                ts.extend(from_decode_to_struct(
+                    name,
+                    &generics,
+                    &ast.fields,
+                    crate_local,
+                ));
+            }
             ts
         }
         _ => unreachable!(),
     }
 }
 
+// Generate IntoCbor implementation, or ToCbor implementation if `by_ref`.
 fn from_struct_to_cbor(
     name: &Ident,
     generics: &Generics,
     fields: &Fields,
     crate_local: bool,
+    by_ref: bool,
 ) -> TokenStream {
     let id_declr = let_id(name, generics);
     let croot = get_root_crate(crate_local);
@@ -82,27 +110,20 @@ fn from_struct_to_cbor(
 
     let token_fields = match fields {
         Fields::Unit => quote! {},
-        Fields::Named(fields) => named_fields_to_cbor(fields, croot.clone()),
+        Fields::Named(fields) => named_fields_to_cbor(fields, croot.clone(), by_ref),
         Fields::Unnamed(_) => {
             abort_call_site!("unnamed struct not supported for Cborize {}", name)
         }
     };
+    let (trait_name, signature) = to_cbor_signature(&croot, by_ref);
 
-    let mut where_clause = match &generics.where_clause {
-        Some(where_clause) => quote! { #where_clause },
-        None => quote! { where },
-    };
-    for param in generics.params.iter() {
-        let type_var = match param {
-            GenericParam::Type(param) => &param.ident,
-            _ => abort_call_site!("only type parameter are supported"),
-        };
-        where_clause.extend(quote! { #type_var: #croot::cbor::IntoCbor, });
-    }
+    let where_clause = where_bound(generics, quote! { #croot::cbor::#trait_name });
 
     quote! {
-        impl#generics #croot::cbor::IntoCbor for #name#generics #where_clause {
-            fn into_cbor(self) -> #croot::Result<#croot::cbor::Cbor> {
+        impl#generics #croot::cbor::#trait_name for #name#generics #where_clause {
+            #signature {
+                use #croot::cbor::IntoCbor;
+
                 let value = self;
                 let mut items: Vec<#croot::cbor::Cbor> = Vec::default();
 
@@ -115,11 +136,14 @@ fn from_struct_to_cbor(
     }
 }
 
+// Generate FromCbor implementation, or FromCborRef implementation if the
+// type has a `lifetime` parameter, borrowing from the input.
 fn from_cbor_to_struct(
     name: &Ident,
     generics: &Generics,
     fields: &Fields,
     crate_local: bool,
+    lifetime: Option<&Lifetime>,
 ) -> TokenStream {
     let name_lit = name.to_string();
     let croot = get_root_crate(crate_local);
@@ -132,12 +156,13 @@ fn from_cbor_to_struct(
     };
 
     let id_declr = let_id(name, generics);
+    let next_item = next_item(lifetime);
     let preamble = quote! {
         // validate the cbor msg for this type.
         if items.len() == 0 {
             #croot::err_at!(FailConvert, msg: "empty msg for {}", #name_lit)?;
         }
-        let data_id = items.remove(0);
+        let data_id = #next_item;
         let type_id: #croot::cbor::Cbor = {
             #id_declr;
             #croot::cbor::Tag::from_identifier(id).into()
@@ -153,7 +178,7 @@ fn from_cbor_to_struct(
     let token_fields = match fields {
         Fields::Unit => quote! {},
         Fields::Named(fields) => {
-            let token_fields = cbor_to_named_fields(fields, croot.clone());
+            let token_fields = cbor_to_named_fields(fields, croot.clone(), lifetime);
             quote! { { #token_fields } }
         }
         Fields::Unnamed(_) => {
@@ -161,24 +186,15 @@ fn from_cbor_to_struct(
         }
     };
 
-    let mut where_clause = match &generics.where_clause {
-        Some(where_clause) => quote! { #where_clause },
-        None => quote! { where },
-    };
-    for param in generics.params.iter() {
-        let type_var = match param {
-            GenericParam::Type(param) => &param.ident,
-            _ => abort_call_site!("only type parameter are supported"),
-        };
-        where_clause.extend(quote! { #type_var: #croot::cbor::FromCbor, });
-    }
+    let (trait_name, signature, items) = from_cbor_signature(&croot, lifetime);
+    let where_clause = where_bound(generics, quote! { #croot::cbor::#trait_name });
 
     quote! {
-        impl#generics #croot::cbor::FromCbor for #name#generics #where_clause {
-            fn from_cbor(value: #croot::cbor::Cbor) -> #croot::Result<#name#generics> {
+        impl#generics #croot::cbor::#trait_name for #name#generics #where_clause {
+            #signature {
                 use #croot::{cbor::IntoCbor, Error};
 
-                let mut items = Vec::<#croot::cbor::Cbor>::from_cbor(value)?;
+                let mut items = #items;
 
                 #preamble
 
@@ -191,24 +207,51 @@ fn from_cbor_to_struct(
 fn impl_cborize_enum(input: &DeriveInput, crate_local: bool) -> TokenStream {
     let name = &input.ident;
     let generics = no_default_generics(input);
+    let lifetime = lifetime_param(&generics);
 
     let mut ts = TokenStream::new();
     match &input.data {
         Data::Enum(ast) => {
             let variants: Vec<&Variant> = ast.variants.iter().collect();
-            ts.extend(from_enum_to_cbor(name, &generics, &variants, crate_local));
-            ts.extend(from_cbor_to_enum(name, &generics, &variants, crate_local));
+            ts.extend(from_enum_to_cbor(
+                name,
+                &generics,
+                &variants,
+                crate_local,
+                false,
+            ));
+            ts.extend(from_enum_to_cbor(
+                name,
+                &generics,
+                &variants,
+                crate_local,
+                true,
+            ));
+            ts.extend(from_cbor_to_enum(
+                name,
+                &generics,
+                &variants,
+                crate_local,
+                lifetime.as_ref(),
+            ));
+            ts.extend(from_enum_to_encode(name, &generics, &variants, crate_local));
+            // borrowed fields can't be decoded from `io::Read`.
+            if lifetime.is_none() {
+                ts.extend(from_decode_to_enum(name, &generics, &variants, crate_local));
+            }
             ts
         }
         _ => unreachable!(),
     }
 }
 
+// Generate IntoCbor implementation, or ToCbor implementation if `by_ref`.
 fn from_enum_to_cbor(
     name: &Ident,
     generics: &Generics,
     variants: &[&Variant],
     crate_local: bool,
+    by_ref: bool,
 ) -> TokenStream {
     let id_declr = let_id(name, generics);
     let croot = get_root_crate(crate_local);
@@ -226,10 +269,11 @@ fn from_enum_to_cbor(
         let variant_lit = variant.ident.to_string();
         let arm = match &variant.fields {
             Fields::Unit => {
-                quote! { #name::#variant_name => #variant_lit.into_cbor()? }
+                quote! { #name::#variant_name => items.push(#variant_lit.into_cbor()?), }
             }
             Fields::Named(fields) => {
-                let (params, body) = named_var_fields_to_cbor(fields, croot.clone());
+                let (params, body) =
+                    named_var_fields_to_cbor(fields, croot.clone(), by_ref);
                 quote! {
                     #name::#variant_name{#params} => {
                         items.push(#variant_lit.into_cbor()?);
@@ -238,7 +282,8 @@ fn from_enum_to_cbor(
                 }
             }
             Fields::Unnamed(fields) => {
-                let (params, body) = unnamed_fields_to_cbor(fields, croot.clone());
+                let (params, body) =
+                    unnamed_fields_to_cbor(fields, croot.clone(), by_ref);
                 quote! {
                     #name::#variant_name(#params) => {
                         items.push(#variant_lit.into_cbor()?);
@@ -249,22 +294,15 @@ fn from_enum_to_cbor(
         };
         tok_variants.extend(arm)
     }
+    let (trait_name, signature) = to_cbor_signature(&croot, by_ref);
 
-    let mut where_clause = match &generics.where_clause {
-        Some(where_clause) => quote! { #where_clause },
-        None => quote! { where },
-    };
-    for param in generics.params.iter() {
-        let type_var = match param {
-            GenericParam::Type(param) => &param.ident,
-            _ => abort_call_site!("only type parameter are supported"),
-        };
-        where_clause.extend(quote! { #type_var: #croot::cbor::IntoCbor, });
-    }
+    let where_clause = where_bound(generics, quote! { #croot::cbor::#trait_name });
 
     quote! {
-        impl#generics #croot::cbor::IntoCbor for #name#generics #where_clause {
-            fn into_cbor(self) -> #croot::Result<#croot::cbor::Cbor> {
+        impl#generics #croot::cbor::#trait_name for #name#generics #where_clause {
+            #signature {
+                use #croot::cbor::IntoCbor;
+
                 let value = self;
 
                 let mut items: Vec<#croot::cbor::Cbor> = Vec::default();
@@ -279,21 +317,25 @@ fn from_enum_to_cbor(
     }
 }
 
+// Generate FromCbor implementation, or FromCborRef implementation if the
+// type has a `lifetime` parameter, borrowing from the input.
 fn from_cbor_to_enum(
     name: &Ident,
     generics: &Generics,
     variants: &[&Variant],
     crate_local: bool,
+    lifetime: Option<&Lifetime>,
 ) -> TokenStream {
     let name_lit = name.to_string();
     let id_declr = let_id(name, generics);
     let croot = get_root_crate(crate_local);
+    let next_item = next_item(lifetime);
     let preamble = quote! {
         // validate the cbor msg for this type.
         if items.len() < 2 {
             #croot::err_at!(FailConvert, msg: "empty msg for {}", #name_lit)?;
         }
-        let data_id = items.remove(0);
+        let data_id = #next_item;
         let type_id: #croot::cbor::Cbor= {
             #id_declr;
             #croot::cbor::Tag::from_identifier(id).into()
@@ -302,7 +344,7 @@ fn from_cbor_to_enum(
             #croot::err_at!(FailConvert, msg: "bad {}", #name_lit)?
         }
 
-        let variant_name = String::from_cbor(items.remove(0))?;
+        let variant_name = <String as #croot::cbor::FromCbor>::from_cbor(#next_item)?;
     };
 
     let mut check_variants: TokenStream = TokenStream::new();
@@ -356,37 +398,29 @@ fn from_cbor_to_enum(
         let variant_lit = &variant.ident.to_string();
         let arm = match &variant.fields {
             Fields::Unit => quote! {
-                #variant_lit => #name::#variant_name
+                #variant_lit => #name::#variant_name,
             },
             Fields::Named(fields) => {
-                let (_, body) = cbor_to_named_var_fields(fields, croot.clone());
+                let (_, body) = cbor_to_named_var_fields(fields, croot.clone(), lifetime);
                 quote! { #variant_lit => #name::#variant_name { #body }, }
             }
             Fields::Unnamed(fields) => {
-                let (_, body) = cbor_to_unnamed_fields(fields, croot.clone());
+                let (_, body) = cbor_to_unnamed_fields(fields, croot.clone(), lifetime);
                 quote! { #variant_lit => #name::#variant_name(#body), }
             }
         };
         tok_variants.extend(arm);
     }
 
-    let mut where_clause = match &generics.where_clause {
-        Some(where_clause) => quote! { #where_clause },
-        None => quote! { where },
-    };
-    for param in generics.params.iter() {
-        let type_var = match param {
-            GenericParam::Type(param) => &param.ident,
-            _ => abort_call_site!("only type parameter are supported"),
-        };
-        where_clause.extend(quote! { #type_var: #croot::cbor::FromCbor, });
-    }
+    let (trait_name, signature, items) = from_cbor_signature(&croot, lifetime);
+    let where_clause = where_bound(generics, quote! { #croot::cbor::#trait_name });
+
     quote! {
-        impl#generics #croot::cbor::FromCbor for #name#generics #where_clause {
-            fn from_cbor(value: #croot::cbor::Cbor) -> #croot::Result<#name#generics> {
+        impl#generics #croot::cbor::#trait_name for #name#generics #where_clause {
+            #signature {
                 use #croot::{cbor::IntoCbor, Error};
 
-                let mut items =  Vec::<#croot::cbor::Cbor>::from_cbor(value)?;
+                let mut items = #items;
 
                 #preamble
 
@@ -409,19 +443,23 @@ fn from_cbor_to_enum(
     }
 }
 
-fn named_fields_to_cbor(fields: &FieldsNamed, croot: TokenStream) -> TokenStream {
+fn named_fields_to_cbor(
+    fields: &FieldsNamed,
+    croot: TokenStream,
+    by_ref: bool,
+) -> TokenStream {
     let mut tokens = TokenStream::new();
     for field in fields.named.iter() {
         let is_bytes = is_bytes_ty(&field.ty);
 
-        match &field.ident {
-            Some(field_name) if is_bytes => tokens.extend(quote! {
-                items.push(#croot::cbor::Cbor::bytes_into_cbor(value.#field_name)?);
-            }),
-            Some(field_name) => tokens.extend(quote! {
-                items.push(value.#field_name.into_cbor()?);
-            }),
-            None => (),
+        if let Some(field_name) = &field.ident {
+            let field_tokens = field_to_cbor(
+                quote! { value.#field_name },
+                is_bytes,
+                croot.clone(),
+                by_ref,
+            );
+            tokens.extend(field_tokens)
         }
     }
     tokens
@@ -430,6 +468,7 @@ fn named_fields_to_cbor(fields: &FieldsNamed, croot: TokenStream) -> TokenStream
 fn named_var_fields_to_cbor(
     fields: &FieldsNamed,
     croot: TokenStream,
+    by_ref: bool,
 ) -> (TokenStream, TokenStream) {
     let mut params = TokenStream::new();
     let mut body = TokenStream::new();
@@ -439,15 +478,12 @@ fn named_var_fields_to_cbor(
         let field_name = field.ident.as_ref().unwrap();
         params.extend(quote! { #field_name, });
 
-        match &field.ident {
-            Some(field_name) if is_bytes => body.extend(quote! {
-                items.push(#croot::cbor::Cbor::bytes_into_cbor(#field_name)?);
-            }),
-            Some(field_name) => body.extend(quote! {
-                items.push(#field_name.into_cbor()?);
-            }),
-            None => (),
-        }
+        body.extend(field_to_cbor(
+            quote! { #field_name },
+            is_bytes,
+            croot.clone(),
+            by_ref,
+        ));
     }
     (params, body)
 }
@@ -455,6 +491,7 @@ fn named_var_fields_to_cbor(
 fn unnamed_fields_to_cbor(
     fields: &FieldsUnnamed,
     croot: TokenStream,
+    by_ref: bool,
 ) -> (TokenStream, TokenStream) {
     let mut params = TokenStream::new();
     let mut body = TokenStream::new();
@@ -464,36 +501,93 @@ fn unnamed_fields_to_cbor(
 
         params.extend(quote! { #field_name, });
 
-        if is_bytes {
-            body.extend(quote! {
-                items.push(#croot::cbor::Cbor::bytes_into_cbor(#field_name)?);
-            });
-        } else {
-            body.extend(quote! {
-                items.push(#field_name.into_cbor()?);
-            });
-        }
+        body.extend(field_to_cbor(
+            quote! { #field_name },
+            is_bytes,
+            croot.clone(),
+            by_ref,
+        ));
     }
     (params, body)
 }
 
-fn cbor_to_named_fields(fields: &FieldsNamed, croot: TokenStream) -> TokenStream {
+// Push the field value, `expr`, into `items`. Field values are borrowed
+// if `by_ref`, otherwise owned.
+fn field_to_cbor(
+    expr: TokenStream,
+    is_bytes: bool,
+    croot: TokenStream,
+    by_ref: bool,
+) -> TokenStream {
+    match (is_bytes, by_ref) {
+        (true, true) => quote! {
+            items.push(#expr.as_slice().into_cbor()?);
+        },
+        (true, false) => quote! {
+            items.push(#croot::cbor::Cbor::bytes_into_cbor(#expr)?);
+        },
+        (false, true) => quote! {
+            items.push(#croot::cbor::ToCbor::to_cbor(&#expr)?);
+        },
+        (false, false) => quote! {
+            items.push(#expr.into_cbor()?);
+        },
+    }
+}
+
+fn to_cbor_signature(croot: &TokenStream, by_ref: bool) -> (TokenStream, TokenStream) {
+    if by_ref {
+        let signature = quote! {
+            fn to_cbor(&self) -> #croot::Result<#croot::cbor::Cbor>
+        };
+        (quote! { ToCbor }, signature)
+    } else {
+        let signature = quote! {
+            fn into_cbor(self) -> #croot::Result<#croot::cbor::Cbor>
+        };
+        (quote! { IntoCbor }, signature)
+    }
+}
+
+// Return the trait name, function signature and the expression to convert
+// `value` into list of items, for FromCbor, or for FromCborRef if `lifetime`.
+fn from_cbor_signature(
+    croot: &TokenStream,
+    lifetime: Option<&Lifetime>,
+) -> (TokenStream, TokenStream, TokenStream) {
+    match lifetime {
+        Some(lt) => {
+            let signature = quote! {
+                fn from_cbor_ref(value: #croot::cbor::CborRef<#lt>) -> #croot::Result<Self>
+            };
+            let items = quote! {
+                <Vec<#croot::cbor::CborRef<#lt>> as #croot::cbor::FromCborRef<#lt>>::
+                    from_cbor_ref(value)?
+            };
+            (quote! { FromCborRef<#lt> }, signature, items)
+        }
+        None => {
+            let signature = quote! {
+                fn from_cbor(value: #croot::cbor::Cbor) -> #croot::Result<Self>
+            };
+            let items = quote! {
+                <Vec<#croot::cbor::Cbor> as #croot::cbor::FromCbor>::from_cbor(value)?
+            };
+            (quote! { FromCbor }, signature, items)
+        }
+    }
+}
+
+fn cbor_to_named_fields(
+    fields: &FieldsNamed,
+    croot: TokenStream,
+    lifetime: Option<&Lifetime>,
+) -> TokenStream {
     let mut tokens = TokenStream::new();
     for field in fields.named.iter() {
-        let is_bytes = is_bytes_ty(&field.ty);
-
         let field_name = field.ident.as_ref().unwrap();
-        let ty = &field.ty;
-        let field_tokens = if is_bytes {
-            quote! {
-                #field_name: items.remove(0).into_bytes()?,
-            }
-        } else {
-            quote! {
-                #field_name: <#ty as #croot::cbor::FromCbor>::from_cbor(items.remove(0))?,
-            }
-        };
-        tokens.extend(field_tokens);
+        let val = cbor_to_field(&field.ty, croot.clone(), lifetime);
+        tokens.extend(quote! { #field_name: #val, });
     }
     tokens
 }
@@ -501,25 +595,16 @@ fn cbor_to_named_fields(fields: &FieldsNamed, croot: TokenStream) -> TokenStream
 fn cbor_to_named_var_fields(
     fields: &FieldsNamed,
     croot: TokenStream,
+    lifetime: Option<&Lifetime>,
 ) -> (TokenStream, TokenStream) {
     let mut params = TokenStream::new();
     let mut body = TokenStream::new();
     for field in fields.named.iter() {
-        let is_bytes = is_bytes_ty(&field.ty);
-
         let field_name = field.ident.as_ref().unwrap();
         params.extend(quote! { #field_name, });
 
-        let ty = &field.ty;
-        if is_bytes {
-            body.extend(quote! {
-                #field_name: items.remove(0).into_bytes()?,
-            });
-        } else {
-            body.extend(quote! {
-                #field_name: <#ty as #croot::cbor::FromCbor>::from_cbor(items.remove(0))?,
-            });
-        }
+        let val = cbor_to_field(&field.ty, croot.clone(), lifetime);
+        body.extend(quote! { #field_name: #val, });
     }
     (params, body)
 }
@@ -527,27 +612,413 @@ fn cbor_to_named_var_fields(
 fn cbor_to_unnamed_fields(
     fields: &FieldsUnnamed,
     croot: TokenStream,
+    lifetime: Option<&Lifetime>,
 ) -> (TokenStream, TokenStream) {
     let mut params = TokenStream::new();
     let mut body = TokenStream::new();
     for (field_name, field) in UNNAMED_FIELDS.iter().zip(fields.unnamed.iter()) {
         let field_name = Ident::new(field_name, field.span());
-        let is_bytes = is_bytes_ty(&field.ty);
-
         params.extend(quote! { #field_name, });
 
-        let ty = &field.ty;
-        if is_bytes {
-            body.extend(quote! { items.remove(0).into_bytes()?, });
-        } else {
-            body.extend(
-                quote! { <#ty as #croot::cbor::FromCbor>::from_cbor(items.remove(0))?, },
-            );
-        }
+        let val = cbor_to_field(&field.ty, croot.clone(), lifetime);
+        body.extend(quote! { #val, });
     }
     (params, body)
 }
 
+// Take the next item, as Cbor value.
+fn next_item(lifetime: Option<&Lifetime>) -> TokenStream {
+    match lifetime {
+        Some(_) => quote! { items.remove(0).into_cbor()? },
+        None => quote! { items.remove(0) },
+    }
+}
+
+// Convert the next item into field value of type `ty`, borrowing from the
+// item if `lifetime`.
+fn cbor_to_field(
+    ty: &syn::Type,
+    croot: TokenStream,
+    lifetime: Option<&Lifetime>,
+) -> TokenStream {
+    match (is_bytes_ty(ty), lifetime) {
+        (true, Some(_)) => quote! { items.remove(0).into_cbor()?.into_bytes()? },
+        (true, None) => quote! { items.remove(0).into_bytes()? },
+        (false, Some(lt)) => quote! {
+            <#ty as #croot::cbor::FromCborRef<#lt>>::from_cbor_ref(items.remove(0))?
+        },
+        (false, None) => quote! {
+            <#ty as #croot::cbor::FromCbor>::from_cbor(items.remove(0))?
+        },
+    }
+}
+
+// Generate CborEncode implementation, writing the same bytes as that of
+// IntoCbor followed by Cbor::encode.
+fn from_struct_to_encode(
+    name: &Ident,
+    generics: &Generics,
+    fields: &Fields,
+    crate_local: bool,
+) -> TokenStream {
+    let croot = get_root_crate(crate_local);
+    let (n_items, token_fields) = match fields {
+        Fields::Unit => (1_usize, quote! {}),
+        Fields::Named(fields) => {
+            let mut tokens = TokenStream::new();
+            for field in fields.named.iter() {
+                let field_name = field.ident.as_ref().unwrap();
+                tokens.extend(field_to_encode(
+                    quote! { self.#field_name },
+                    is_bytes_ty(&field.ty),
+                    croot.clone(),
+                ));
+            }
+            (fields.named.len() + 1, tokens)
+        }
+        Fields::Unnamed(_) => {
+            abort_call_site!("unnamed struct not supported for Cborize {}", name)
+        }
+    };
+    let where_clause = where_bound(generics, quote! { #croot::cbor::CborEncode });
+
+    quote! {
+        impl#generics #croot::cbor::CborEncode for #name#generics #where_clause {
+            fn encode_to<__W>(&self, w: &mut __W) -> #croot::Result<usize>
+            where
+                __W: ::std::io::Write,
+            {
+                let mut n = #croot::cbor::Cbor::encode_list_hdr(#n_items, w)?;
+                n += #croot::cbor::Cbor::encode_tag_hdr(39, w)?;
+                n += #croot::cbor::CborEncode::encode_to(&Self::ID, w)?;
+                #token_fields
+                Ok(n)
+            }
+        }
+    }
+}
+
+// Generate CborDecode implementation, accepting the same bytes as that of
+// Cbor::decode followed by FromCbor.
+fn from_decode_to_struct(
+    name: &Ident,
+    generics: &Generics,
+    fields: &Fields,
+    crate_local: bool,
+) -> TokenStream {
+    let name_lit = name.to_string();
+    let croot = get_root_crate(crate_local);
+    let (n_fields, token_fields) = match fields {
+        Fields::Unit => (0_u64, quote! {}),
+        Fields::Named(fields) => {
+            let mut tokens = TokenStream::new();
+            for field in fields.named.iter() {
+                let field_name = field.ident.as_ref().unwrap();
+                let val = decode_to_field(&field.ty, croot.clone());
+                tokens.extend(quote! { #field_name: #val, });
+            }
+            (fields.named.len() as u64, quote! { { #tokens } })
+        }
+        Fields::Unnamed(_) => {
+            abort_call_site!("unnamed struct not supported for Cborize {}", name)
+        }
+    };
+    let preamble = decode_preamble(&name_lit, croot.clone());
+    let where_clause = where_bound(generics, quote! { #croot::cbor::CborDecode });
+
+    quote! {
+        impl#generics #croot::cbor::CborDecode for #name#generics #where_clause {
+            fn decode_after_hdr<__R>(
+                major: u8,
+                info: #croot::cbor::Info,
+                r: &mut __R,
+            ) -> #croot::Result<(Self, usize)>
+            where
+                __R: ::std::io::Read,
+            {
+                use #croot::{cbor::IntoCbor, Error};
+
+                #preamble
+                if let Some(len) = len {
+                    if len != #n_fields + 1 {
+                        #croot::err_at!(FailConvert, msg: "bad arity {} {}", #n_fields, len - 1)?;
+                    }
+                }
+
+                let val = #name #token_fields;
+                if len.is_none() {
+                    n += #croot::cbor::Cbor::decode_break(r)?;
+                }
+                Ok((val, n))
+            }
+        }
+    }
+}
+
+// Generate CborEncode implementation, writing the same bytes as that of
+// IntoCbor followed by Cbor::encode.
+fn from_enum_to_encode(
+    name: &Ident,
+    generics: &Generics,
+    variants: &[&Variant],
+    crate_local: bool,
+) -> TokenStream {
+    let croot = get_root_crate(crate_local);
+
+    let mut len_variants = TokenStream::new();
+    let mut tok_variants = TokenStream::new();
+    for variant in variants.iter() {
+        let variant_name = &variant.ident;
+        let variant_lit = variant.ident.to_string();
+        let (len_arm, arm) = match &variant.fields {
+            Fields::Unit => (
+                quote! { #name::#variant_name => 2, },
+                quote! {
+                    #name::#variant_name => {
+                        n += #croot::cbor::CborEncode::encode_to(#variant_lit, w)?;
+                    }
+                },
+            ),
+            Fields::Named(fields) => {
+                let mut params = TokenStream::new();
+                let mut body = TokenStream::new();
+                for field in fields.named.iter() {
+                    let field_name = field.ident.as_ref().unwrap();
+                    let param = format_ident!("__{}", field_name);
+                    params.extend(quote! { #field_name: #param, });
+                    body.extend(field_to_encode(
+                        quote! { #param },
+                        is_bytes_ty(&field.ty),
+                        croot.clone(),
+                    ));
+                }
+                let n_items = fields.named.len() + 2;
+                (
+                    quote! { #name::#variant_name{..} => #n_items, },
+                    quote! {
+                        #name::#variant_name{#params} => {
+                            n += #croot::cbor::CborEncode::encode_to(#variant_lit, w)?;
+                            #body
+                        }
+                    },
+                )
+            }
+            Fields::Unnamed(fields) => {
+                let mut params = TokenStream::new();
+                let mut body = TokenStream::new();
+                for (field_name, field) in
+                    UNNAMED_FIELDS.iter().zip(fields.unnamed.iter())
+                {
+                    let param = format_ident!("__{}", field_name);
+                    params.extend(quote! { #param, });
+                    body.extend(field_to_encode(
+                        quote! { #param },
+                        is_bytes_ty(&field.ty),
+                        croot.clone(),
+                    ));
+                }
+                let n_items = fields.unnamed.len() + 2;
+                (
+                    quote! { #name::#variant_name(..) => #n_items, },
+                    quote! {
+                        #name::#variant_name(#params) => {
+                            n += #croot::cbor::CborEncode::encode_to(#variant_lit, w)?;
+                            #body
+                        }
+                    },
+                )
+            }
+        };
+        len_variants.extend(len_arm);
+        tok_variants.extend(arm);
+    }
+    let where_clause = where_bound(generics, quote! { #croot::cbor::CborEncode });
+
+    quote! {
+        impl#generics #croot::cbor::CborEncode for #name#generics #where_clause {
+            fn encode_to<__W>(&self, w: &mut __W) -> #croot::Result<usize>
+            where
+                __W: ::std::io::Write,
+            {
+                let n_items: usize = match self {
+                    #len_variants
+                };
+                let mut n = #croot::cbor::Cbor::encode_list_hdr(n_items, w)?;
+                n += #croot::cbor::Cbor::encode_tag_hdr(39, w)?;
+                n += #croot::cbor::CborEncode::encode_to(&Self::ID, w)?;
+                match self {
+                    #tok_variants
+                }
+                Ok(n)
+            }
+        }
+    }
+}
+
+// Generate CborDecode implementation, accepting the same bytes as that of
+// Cbor::decode followed by FromCbor.
+fn from_decode_to_enum(
+    name: &Ident,
+    generics: &Generics,
+    variants: &[&Variant],
+    crate_local: bool,
+) -> TokenStream {
+    let name_lit = name.to_string();
+    let croot = get_root_crate(crate_local);
+
+    let mut len_variants = TokenStream::new();
+    let mut tok_variants = TokenStream::new();
+    for variant in variants.iter() {
+        let variant_name = &variant.ident;
+        let variant_lit = variant.ident.to_string();
+        let (n_fields, arm) = match &variant.fields {
+            Fields::Unit => (0_u64, quote! { #variant_lit => #name::#variant_name, }),
+            Fields::Named(fields) => {
+                let mut body = TokenStream::new();
+                for field in fields.named.iter() {
+                    let field_name = field.ident.as_ref().unwrap();
+                    let val = decode_to_field(&field.ty, croot.clone());
+                    body.extend(quote! { #field_name: #val, });
+                }
+                let arm = quote! { #variant_lit => #name::#variant_name { #body }, };
+                (fields.named.len() as u64, arm)
+            }
+            Fields::Unnamed(fields) => {
+                let mut body = TokenStream::new();
+                for field in fields.unnamed.iter() {
+                    let val = decode_to_field(&field.ty, croot.clone());
+                    body.extend(quote! { #val, });
+                }
+                let arm = quote! { #variant_lit => #name::#variant_name(#body), };
+                (fields.unnamed.len() as u64, arm)
+            }
+        };
+        len_variants.extend(quote! { #variant_lit => #n_fields, });
+        tok_variants.extend(arm);
+    }
+    let preamble = decode_preamble(&name_lit, croot.clone());
+    let where_clause = where_bound(generics, quote! { #croot::cbor::CborDecode });
+
+    quote! {
+        impl#generics #croot::cbor::CborDecode for #name#generics #where_clause {
+            fn decode_after_hdr<__R>(
+                major: u8,
+                info: #croot::cbor::Info,
+                r: &mut __R,
+            ) -> #croot::Result<(Self, usize)>
+            where
+                __R: ::std::io::Read,
+            {
+                use #croot::{cbor::IntoCbor, Error};
+
+                #preamble
+
+                let (variant_name, m) =
+                    <String as #croot::cbor::CborDecode>::decode_from(r)?;
+                n += m;
+
+                let n_fields: u64 = match variant_name.as_str() {
+                    #len_variants
+                    _ => #croot::err_at!(
+                        FailConvert, msg: "invalid variant_name {}", variant_name
+                    )?,
+                };
+                if let Some(len) = len {
+                    if len != n_fields + 2 {
+                        #croot::err_at!(
+                            FailConvert, msg: "bad arity {} {}", n_fields, len - 2
+                        )?;
+                    }
+                }
+
+                let val = match variant_name.as_str() {
+                    #tok_variants
+                    _ => #croot::err_at!(
+                        FailConvert, msg: "invalid variant_name {}", variant_name
+                    )?,
+                };
+                if len.is_none() {
+                    n += #croot::cbor::Cbor::decode_break(r)?;
+                }
+                Ok((val, n))
+            }
+        }
+    }
+}
+
+// Decode list header and validate the identifier, leaves list length as
+// `len` and number of bytes read as `n`.
+fn decode_preamble(name_lit: &str, croot: TokenStream) -> TokenStream {
+    quote! {
+        let (len, mut n) = #croot::cbor::Cbor::decode_list_len(major, info, r)?;
+        if len == Some(0) {
+            #croot::err_at!(FailConvert, msg: "empty msg for {}", #name_lit)?;
+        }
+        let (data_id, m) =
+            <#croot::cbor::Cbor as #croot::cbor::CborDecode>::decode_from(r)?;
+        n += m;
+        let type_id: #croot::cbor::Cbor = {
+            let id = Self::ID.into_cbor()?;
+            #croot::cbor::Tag::from_identifier(id).into()
+        };
+        if data_id != type_id {
+            #croot::err_at!(FailConvert, msg: "bad id for {}", #name_lit)?;
+        }
+    }
+}
+
+// Write the field value, `expr`, into `w`.
+fn field_to_encode(expr: TokenStream, is_bytes: bool, croot: TokenStream) -> TokenStream {
+    if is_bytes {
+        quote! { n += #croot::cbor::CborEncode::encode_to(#expr.as_slice(), w)?; }
+    } else {
+        quote! { n += #croot::cbor::CborEncode::encode_to(&#expr, w)?; }
+    }
+}
+
+// Read the field value of type `ty` from `r`.
+fn decode_to_field(ty: &syn::Type, croot: TokenStream) -> TokenStream {
+    if is_bytes_ty(ty) {
+        quote! {{
+            let (val, m) = <#croot::cbor::Cbor as #croot::cbor::CborDecode>::decode_from(r)?;
+            n += m;
+            val.into_bytes()?
+        }}
+    } else {
+        quote! {{
+            let (val, m) = <#ty as #croot::cbor::CborDecode>::decode_from(r)?;
+            n += m;
+            val
+        }}
+    }
+}
+
+fn where_bound(generics: &Generics, bound: TokenStream) -> TokenStream {
+    let mut where_clause = match &generics.where_clause {
+        Some(where_clause) => quote! { #where_clause },
+        None => quote! { where },
+    };
+    for param in generics.params.iter() {
+        let type_var = match param {
+            GenericParam::Type(param) => &param.ident,
+            GenericParam::Lifetime(_) => continue,
+            _ => abort_call_site!("only type and lifetime parameter are supported"),
+        };
+        where_clause.extend(quote! { #type_var: #bound, });
+    }
+    where_clause
+}
+
+// Return the lifetime parameter, if any, fields can borrow for that lifetime.
+fn lifetime_param(generics: &Generics) -> Option<Lifetime> {
+    let mut lifetimes = generics.lifetimes();
+    match (lifetimes.next(), lifetimes.next()) {
+        (None, _) => None,
+        (Some(param), None) => Some(param.lifetime.clone()),
+        (Some(_), Some(_)) => abort_call_site!("only one lifetime parameter is supported"),
+    }
+}
+
 fn let_id(name: &Ident, generics: &Generics) -> TokenStream {
     if generics.params.is_empty() {
         quote! { let id = #name::ID.into_cbor()? }
@@ -0,0 +1,380 @@
+//! Command line tool to inspect and convert CBOR data.
+//!
+//! Input is read from file, or from stdin when file is not supplied, and
+//! can be a sequence of CBOR data items, refer to [RFC 8742][rfc].
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc8742.html
+
+use structopt::StructOpt;
+
+use std::{
+    convert::TryFrom,
+    fs,
+    io::{self, Read, Write},
+    path, process,
+};
+
+use mkit::{
+    cbor::{
+        Cbor, CborReader, CborSeqReader, DecodeOptions, Event, Info, JsonOptions,
+        SeqItem, SimpleValue, Tag, TagMode,
+    },
+    err_at, Error, Result,
+};
+
+/// Tag number used by mkit to identify types, refer to [Tag::Identifier].
+const TAG_IDENTIFIER: u64 = 39;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mkit-cbor", about = "Inspect and convert CBOR data")]
+enum Opt {
+    /// Annotated hex dump, with major type, additional info and offsets.
+    Decode {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Indented tree view of data items.
+    Tree {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Render data items in diagnostic notation.
+    Diag {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Convert data items to JSON, one per line, or JSON to CBOR.
+    Json {
+        #[structopt(flatten)]
+        input: Input,
+        /// Convert JSON text into CBOR, written to stdout.
+        #[structopt(long = "reverse", short = "r")]
+        reverse: bool,
+        /// Convert tags to and from {"@tag": num, "@value": item} objects.
+        #[structopt(long = "tag-object")]
+        tag_object: bool,
+    },
+    /// Check data items for well-formedness and limits.
+    Validate {
+        #[structopt(flatten)]
+        input: Input,
+        /// Reject non-shortest encoding and invalid UTF-8 text.
+        #[structopt(long = "strict")]
+        strict: bool,
+        /// Maximum number of bytes for each data item.
+        #[structopt(long = "max-size")]
+        max_size: Option<usize>,
+        /// Maximum length of byte-string or text-string.
+        #[structopt(long = "max-string")]
+        max_string: Option<usize>,
+        /// Maximum number of items in a list, or entries in a map.
+        #[structopt(long = "max-items")]
+        max_items: Option<usize>,
+        /// Maximum nesting depth.
+        #[structopt(long = "max-depth")]
+        max_depth: Option<u32>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct Input {
+    /// Input is hex text, white-space is ignored.
+    #[structopt(long = "hex")]
+    hex: bool,
+    /// Input file, read from stdin if not supplied.
+    #[structopt(parse(from_os_str))]
+    file: Option<path::PathBuf>,
+}
+
+impl Input {
+    fn read(&self) -> Result<Vec<u8>> {
+        let data = match &self.file {
+            Some(file) => err_at!(IOError, fs::read(file), "{:?}", file)?,
+            None => {
+                let mut data = vec![];
+                err_at!(IOError, io::stdin().read_to_end(&mut data))?;
+                data
+            }
+        };
+
+        match self.hex {
+            true => from_hex(&data),
+            false => Ok(data),
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Opt::from_args()) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run(opt: Opt) -> Result<()> {
+    match opt {
+        Opt::Decode { input } => do_decode(&input.read()?),
+        Opt::Tree { input } => {
+            for val in decode_seq(&input.read()?)? {
+                print_tree(&val, "", 0);
+            }
+            Ok(())
+        }
+        Opt::Diag { input } => {
+            for val in decode_seq(&input.read()?)? {
+                println!("{}", val);
+            }
+            Ok(())
+        }
+        Opt::Json {
+            input,
+            reverse,
+            tag_object,
+        } => {
+            let opts = JsonOptions {
+                tags: match tag_object {
+                    true => TagMode::Object,
+                    false => TagMode::Unwrap,
+                },
+            };
+            match reverse {
+                true => {
+                    let data = input.read()?;
+                    let text = err_at!(FailConvert, std::str::from_utf8(&data))?;
+                    let mut buf: Vec<u8> = vec![];
+                    Cbor::from_json_with(text, opts)?.encode(&mut buf)?;
+                    err_at!(IOError, io::stdout().write_all(&buf))
+                }
+                false => {
+                    for val in decode_seq(&input.read()?)? {
+                        println!("{}", val.to_json_with(opts)?);
+                    }
+                    Ok(())
+                }
+            }
+        }
+        Opt::Validate {
+            input,
+            strict,
+            max_size,
+            max_string,
+            max_items,
+            max_depth,
+        } => {
+            let def = DecodeOptions::default();
+            let opts = DecodeOptions {
+                max_size: max_size.unwrap_or(def.max_size),
+                max_string: max_string.unwrap_or(def.max_string),
+                max_items: max_items.unwrap_or(def.max_items),
+                max_depth: max_depth.unwrap_or(def.max_depth),
+                strict,
+                lazy: None,
+                stringref: false,
+            };
+            do_validate(&input.read()?, opts)
+        }
+    }
+}
+
+fn decode_seq(data: &[u8]) -> Result<Vec<Cbor>> {
+    let mut seq = CborSeqReader::new(data);
+    let mut items = vec![];
+    loop {
+        let off = seq.to_offset();
+        match seq.read_value()? {
+            SeqItem::Item(val) => items.push(val),
+            SeqItem::Eof => break Ok(items),
+            SeqItem::Truncated(n) => {
+                err_at!(FailCbor, msg: "item at offset {}: truncated after {} bytes", off, n)?
+            }
+        }
+    }
+}
+
+fn do_validate(data: &[u8], opts: DecodeOptions) -> Result<()> {
+    let mut seq = CborSeqReader::with_options(data, opts);
+    let mut n = 0;
+    loop {
+        let off = seq.to_offset();
+        match seq.read_value() {
+            Ok(SeqItem::Item(_)) => {
+                let m = seq.to_offset() - off;
+                println!("item {} at offset {}: ok, {} bytes", n, off, m)
+            }
+            Ok(SeqItem::Eof) => break,
+            Ok(SeqItem::Truncated(m)) => {
+                err_at!(FailCbor, msg: "item {} at offset {}: truncated after {} bytes", n, off, m)?
+            }
+            Err(err) => err_at!(FailCbor, msg: "item {} at offset {}: {}", n, off, err)?,
+        }
+        n += 1;
+    }
+    println!("{} items, {} bytes", n, data.len());
+    Ok(())
+}
+
+fn do_decode(data: &[u8]) -> Result<()> {
+    // hex bytes beyond this are elided.
+    const MAX_HEX: usize = 16;
+
+    let mut r = CborReader::new(data);
+    let mut prev_tag: Option<u64> = None;
+    loop {
+        let start = r.to_offset();
+        let event = match r.next_event()? {
+            Some(event) => event,
+            None => break Ok(()),
+        };
+        let end = r.to_offset();
+        // containers and tags have pushed a new level, for their items.
+        let depth = match event {
+            Event::ArrayStart(_) | Event::MapStart(_) | Event::Tag(_) => r.to_depth() - 1,
+            _ => r.to_depth(),
+        };
+
+        let hdr = data[start];
+        let info = Info::try_from(hdr & 0x1f)?;
+        let mut hex: String = data[start..end.min(start + MAX_HEX)]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if end - start > MAX_HEX {
+            hex.push_str("..");
+        }
+
+        let indent = "  ".repeat(depth - 1);
+        let desc = describe(&event, prev_tag);
+        println!(
+            "{:08x}  {:<36} # {}{}  [major {}, {:?}]",
+            start,
+            hex,
+            indent,
+            desc,
+            hdr >> 5,
+            info
+        );
+
+        prev_tag = match event {
+            Event::Tag(num) => Some(num),
+            _ => None,
+        };
+    }
+}
+
+fn describe(event: &Event, prev_tag: Option<u64>) -> String {
+    match event {
+        Event::UInt(num) if prev_tag == Some(TAG_IDENTIFIER) => {
+            format!("type-id {}", num)
+        }
+        Event::UInt(num) => format!("uint {}", num),
+        Event::NInt(num) => format!("nint {}", -i128::from(*num) - 1),
+        Event::Bytes(data) => format!("bytes ({})", data.len()),
+        Event::Text(text) if prev_tag == Some(TAG_IDENTIFIER) => {
+            format!("type-id {:?}", text)
+        }
+        Event::Text(text) => format!("text ({}) {:?}", text.len(), text),
+        Event::ArrayStart(Some(n)) => format!("list ({})", n),
+        Event::ArrayStart(None) => "list (_)".to_string(),
+        Event::MapStart(Some(n)) => format!("map ({})", n),
+        Event::MapStart(None) => "map (_)".to_string(),
+        Event::Tag(num) => format!("tag {}{}", num, tag_name(*num)),
+        Event::Simple(sval) => describe_simple(sval),
+        Event::Break => "break".to_string(),
+    }
+}
+
+fn describe_simple(sval: &SimpleValue) -> String {
+    // display ignores the additional info for major type 7.
+    let val = Cbor::Major7(Info::Tiny(0), *sval);
+    match sval {
+        SimpleValue::F16(_) => format!("float16 {}", val),
+        SimpleValue::F32(_) => format!("float32 {}", val),
+        SimpleValue::F64(_) => format!("float64 {}", val),
+        _ => format!("simple {}", val),
+    }
+}
+
+fn tag_name(num: u64) -> &'static str {
+    use mkit::cbor::*;
+
+    match num {
+        TAG_IDENTIFIER => " mkit-type-identifier",
+        TAG_DATETIME => " date-time",
+        TAG_EPOCH_TIME => " epoch-time",
+        TAG_POS_BIGNUM => " positive-bignum",
+        TAG_NEG_BIGNUM => " negative-bignum",
+        TAG_DECIMAL => " decimal-fraction",
+        TAG_BIGFLOAT => " bigfloat",
+        TAG_EMBEDDED => " embedded-cbor",
+        TAG_STRINGREF => " stringref",
+        TAG_SHAREABLE => " shareable",
+        TAG_SHAREDREF => " shared-reference",
+        TAG_URI => " uri",
+        TAG_UUID => " uuid",
+        TAG_IPV4 => " ipv4-address",
+        TAG_IPV6 => " ipv6-address",
+        TAG_STRINGREF_NAMESPACE => " stringref-namespace",
+        TAG_SELF_DESCRIBE => " self-described-cbor",
+        _ => "",
+    }
+}
+
+fn print_tree(val: &Cbor, prefix: &str, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match val {
+        Cbor::Major0(_, num) => println!("{}{}uint {}", indent, prefix, num),
+        Cbor::Major1(_, num) => {
+            println!("{}{}nint {}", indent, prefix, -i128::from(*num) - 1)
+        }
+        Cbor::Major2(_, data) => {
+            let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+            println!("{}{}bytes ({}) h'{}'", indent, prefix, data.len(), hex)
+        }
+        Cbor::Major3(_, _) => println!("{}{}text {}", indent, prefix, val),
+        Cbor::Major4(_, list) => {
+            println!("{}{}list ({})", indent, prefix, list.len());
+            for item in list.iter() {
+                print_tree(item, "", depth + 1);
+            }
+        }
+        Cbor::Major5(_, map) => {
+            println!("{}{}map ({})", indent, prefix, map.len());
+            for (key, val) in map.iter() {
+                print_tree(val, &format!("{}: ", key), depth + 1);
+            }
+        }
+        Cbor::Major6(_, Tag::Identifier(item)) => {
+            println!("{}{}type-id {}", indent, prefix, item)
+        }
+        Cbor::Major6(_, tag) => {
+            let num = tag.to_tag_value();
+            println!("{}{}tag {}{}", indent, prefix, num, tag_name(num));
+            print_tree(tag.as_item(), "", depth + 1);
+        }
+        Cbor::Major7(_, sval) => {
+            println!("{}{}{}", indent, prefix, describe_simple(sval))
+        }
+        Cbor::Binary(data) => match Cbor::decode(&mut data.as_slice()) {
+            Ok((val, _)) => print_tree(&val, prefix, depth),
+            Err(err) => println!("{}{}binary ({}) {}", indent, prefix, data.len(), err),
+        },
+    }
+}
+
+fn from_hex(data: &[u8]) -> Result<Vec<u8>> {
+    let hex: Vec<u8> = data
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .copied()
+        .collect();
+
+    let mut out = Vec::with_capacity(hex.len() / 2);
+    for pair in hex.chunks(2) {
+        let s = String::from_utf8_lossy(pair);
+        match pair.iter().all(u8::is_ascii_hexdigit) && pair.len() == 2 {
+            true => out.push(u8::from_str_radix(&s, 16).unwrap()),
+            false => err_at!(FailConvert, msg: "invalid hex input {:?}", s)?,
+        }
+    }
+    Ok(out)
+}
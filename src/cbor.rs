@@ -7,21 +7,77 @@
 //!
 //! [cbor]: https://tools.ietf.org/html/rfc7049
 
-use arbitrary::{self, Arbitrary, Unstructured};
+use arbitrary::{self, Unstructured};
 
 use crate::{Error, Result};
 
 #[cfg(unix)]
-use std::os::unix::ffi::OsStringExt;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
 #[cfg(windows)]
 use std::os::windows::ffi::OsStringExt;
 use std::{
+    borrow::Cow,
     cmp,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     convert::{TryFrom, TryInto},
-    ffi, io,
+    ffi,
+    hash::{BuildHasher, Hash},
+    io, mem, path,
+    rc::Rc,
+    result,
     sync::Arc,
+    time,
 };
 
+#[path = "cbor_codec.rs"]
+mod cbor_codec;
+#[path = "cbor_decoder.rs"]
+mod cbor_decoder;
+#[path = "cbor_diag.rs"]
+mod cbor_diag;
+#[path = "cbor_json.rs"]
+mod cbor_json;
+#[path = "cbor_lexer.rs"]
+mod cbor_lexer;
+#[path = "cbor_path.rs"]
+mod cbor_path;
+#[path = "cbor_reader.rs"]
+mod cbor_reader;
+#[path = "cbor_ref.rs"]
+mod cbor_ref;
+#[path = "cbor_seq.rs"]
+mod cbor_seq;
+#[cfg(feature = "serde")]
+#[path = "cbor_serde.rs"]
+mod cbor_serde;
+#[path = "cbor_share.rs"]
+mod cbor_share;
+#[path = "cbor_stringref.rs"]
+mod cbor_stringref;
+#[path = "cbor_tags.rs"]
+mod cbor_tags;
+#[path = "cbor_writer.rs"]
+mod cbor_writer;
+
+pub use crate::codec_by_tree;
+pub use cbor_codec::{CborDecode, CborEncode};
+pub use cbor_decoder::{CborDecoder, Progress};
+pub use cbor_json::{JsonOptions, TagMode};
+pub use cbor_path::PathSeg;
+pub use cbor_reader::{CborReader, Event};
+pub use cbor_ref::{CborRef, FromCborRef, TagRef};
+pub use cbor_seq::{CborSeqReader, CborSeqWriter, SeqItem, SeqItems};
+#[cfg(feature = "serde")]
+pub use cbor_serde::{from_cbor, to_cbor, Serde};
+pub use cbor_share::{from_cbor_shared, to_cbor_shared};
+pub use cbor_tags::{
+    BigFloat, BigNum, DateTime, Decimal, Embedded, EpochTime, Mantissa, SelfDescribe,
+    Uri, Uuid, TAG_BIGFLOAT, TAG_DATETIME, TAG_DECIMAL, TAG_EMBEDDED, TAG_EPOCH_TIME,
+    TAG_IPV4, TAG_IPV6, TAG_NEG_BIGNUM, TAG_POS_BIGNUM, TAG_SELF_DESCRIBE, TAG_SHAREABLE,
+    TAG_SHAREDREF, TAG_STRINGREF, TAG_STRINGREF_NAMESPACE, TAG_URI, TAG_UUID,
+};
+pub use cbor_writer::CborWriter;
+
 macro_rules! read_r {
     ($r:ident, $buf:expr) => {
         err_at!(IOError, $r.read_exact($buf))?
@@ -37,12 +93,53 @@ macro_rules! write_w {
 /// Convert rust-native value to [Cbor], which can then be encoded into bytes
 /// using Cbor.
 ///
-/// Refer to [FromCbor] the reverse transformation of a type to [Cbor] value.
+/// Refer to [FromCbor] the reverse transformation of a type to [Cbor] value,
+/// and [ToCbor] to convert without consuming the value.
 pub trait IntoCbor {
     /// Convert implementing type's value into [Cbor].
     fn into_cbor(self) -> Result<Cbor>;
 }
 
+/// Convert rust-native value to [Cbor] by reference, without consuming the
+/// value, like when encoding values held behind a shared reference.
+///
+/// All types implementing ToCbor are convertible by reference, that is,
+/// `&T` implements [IntoCbor].
+pub trait ToCbor {
+    /// Convert implementing type's value into [Cbor].
+    fn to_cbor(&self) -> Result<Cbor>;
+}
+
+impl<T> ToCbor for &T
+where
+    T: ToCbor + ?Sized,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        (**self).to_cbor()
+    }
+}
+
+impl<T> IntoCbor for &T
+where
+    T: ToCbor + ?Sized,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        self.to_cbor()
+    }
+}
+
+// Implement IntoCbor for types that are as cheap to convert by reference.
+macro_rules! into_cbor_by_ref {
+    ($($t:ty),* $(,)?) => {$(
+        impl IntoCbor for $t {
+            fn into_cbor(self) -> Result<Cbor> {
+                self.to_cbor()
+            }
+        }
+    )*}
+}
+pub(crate) use into_cbor_by_ref;
+
 /// Convert from Cbor, the cbor value is typically obtained by
 /// decoding it from bytes.
 ///
@@ -54,20 +151,140 @@ pub trait FromCbor: Sized {
 
 impl<T> FromCbor for Arc<T>
 where
-    T: FromCbor,
+    T: FromCbor + 'static,
 {
     fn from_cbor(val: Cbor) -> Result<Self> {
-        T::from_cbor(val).map(Arc::new)
+        cbor_share::arc_from_cbor(val)
     }
 }
 
 /// Recursion limit for nested Cbor objects.
 pub const RECURSION_LIMIT: u32 = 1000;
 
+/// Options to decode CBOR data, refer to [Cbor::decode_with].
+///
+/// Limits are meant for decoding input from untrusted sources, exceeding
+/// any of them fails the decoding with [Error::FailCbor]. Default options
+/// do not limit the input, other than the [RECURSION_LIMIT].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DecodeOptions {
+    /// Maximum number of bytes to read for the top level value.
+    pub max_size: usize,
+    /// Maximum length of byte-string or text-string, in bytes.
+    pub max_string: usize,
+    /// Maximum number of items in a list, or entries in a map.
+    pub max_items: usize,
+    /// Maximum nesting depth, top level value is at depth 1.
+    pub max_depth: u32,
+    /// Refer to [Cbor::decode_strict].
+    pub strict: bool,
+    /// Refer to [Cbor::decode_lazy]. Containers captured as [Cbor::Binary]
+    /// are only bounded by `max_size`, unless `strict` is also enabled.
+    pub lazy: Option<u32>,
+    /// Resolve string references into strings, and unwrap their namespace,
+    /// refer to [Cbor::into_stringref]. Containers within a namespace are
+    /// decoded in full, irrespective of `lazy`.
+    pub stringref: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> DecodeOptions {
+        DecodeOptions {
+            max_size: usize::MAX,
+            max_string: usize::MAX,
+            max_items: usize::MAX,
+            max_depth: RECURSION_LIMIT,
+            strict: false,
+            lazy: None,
+            stringref: false,
+        }
+    }
+}
+
+// Pre-allocation limit, while reading byte-string and text-string.
+const STRING_CHUNK: usize = 64 * 1024;
+
+// Decoder state, for a single top level value.
+struct Ctx {
+    opts: DecodeOptions,
+    // number of bytes read so far.
+    n: usize,
+    // string tables of enclosing stringref namespaces, innermost last.
+    strings: Vec<Vec<Cbor>>,
+}
+
+impl Ctx {
+    fn consume(&mut self, n: usize) -> Result<()> {
+        self.n = self.n.saturating_add(n);
+        if self.n > self.opts.max_size {
+            let limit = self.opts.max_size;
+            err_at!(FailCbor, msg: "decode max_size {} exceeded: {}", limit, self.n)?
+        }
+        Ok(())
+    }
+
+    fn check_string(&self, len: usize) -> Result<()> {
+        if len > self.opts.max_string {
+            let limit = self.opts.max_string;
+            err_at!(FailCbor, msg: "decode max_string {} exceeded: {}", limit, len)?
+        }
+        Ok(())
+    }
+
+    fn check_items(&self, len: u64) -> Result<()> {
+        match usize::try_from(len) {
+            Ok(len) if len <= self.opts.max_items => Ok(()),
+            _ => {
+                let limit = self.opts.max_items;
+                err_at!(FailCbor, msg: "decode max_items {} exceeded: {}", limit, len)
+            }
+        }
+    }
+
+    // in strict mode, additional info must be the shortest form.
+    fn decode_addnl<R>(
+        &mut self,
+        major: u8,
+        info: Info,
+        r: &mut R,
+    ) -> Result<(u64, usize)>
+    where
+        R: io::Read,
+    {
+        let (val, m) = decode_addnl(info, r)?;
+        self.consume(m)?;
+        if self.opts.strict && Info::from(val) != info {
+            err_at!(FailCbor, msg: "strict: {:?} for {} major {}", info, val, major)?
+        }
+        Ok((val, m))
+    }
+
+    // read `len` bytes of string, without trusting `len` for pre-allocation.
+    fn read_string<R>(&mut self, len: u64, r: &mut R) -> Result<Vec<u8>>
+    where
+        R: io::Read,
+    {
+        let n: usize = err_at!(FailConvert, usize::try_from(len))?;
+        self.check_string(n)?;
+        self.consume(n)?;
+
+        let mut data = Vec::with_capacity(cmp::min(n, STRING_CHUNK));
+        let m = err_at!(
+            IOError,
+            io::Read::read_to_end(&mut io::Read::take(r, len), &mut data)
+        )?;
+        if m != n {
+            err_at!(IOError, msg: "insufficient bytes {}/{}", m, n)?
+        }
+        Ok(data)
+    }
+}
+
 /// Cbor type enumerated over its major variants.
 ///
 /// Use one of the conversion trait to convert language-native-type to a
-/// Cbor variant. For lazy decoding, use [Cbor::Binary] variant.
+/// Cbor variant. For lazy decoding, use [Cbor::Binary] variant. Values
+/// are displayed in CBOR diagnostic notation, refer [Cbor::to_diagnostic].
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Cbor {
     Major0(Info, u64),              // uint 0-23,24,25,26,27
@@ -166,6 +383,15 @@ impl Cbor {
                 let n = encode_hdr(major, *info, w)?;
                 n + encode_addnl(*num, w)?
             }
+            Cbor::Major2(Info::Indefinite, byts)
+            | Cbor::Major3(Info::Indefinite, byts) => {
+                // single definite chunk, followed by break stop.
+                let n = encode_hdr(major, Info::Indefinite, w)?;
+                let len = err_at!(FailConvert, u64::try_from(byts.len()))?;
+                let m = encode_hdr(major, len.into(), w)? + encode_addnl(len, w)?;
+                write_w!(w, byts);
+                n + m + byts.len() + encode_hdr(7, Info::Indefinite, w)?
+            }
             Cbor::Major2(info, byts) => {
                 let n = encode_hdr(major, *info, w)?;
                 let m =
@@ -179,6 +405,14 @@ impl Cbor {
                 write_w!(w, text);
                 n + m + text.len()
             }
+            Cbor::Major4(Info::Indefinite, list) => {
+                let n = encode_hdr(major, Info::Indefinite, w)?;
+                let mut acc = 0;
+                for x in list.iter() {
+                    acc += x.do_encode(w, depth + 1)?;
+                }
+                n + acc + encode_hdr(7, Info::Indefinite, w)?
+            }
             Cbor::Major4(info, list) => {
                 let n = encode_hdr(major, *info, w)?;
                 let m =
@@ -189,6 +423,16 @@ impl Cbor {
                 }
                 n + m + acc
             }
+            Cbor::Major5(Info::Indefinite, map) => {
+                let n = encode_hdr(major, Info::Indefinite, w)?;
+                let mut acc = 0;
+                for (key, val) in map.iter() {
+                    let key = key.clone().into_cbor()?;
+                    acc += key.do_encode(w, depth + 1)?;
+                    acc += val.do_encode(w, depth + 1)?;
+                }
+                n + acc + encode_hdr(7, Info::Indefinite, w)?
+            }
             Cbor::Major5(info, map) => {
                 let n = encode_hdr(major, *info, w)?;
                 let m = encode_addnl(err_at!(FailConvert, u64::try_from(map.len()))?, w)?;
@@ -202,7 +446,7 @@ impl Cbor {
             }
             Cbor::Major6(info, tag) => {
                 let n = encode_hdr(major, *info, w)?;
-                let m = Tag::encode(tag, w)?;
+                let m = Tag::encode(tag, w, depth)?;
                 n + m
             }
             Cbor::Major7(info, sval) => {
@@ -219,94 +463,325 @@ impl Cbor {
         Ok(n)
     }
 
+    /// Serialize this cbor value as per the core deterministic encoding
+    /// requirements of [RFC 8949][rfc]. Integers, lengths and tags are
+    /// encoded in their shortest form, strings and containers are encoded
+    /// with definite length, map entries are sorted by the bytewise encoding
+    /// of their keys and floating point values are encoded in their shortest
+    /// lossless form, NaN is encoded as half-precision `0x7e00`.
+    ///
+    /// It is an error to have duplicate keys in a map. [Cbor::Binary] values
+    /// are decoded and re-encoded. Refer to [Cbor::decode_strict] for the
+    /// decoder.
+    ///
+    /// [rfc]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.2.1
+    pub fn encode_canonical<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        self.do_encode_canonical(w, 1)
+    }
+
+    fn do_encode_canonical<W>(&self, w: &mut W, depth: u32) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        if depth > RECURSION_LIMIT {
+            return err_at!(FailCbor, msg: "encode recursion limit exceeded");
+        }
+
+        let major = self.to_major_val();
+        let n = match self {
+            Cbor::Major0(_, num) | Cbor::Major1(_, num) => {
+                encode_hdr(major, (*num).into(), w)? + encode_addnl(*num, w)?
+            }
+            Cbor::Major2(_, data) | Cbor::Major3(_, data) => {
+                let len = err_at!(FailConvert, u64::try_from(data.len()))?;
+                let n = encode_hdr(major, len.into(), w)? + encode_addnl(len, w)?;
+                write_w!(w, data);
+                n + data.len()
+            }
+            Cbor::Major4(_, list) => {
+                let len = err_at!(FailConvert, u64::try_from(list.len()))?;
+                let mut n = encode_hdr(major, len.into(), w)? + encode_addnl(len, w)?;
+                for x in list.iter() {
+                    n += x.do_encode_canonical(w, depth + 1)?;
+                }
+                n
+            }
+            Cbor::Major5(_, map) => {
+                let mut entries: Vec<(Vec<u8>, &Cbor)> = Vec::with_capacity(map.len());
+                for (key, val) in map.iter() {
+                    let mut buf: Vec<u8> = vec![];
+                    key.clone()
+                        .into_cbor()?
+                        .do_encode_canonical(&mut buf, depth + 1)?;
+                    entries.push((buf, val));
+                }
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                if entries.windows(2).any(|x| x[0].0 == x[1].0) {
+                    err_at!(FailCbor, msg: "duplicate map key")?
+                }
+
+                let len = err_at!(FailConvert, u64::try_from(entries.len()))?;
+                let mut n = encode_hdr(major, len.into(), w)? + encode_addnl(len, w)?;
+                for (key, val) in entries.iter() {
+                    write_w!(w, key);
+                    n += key.len() + val.do_encode_canonical(w, depth + 1)?;
+                }
+                n
+            }
+            Cbor::Major6(_, tag) => {
+                let num = tag.to_tag_value();
+                let n = encode_hdr(major, num.into(), w)? + encode_addnl(num, w)?;
+                n + tag.as_item().do_encode_canonical(w, depth + 1)?
+            }
+            Cbor::Major7(_, sval) => {
+                let sval = sval.to_canonical()?;
+                let info = match sval.into_cbor()? {
+                    Cbor::Major7(info, _) => info,
+                    _ => unreachable!(),
+                };
+                encode_hdr(major, info, w)? + SimpleValue::encode(&sval, w)?
+            }
+            Cbor::Binary(data) => {
+                let (val, _) = Cbor::decode(&mut data.as_slice())?;
+                val.do_encode_canonical(w, depth)?
+            }
+        };
+
+        Ok(n)
+    }
+
     /// Deserialize bytes from reader `r` to Cbor value, return the cbor value
     /// and number of bytes read to construct the value.
+    ///
+    /// Length prefixes in the input are trusted as is, use
+    /// [Cbor::decode_with] to decode input from untrusted sources.
     pub fn decode<R>(r: &mut R) -> Result<(Cbor, usize)>
     where
         R: io::Read,
     {
-        Cbor::do_decode(r, 1)
+        Cbor::decode_with(r, DecodeOptions::default())
+    }
+
+    /// Same as [Cbor::decode], but reject input that is not encoded as per
+    /// the core deterministic encoding requirements of [RFC 8949][rfc].
+    /// That is, integers, lengths and tags in their shortest form, definite
+    /// length strings and containers, map keys sorted by their bytewise
+    /// encoding without duplicates, floating point values in their shortest
+    /// lossless form and valid UTF-8 for text strings.
+    ///
+    /// Refer to [Cbor::encode_canonical] for the encoder.
+    ///
+    /// [rfc]: https://www.rfc-editor.org/rfc/rfc8949.html#section-4.2.1
+    pub fn decode_strict<R>(r: &mut R) -> Result<(Cbor, usize)>
+    where
+        R: io::Read,
+    {
+        let opts = DecodeOptions {
+            strict: true,
+            ..DecodeOptions::default()
+        };
+        Cbor::decode_with(r, opts)
+    }
+
+    /// Same as [Cbor::decode], but list and map values nested below `depth`
+    /// are not decoded, instead their encoded bytes are returned as
+    /// [Cbor::Binary] values. Top level value is at depth 1, hence with
+    /// `depth` as 1, items of the top level list or map are returned as is
+    /// if they are scalars, and as [Cbor::Binary] if they are containers.
+    ///
+    /// Use [Cbor::expand] to decode [Cbor::Binary] values on demand, and
+    /// [Cbor::encode] writes them through as-is.
+    pub fn decode_lazy<R>(r: &mut R, depth: u32) -> Result<(Cbor, usize)>
+    where
+        R: io::Read,
+    {
+        let opts = DecodeOptions {
+            lazy: Some(depth),
+            ..DecodeOptions::default()
+        };
+        Cbor::decode_with(r, opts)
+    }
+
+    /// Same as [Cbor::decode], with limits and decoding modes as per `opts`.
+    pub fn decode_with<R>(r: &mut R, opts: DecodeOptions) -> Result<(Cbor, usize)>
+    where
+        R: io::Read,
+    {
+        let mut ctx = Ctx {
+            opts,
+            n: 0,
+            strings: Vec::default(),
+        };
+        Cbor::do_decode(r, 1, &mut ctx)
+    }
+
+    /// Decode [Cbor::Binary] value, return other values as is. It is an error
+    /// to have trailing bytes after the encoded value.
+    pub fn expand(self) -> Result<Cbor> {
+        match self {
+            Cbor::Binary(data) => {
+                let (val, n) = Cbor::decode(&mut data.as_slice())?;
+                if n != data.len() {
+                    err_at!(FailCbor, msg: "trailing bytes {}/{}", n, data.len())?
+                }
+                Ok(val)
+            }
+            val => Ok(val),
+        }
     }
 
-    fn do_decode<R>(r: &mut R, depth: u32) -> Result<(Cbor, usize)>
+    fn do_decode<R>(r: &mut R, depth: u32, ctx: &mut Ctx) -> Result<(Cbor, usize)>
     where
         R: io::Read,
     {
         if depth > RECURSION_LIMIT {
             return err_at!(FailCbor, msg: "decode recursion limt exceeded");
+        } else if depth > ctx.opts.max_depth {
+            let limit = ctx.opts.max_depth;
+            return err_at!(FailCbor, msg: "decode max_depth {} exceeded: {}", limit, depth);
         }
 
         let (major, info, n) = decode_hdr(r)?;
+        ctx.consume(n)?;
+
+        let strict = ctx.opts.strict;
+        match (major, ctx.opts.lazy) {
+            // strings within a stringref namespace are decoded in order.
+            (4 | 5, Some(lazy)) if depth > lazy && ctx.strings.is_empty() => {
+                // replay the header and record the container as is.
+                let mut hdr: Vec<u8> = Vec::with_capacity(1);
+                encode_hdr(major, info, &mut hdr)?;
+                let (m, data) = if strict {
+                    ctx.n -= n;
+                    ctx.opts.lazy = None;
+                    let mut rr = Recorder {
+                        r: &mut io::Read::chain(hdr.as_slice(), r),
+                        buf: Vec::default(),
+                    };
+                    let res = Cbor::do_decode(&mut rr, depth, ctx);
+                    ctx.opts.lazy = Some(lazy);
+                    (res?.1, rr.buf)
+                } else {
+                    // skipped bytes are only bounded by max_size.
+                    let limit = ctx.opts.max_size.saturating_sub(ctx.n);
+                    let limit = err_at!(FailConvert, u64::try_from(limit))?;
+                    let mut lr = io::Read::take(r, limit.saturating_add(1));
+                    let mut rr = Recorder {
+                        r: &mut io::Read::chain(hdr.as_slice(), &mut lr),
+                        buf: Vec::default(),
+                    };
+                    match Cbor::do_skip(&mut rr, depth) {
+                        Ok((m, _)) => {
+                            let data = rr.buf;
+                            ctx.consume(m - n)?;
+                            (m, data)
+                        }
+                        // reading stopped one byte past the limit.
+                        Err(_) if lr.limit() == 0 => {
+                            let limit = ctx.opts.max_size;
+                            let len = limit.saturating_add(1);
+                            err_at!(
+                                FailCbor, msg: "decode max_size {} exceeded: {}", limit, len
+                            )?
+                        }
+                        Err(err) => return Err(err),
+                    }
+                };
+                return Ok((Cbor::Binary(data), m));
+            }
+            _ => (),
+        }
+
+        if strict && major != 7 && info == Info::Indefinite {
+            err_at!(FailCbor, msg: "strict: indefinite encoding for major {}", major)?
+        }
 
         let (val, m) = match (major, info) {
             (0, info) => {
-                let (val, m) = decode_addnl(info, r)?;
+                let (val, m) = ctx.decode_addnl(major, info, r)?;
                 (Cbor::Major0(info, val), m)
             }
             (1, info) => {
-                let (val, m) = decode_addnl(info, r)?;
+                let (val, m) = ctx.decode_addnl(major, info, r)?;
                 (Cbor::Major1(info, val), m)
             }
             (2, Info::Indefinite) => {
+                // chunks are not referable as strings.
+                let strings = mem::take(&mut ctx.strings);
                 let mut data: Vec<u8> = Vec::default();
                 let mut m = 0_usize;
                 loop {
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
+                    m += k;
                     match val {
                         Cbor::Major2(_, chunk) => data.extend_from_slice(&chunk),
                         Cbor::Major7(_, SimpleValue::Break) => break,
                         _ => err_at!(FailConvert, msg: "expected byte chunk")?,
                     }
-                    m += k;
+                    ctx.check_string(data.len())?;
                 }
+                ctx.strings = strings;
                 (Cbor::Major2(info, data), m)
             }
             (2, info) => {
-                let (val, m) = decode_addnl(info, r)?;
-                let len: usize = err_at!(FailConvert, val.try_into())?;
-                let mut data = vec![0; len];
-                read_r!(r, &mut data);
-                (Cbor::Major2(info, data), m + len)
+                let (val, m) = ctx.decode_addnl(major, info, r)?;
+                let data = ctx.read_string(val, r)?;
+                let len = data.len();
+                let val = Cbor::Major2(info, data);
+                ctx.add_string(&val);
+                (val, m + len)
             }
             (3, Info::Indefinite) => {
+                let strings = mem::take(&mut ctx.strings);
                 let mut text: Vec<u8> = Vec::default();
                 let mut m = 0_usize;
                 loop {
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
+                    m += k;
                     match val {
                         Cbor::Major3(_, chunk) => text.extend_from_slice(&chunk),
                         Cbor::Major7(_, SimpleValue::Break) => break,
-                        _ => err_at!(FailConvert, msg: "expected byte chunk")?,
+                        _ => err_at!(FailConvert, msg: "expected text chunk")?,
                     }
-                    m += k;
+                    ctx.check_string(text.len())?;
                 }
+                ctx.strings = strings;
                 (Cbor::Major3(info, text), m)
             }
             (3, info) => {
-                let (val, m) = decode_addnl(info, r)?;
-                let len: usize = err_at!(FailConvert, val.try_into())?;
-                let mut text = vec![0; len];
-                read_r!(r, &mut text);
-                (Cbor::Major3(info, text), m + len)
+                let (val, m) = ctx.decode_addnl(major, info, r)?;
+                let text = ctx.read_string(val, r)?;
+                if strict {
+                    err_at!(FailCbor, std::str::from_utf8(&text), "strict")?;
+                }
+                let len = text.len();
+                let val = Cbor::Major3(info, text);
+                ctx.add_string(&val);
+                (val, m + len)
             }
             (4, Info::Indefinite) => {
                 let mut list: Vec<Cbor> = vec![];
                 let mut m = 0_usize;
                 loop {
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
+                    m += k;
                     match val {
                         Cbor::Major7(_, SimpleValue::Break) => break,
                         item => list.push(item),
                     }
-                    m += k;
+                    ctx.check_items(list.len() as u64)?;
                 }
                 (Cbor::Major4(info, list), m)
             }
             (4, info) => {
                 let mut list: Vec<Cbor> = vec![];
-                let (len, mut m) = decode_addnl(info, r)?;
+                let (len, mut m) = ctx.decode_addnl(major, info, r)?;
+                ctx.check_items(len)?;
                 for _ in 0..len {
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
                     list.push(val);
                     m += k;
                 }
@@ -316,34 +791,68 @@ impl Cbor {
                 let mut map: Vec<(Key, Cbor)> = Vec::default();
                 let mut m = 0_usize;
                 loop {
-                    let (key, j) = Cbor::do_decode(r, depth + 1)?;
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
-                    let val = match val {
-                        Cbor::Major7(_, SimpleValue::Break) => break,
-                        val => val,
-                    };
+                    let (key, j) = Cbor::do_decode(r, depth + 1, ctx)?;
+                    if let Cbor::Major7(_, SimpleValue::Break) = key {
+                        m += j;
+                        break;
+                    }
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
                     map.push((Key::from_cbor(key)?, val));
                     m += j + k;
+                    ctx.check_items(map.len() as u64)?;
                 }
                 (Cbor::Major5(info, map), m)
             }
             (5, info) => {
                 let mut map: Vec<(Key, Cbor)> = Vec::default();
-                let (len, mut m) = decode_addnl(info, r)?;
+                let (len, mut m) = ctx.decode_addnl(major, info, r)?;
+                ctx.check_items(len)?;
+                let mut prev: Option<Vec<u8>> = None;
                 for _ in 0..len {
-                    let (key, j) = Cbor::do_decode(r, depth + 1)?;
-                    let (val, k) = Cbor::do_decode(r, depth + 1)?;
+                    let (key, j) = Cbor::do_decode(r, depth + 1, ctx)?;
+                    if strict {
+                        // strictly decoded key re-encodes to its input bytes.
+                        let mut buf: Vec<u8> = vec![];
+                        key.encode_canonical(&mut buf)?;
+                        match prev.as_ref().map(|prev| prev.as_slice().cmp(&buf)) {
+                            Some(cmp::Ordering::Equal) => {
+                                err_at!(FailCbor, msg: "strict: duplicate map key")?
+                            }
+                            Some(cmp::Ordering::Greater) => {
+                                err_at!(FailCbor, msg: "strict: map keys not sorted")?
+                            }
+                            _ => prev = Some(buf),
+                        }
+                    }
+                    let (val, k) = Cbor::do_decode(r, depth + 1, ctx)?;
                     map.push((Key::from_cbor(key)?, val));
                     m += j + k;
                 }
                 (Cbor::Major5(info, map), m)
             }
-            (6, info) => {
-                let (tag, m) = Tag::decode(info, r)?;
-                (Cbor::Major6(info, tag), m)
-            }
+            (6, info) => match Tag::decode(info, r, depth, ctx)? {
+                // string references are resolved, refer to Cbor::into_stringref.
+                (Tag::Value(TAG_STRINGREF_NAMESPACE, item), m) if ctx.opts.stringref => {
+                    (*item, m)
+                }
+                (Tag::Value(TAG_STRINGREF, item), m) if !ctx.strings.is_empty() => {
+                    (ctx.stringref(*item)?, m)
+                }
+                (tag, m) => (Cbor::Major6(info, tag), m),
+            },
             (7, info) => {
                 let (sval, m) = SimpleValue::decode(info, r)?;
+                ctx.consume(m)?;
+                match sval {
+                    // no indefinite items in strict mode, break is out of place.
+                    SimpleValue::Break if strict => {
+                        err_at!(FailCbor, msg: "strict: break outside indefinite item")?
+                    }
+                    _ if strict && !sval.is_canonical() => {
+                        err_at!(FailCbor, msg: "strict: non-canonical {:?}", sval)?
+                    }
+                    _ => (),
+                }
                 (Cbor::Major7(info, sval), m)
             }
             _ => unreachable!(),
@@ -352,6 +861,152 @@ impl Cbor {
         Ok((val, (m + n)))
     }
 
+    /// Advance reader `r` past one complete data item, without decoding it
+    /// into memory, return the number of bytes skipped. Handles indefinite
+    /// encoding and nested tags, and enforces the same [RECURSION_LIMIT] as
+    /// [Cbor::decode].
+    pub fn skip<R>(r: &mut R) -> Result<usize>
+    where
+        R: io::Read,
+    {
+        match Cbor::do_skip(r, 1)? {
+            (_, true) => err_at!(FailCbor, msg: "unexpected break"),
+            (n, false) => Ok(n),
+        }
+    }
+
+    // return the number of bytes skipped, and whether the item is break stop.
+    fn do_skip<R>(r: &mut R, depth: u32) -> Result<(usize, bool)>
+    where
+        R: io::Read,
+    {
+        if depth > RECURSION_LIMIT {
+            return err_at!(FailCbor, msg: "decode recursion limt exceeded");
+        }
+
+        let (major, info, n) = decode_hdr(r)?;
+        let m = match (major, info) {
+            (7, Info::Indefinite) => return Ok((n, true)),
+            (0, info) | (1, info) => decode_addnl(info, r)?.1,
+            (2, Info::Indefinite) | (3, Info::Indefinite) => {
+                let mut m = 0_usize;
+                loop {
+                    match decode_hdr(r)? {
+                        (7, Info::Indefinite, k) => break m + k,
+                        (mj, Info::Indefinite, _) if mj == major => {
+                            err_at!(FailCbor, msg: "nested chunk")?
+                        }
+                        (mj, info, k) if mj == major => m += k + skip_string(info, r)?,
+                        _ => err_at!(FailConvert, msg: "expected chunk")?,
+                    }
+                }
+            }
+            (2, info) | (3, info) => skip_string(info, r)?,
+            (4, Info::Indefinite) => {
+                let mut m = 0_usize;
+                loop {
+                    let (k, brk) = Cbor::do_skip(r, depth + 1)?;
+                    m += k;
+                    if brk {
+                        break m;
+                    }
+                }
+            }
+            (5, Info::Indefinite) => {
+                let mut m = 0_usize;
+                loop {
+                    let (j, brk) = Cbor::do_skip(r, depth + 1)?;
+                    m += j;
+                    if brk {
+                        break m;
+                    }
+                    match Cbor::do_skip(r, depth + 1)? {
+                        (_, true) => err_at!(FailCbor, msg: "break after map key")?,
+                        (k, false) => m += k,
+                    }
+                }
+            }
+            (4, info) | (5, info) => {
+                let (len, mut m) = decode_addnl(info, r)?;
+                let arity = if major == 5 { 2 } else { 1 };
+                for _ in 0..len {
+                    for _ in 0..arity {
+                        match Cbor::do_skip(r, depth + 1)? {
+                            (_, true) => err_at!(FailCbor, msg: "unexpected break")?,
+                            (k, false) => m += k,
+                        }
+                    }
+                }
+                m
+            }
+            (6, info) => {
+                let (_, m) = decode_addnl(info, r)?;
+                match Cbor::do_skip(r, depth + 1)? {
+                    (_, true) => err_at!(FailCbor, msg: "break after tag")?,
+                    (k, false) => m + k,
+                }
+            }
+            (7, info) => SimpleValue::decode(info, r)?.1,
+            _ => unreachable!(),
+        };
+
+        Ok((n + m, false))
+    }
+
+    /// Return the number of bytes, this value shall be serialized into,
+    /// using [Cbor::encode].
+    pub fn encoded_len(&self) -> Result<usize> {
+        self.do_encoded_len(1)
+    }
+
+    fn do_encoded_len(&self, depth: u32) -> Result<usize> {
+        if depth > RECURSION_LIMIT {
+            return err_at!(FailCbor, msg: "encode recursion limit exceeded");
+        }
+
+        let n = match self {
+            Cbor::Major0(_, num) | Cbor::Major1(_, num) => 1 + addnl_len(*num),
+            Cbor::Major2(Info::Indefinite, byts)
+            | Cbor::Major3(Info::Indefinite, byts) => {
+                // single definite chunk, followed by break stop.
+                let len = err_at!(FailConvert, u64::try_from(byts.len()))?;
+                1 + 1 + addnl_len(len) + byts.len() + 1
+            }
+            Cbor::Major2(_, byts) | Cbor::Major3(_, byts) => {
+                let len = err_at!(FailConvert, u64::try_from(byts.len()))?;
+                1 + addnl_len(len) + byts.len()
+            }
+            Cbor::Major4(info, list) => {
+                let mut n = match info {
+                    Info::Indefinite => 2,
+                    _ => 1 + addnl_len(err_at!(FailConvert, u64::try_from(list.len()))?),
+                };
+                for x in list.iter() {
+                    n += x.do_encoded_len(depth + 1)?;
+                }
+                n
+            }
+            Cbor::Major5(info, map) => {
+                let mut n = match info {
+                    Info::Indefinite => 2,
+                    _ => 1 + addnl_len(err_at!(FailConvert, u64::try_from(map.len()))?),
+                };
+                for (key, val) in map.iter() {
+                    n += key.encoded_len()? + val.do_encoded_len(depth + 1)?;
+                }
+                n
+            }
+            Cbor::Major6(_, tag) => {
+                let n = 1 + addnl_len(tag.to_tag_value());
+                n + tag.as_item().do_encoded_len(depth + 1)?
+            }
+            Cbor::Major7(_, sval) => 1 + sval.encoded_len(),
+            Cbor::Binary(data) => data.len(),
+        };
+
+        Ok(n)
+    }
+
     fn to_major_val(&self) -> u8 {
         match self {
             Cbor::Major0(_, _) => 0,
@@ -387,6 +1042,37 @@ impl Cbor {
     }
 }
 
+// Sort map entries in the order of Cbor::encode_canonical, that is, by the
+// bytewise encoding of their keys.
+fn sort_canonical(map: &mut Vec<(Key, Cbor)>) -> Result<()> {
+    let mut keys: Vec<Vec<u8>> = Vec::with_capacity(map.len());
+    for (key, _) in map.iter() {
+        let mut buf: Vec<u8> = vec![];
+        key.clone().into_cbor()?.encode_canonical(&mut buf)?;
+        keys.push(buf);
+    }
+    let mut entries: Vec<(Vec<u8>, (Key, Cbor))> =
+        keys.into_iter().zip(map.drain(..)).collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    map.extend(entries.into_iter().map(|(_, entry)| entry));
+    Ok(())
+}
+
+// Reader adapter that records all the bytes read from `r`. Reader is type
+// erased, to bound the recursive instantiation of `Cbor::do_decode`.
+struct Recorder<'a> {
+    r: &'a mut dyn io::Read,
+    buf: Vec<u8>,
+}
+
+impl<'a> io::Read for Recorder<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.r.read(buf)?;
+        self.buf.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
 /// 5-bit value for additional info. Refer to Cbor [spec] for details.
 ///
 /// [spec]: https://tools.ietf.org/html/rfc7049
@@ -529,13 +1215,40 @@ where
     Ok(n)
 }
 
-fn decode_addnl<R>(info: Info, r: &mut R) -> Result<(u64, usize)>
+// number of bytes, additional info for `num` shall be serialized into.
+fn addnl_len(num: u64) -> usize {
+    match Info::from(num) {
+        Info::Tiny(_) => 0,
+        Info::U8 => 1,
+        Info::U16 => 2,
+        Info::U32 => 4,
+        _ => 8,
+    }
+}
+
+// skip the payload of definite length byte-string or text-string.
+fn skip_string<R>(info: Info, r: &mut R) -> Result<usize>
 where
     R: io::Read,
 {
-    let mut scratch = [0_u8; 8];
-    let (num, n) = match info {
-        Info::Tiny(num) => (num as u64, 0),
+    let (len, n) = decode_addnl(info, r)?;
+    let m = err_at!(
+        IOError,
+        io::copy(&mut io::Read::take(r, len), &mut io::sink())
+    )?;
+    if m != len {
+        err_at!(IOError, msg: "insufficient bytes {}/{}", m, len)?
+    }
+    Ok(n + err_at!(FailConvert, usize::try_from(len))?)
+}
+
+fn decode_addnl<R>(info: Info, r: &mut R) -> Result<(u64, usize)>
+where
+    R: io::Read,
+{
+    let mut scratch = [0_u8; 8];
+    let (num, n) = match info {
+        Info::Tiny(num) => (num as u64, 0),
         Info::U8 => {
             read_r!(r, &mut scratch[..1]);
             (
@@ -587,8 +1300,9 @@ pub enum SimpleValue {
     Undefined, // 23, tiny simple-value
     /// Reserved.
     Reserved24(u8), // 24, one-byte simple-value
-    /// 16-bit floating point.
-    F16(u16), // 25, not-implemented
+    /// 16-bit floating point, IEEE 754 binary16 bits. Refer to [f16_to_f32]
+    /// and [f32_to_f16] for conversions.
+    F16(u16), // 25, half-precision float
     /// 32-bit floating point.
     F32(f32), // 26, single-precision float
     /// 64-bit floating point.
@@ -599,6 +1313,7 @@ pub enum SimpleValue {
 
 impl arbitrary::Arbitrary for SimpleValue {
     fn arbitrary(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let f2 = u.arbitrary::<u16>()?;
         let f4 = u.arbitrary::<f32>()?;
         let f8 = u.arbitrary::<f64>()?;
 
@@ -606,6 +1321,7 @@ impl arbitrary::Arbitrary for SimpleValue {
             SimpleValue::True,
             SimpleValue::False,
             SimpleValue::Null,
+            SimpleValue::F16(f2),
             SimpleValue::F32(f4),
             SimpleValue::F64(f8),
         ])?)
@@ -661,18 +1377,18 @@ impl PartialEq for SimpleValue {
     }
 }
 
-impl IntoCbor for SimpleValue {
-    fn into_cbor(self) -> Result<Cbor> {
+impl ToCbor for SimpleValue {
+    fn to_cbor(&self) -> Result<Cbor> {
         use SimpleValue::*;
 
-        let val = match self {
+        let val = match *self {
             Unassigned => err_at!(FailConvert, msg: "simple-value-unassigned")?,
             val @ True => Cbor::Major7(Info::Tiny(20), val),
             val @ False => Cbor::Major7(Info::Tiny(21), val),
             val @ Null => Cbor::Major7(Info::Tiny(22), val),
             Undefined => err_at!(FailConvert, msg: "simple-value-undefined")?,
             Reserved24(_) => err_at!(FailConvert, msg: "simple-value-unassigned1")?,
-            F16(_) => err_at!(FailConvert, msg: "simple-value-f16")?,
+            val @ F16(_) => Cbor::Major7(Info::U16, val),
             val @ F32(_) => Cbor::Major7(Info::U32, val),
             val @ F64(_) => Cbor::Major7(Info::U64, val),
             val @ Break => Cbor::Major7(Info::Indefinite, val),
@@ -700,6 +1416,51 @@ impl SimpleValue {
         }
     }
 
+    // Return the shortest lossless form for floating point values, NaN is
+    // folded into half-precision quiet NaN. Break is not a data item.
+    fn to_canonical(self) -> Result<SimpleValue> {
+        use SimpleValue::*;
+
+        let val = match self {
+            F16(f) if f16_to_f64(f).is_nan() => F16(0x7e00),
+            F32(f) => F64(f64::from(f)).to_canonical()?,
+            F64(f) if f.is_nan() => F16(0x7e00),
+            F64(f) => {
+                let half = f64_to_f16(f);
+                if f16_to_f64(half).to_bits() == f.to_bits() {
+                    F16(half)
+                } else if f64::from(f as f32).to_bits() == f.to_bits() {
+                    F32(f as f32)
+                } else {
+                    F64(f)
+                }
+            }
+            Break => err_at!(FailCbor, msg: "break is not a data item")?,
+            val => val,
+        };
+        Ok(val)
+    }
+
+    fn is_canonical(&self) -> bool {
+        match self.to_canonical() {
+            Ok(val) => val == *self,
+            Err(_) => false,
+        }
+    }
+
+    // number of bytes following the header, refer to SimpleValue::encode.
+    fn encoded_len(&self) -> usize {
+        use SimpleValue::*;
+
+        match self {
+            True | False | Null | Undefined | Break | Unassigned => 0,
+            Reserved24(_) => 1,
+            F16(_) => 2,
+            F32(_) => 4,
+            F64(_) => 8,
+        }
+    }
+
     fn encode<W>(sval: &SimpleValue, w: &mut W) -> Result<usize>
     where
         W: io::Write,
@@ -742,7 +1503,11 @@ impl SimpleValue {
             Info::Tiny(23) => err_at!(FailCbor, msg: "simple-value-undefined")?,
             Info::Tiny(_) => err_at!(FailCbor, msg: "simple-value-unassigned")?,
             Info::U8 => err_at!(FailCbor, msg: "simple-value-unassigned1")?,
-            Info::U16 => err_at!(FailCbor, msg: "simple-value-f16")?,
+            Info::U16 => {
+                read_r!(r, &mut scratch[..2]);
+                let val = u16::from_be_bytes(scratch[..2].try_into().unwrap());
+                (SimpleValue::F16(val), 2)
+            }
             Info::U32 => {
                 read_r!(r, &mut scratch[..4]);
                 let val = f32::from_be_bytes(scratch[..4].try_into().unwrap());
@@ -762,18 +1527,99 @@ impl SimpleValue {
     }
 }
 
+/// Convert IEEE 754 binary16 value, in its bit representation, to f32.
+/// Conversion is lossless, including subnormals, infinities and NaN.
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = u32::from(half & 0x8000) << 16;
+    let (exp, mant) = ((half >> 10) & 0x1f, u32::from(half & 0x3ff));
+    match exp {
+        0 => {
+            // subnormal and zero, value is mant * 2^-24
+            let val = (mant as f32) * f32::from_bits(0x3380_0000);
+            f32::from_bits(val.to_bits() | sign)
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mant << 13)),
+        exp => {
+            let exp = u32::from(exp) + 127 - 15;
+            f32::from_bits(sign | (exp << 23) | (mant << 13))
+        }
+    }
+}
+
+/// Convert IEEE 754 binary16 value, in its bit representation, to f64.
+/// Conversion is lossless, including subnormals, infinities and NaN.
+pub fn f16_to_f64(half: u16) -> f64 {
+    let val = f16_to_f32(half);
+    match val.is_nan() {
+        true => {
+            let sign = u64::from(half & 0x8000) << 48;
+            f64::from_bits(sign | 0x7ff0_0000_0000_0000 | (u64::from(half & 0x3ff) << 42))
+        }
+        false => f64::from(val),
+    }
+}
+
+/// Convert f32 to IEEE 754 binary16 value, in its bit representation.
+/// Values are rounded to nearest, ties to even. Out of range values are
+/// converted to infinity.
+pub fn f32_to_f16(val: f32) -> u16 {
+    f64_to_f16(f64::from(val))
+}
+
+/// Convert f64 to IEEE 754 binary16 value, in its bit representation.
+/// Values are rounded to nearest, ties to even. Out of range values are
+/// converted to infinity.
+pub fn f64_to_f16(val: f64) -> u16 {
+    let bits = val.to_bits();
+    let sign = ((bits >> 48) & 0x8000) as u16;
+    let exp = ((bits >> 52) & 0x7ff) as i32;
+    let mant = bits & 0x000f_ffff_ffff_ffff;
+
+    // round `m` right-shifted by `shift` bits, to nearest even.
+    let round = |m: u64, shift: u32| -> u16 {
+        let (half, rem) = (m >> shift, m & ((1 << shift) - 1));
+        let halfway = 1 << (shift - 1);
+        match rem > halfway || (rem == halfway && (half & 1) == 1) {
+            true => (half + 1) as u16,
+            false => half as u16,
+        }
+    };
+
+    match exp - 1023 + 15 {
+        _ if exp == 0x7ff && mant == 0 => sign | 0x7c00,
+        _ if exp == 0x7ff => sign | 0x7e00 | ((mant >> 42) as u16 & 0x3ff),
+        e if e >= 0x1f => sign | 0x7c00,
+        e if e < -10 => sign,
+        // subnormal, carry on rounding shall promote it to normal.
+        e if e <= 0 => sign | round(mant | (1 << 52), (43 - e) as u32),
+        // normal, carry on rounding shall promote exponent or to infinity.
+        e => sign | (((e as u16) << 10) + round(mant, 42)),
+    }
+}
+
 /// Major type 6, Tag values. Refer to Cbor [spec] for details.
 ///
 /// [spec]: https://tools.ietf.org/html/rfc7049
-#[derive(Debug, Clone, Eq, PartialEq, Arbitrary)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Tag {
     /// Tag 39, used as identifier marker. This implementation shall
     /// treat them as literal values. Used by `Cborize` procedural
     /// macro to match values with types.
     Identifier(Box<Cbor>),
     /// Catch all tag-value, follows the generic Tag specification
-    /// for Cbor.
-    Value(u64),
+    /// for Cbor. Tag number is followed by the enclosed data item.
+    Value(u64, Box<Cbor>),
+}
+
+impl arbitrary::Arbitrary for Tag {
+    fn arbitrary(u: &mut Unstructured) -> arbitrary::Result<Self> {
+        let val = Box::new(u.arbitrary::<Cbor>()?);
+        let tag = match u.arbitrary::<u64>()? {
+            39 => Tag::Identifier(val),
+            num => Tag::Value(num, val),
+        };
+        Ok(tag)
+    }
 }
 
 impl From<Tag> for Cbor {
@@ -784,9 +1630,13 @@ impl From<Tag> for Cbor {
 }
 
 impl Tag {
-    /// Construct a Tag value from u64 type.
-    pub fn from_value(value: u64) -> Tag {
-        Tag::Value(value)
+    /// Construct a Tag value from u64 type, enclosing the data item `val`.
+    /// Tag number 39 is constructed as [Tag::Identifier].
+    pub fn from_value(value: u64, val: Cbor) -> Tag {
+        match value {
+            39 => Tag::Identifier(Box::new(val)),
+            value => Tag::Value(value, Box::new(val)),
+        }
     }
 
     /// Wrap value with Identifier tag.
@@ -798,37 +1648,52 @@ impl Tag {
     pub fn to_tag_value(&self) -> u64 {
         match self {
             Tag::Identifier(_) => 39,
-            Tag::Value(val) => *val,
+            Tag::Value(val, _) => *val,
+        }
+    }
+
+    /// Return the enclosed data item.
+    pub fn as_item(&self) -> &Cbor {
+        match self {
+            Tag::Identifier(val) => val,
+            Tag::Value(_, val) => val,
+        }
+    }
+
+    /// Convert into the enclosed data item.
+    pub fn into_item(self) -> Cbor {
+        match self {
+            Tag::Identifier(val) => *val,
+            Tag::Value(_, val) => *val,
         }
     }
 
-    fn encode<W>(tag: &Tag, w: &mut W) -> Result<usize>
+    fn encode<W>(tag: &Tag, w: &mut W, depth: u32) -> Result<usize>
     where
         W: io::Write,
     {
         let num = tag.to_tag_value();
-        let mut n = encode_addnl(num, w)?;
-        n += match tag {
-            Tag::Identifier(val) => val.encode(w)?,
-            Tag::Value(_) => 0,
-        };
-
-        Ok(n)
+        let n = encode_addnl(num, w)?;
+        Ok(n + tag.as_item().do_encode(w, depth + 1)?)
     }
 
-    fn decode<R>(info: Info, r: &mut R) -> Result<(Tag, usize)>
+    fn decode<R>(info: Info, r: &mut R, depth: u32, ctx: &mut Ctx) -> Result<(Tag, usize)>
     where
         R: io::Read,
     {
-        let (tag, n) = decode_addnl(info, r)?;
-        let (tag, m) = match tag {
-            39 => {
-                let (val, m) = Cbor::decode(r)?;
-                (Tag::Identifier(Box::new(val)), m)
-            }
-            val => (Tag::Value(val), 0),
-        };
-        Ok((tag, m + n))
+        let (num, n) = ctx.decode_addnl(6, info, r)?;
+        let namespace = ctx.opts.stringref && num == TAG_STRINGREF_NAMESPACE;
+        if namespace {
+            ctx.strings.push(Vec::default());
+        }
+        let (val, m) = Cbor::do_decode(r, depth + 1, ctx)?;
+        if namespace {
+            ctx.strings.pop();
+        }
+        if let Cbor::Major7(_, SimpleValue::Break) = val {
+            err_at!(FailCbor, msg: "break after tag {}", num)?
+        }
+        Ok((Tag::from_value(num, val), m + n))
     }
 }
 
@@ -885,6 +1750,32 @@ impl Key {
             Text(_) => 24,
         }
     }
+
+    // number of bytes, this key shall be serialized into.
+    fn encoded_len(&self) -> Result<usize> {
+        use Key::*;
+
+        let n = match self {
+            Bool(_) => 1,
+            N64(key) if *key >= 0 => {
+                err_at!(FailConvert, msg: "Key::N64({}) cannot be positive", key)?
+            }
+            N64(key) => {
+                1 + addnl_len(err_at!(FailConvert, u64::try_from(key.abs() - 1))?)
+            }
+            U64(key) => 1 + addnl_len(*key),
+            F32(_) => 5,
+            F64(_) => 9,
+            Bytes(key) => {
+                1 + addnl_len(err_at!(FailConvert, u64::try_from(key.len()))?) + key.len()
+            }
+            Text(key) => {
+                1 + addnl_len(err_at!(FailConvert, u64::try_from(key.len()))?) + key.len()
+            }
+        };
+
+        Ok(n)
+    }
 }
 
 impl Eq for Key {}
@@ -963,6 +1854,12 @@ impl IntoCbor for Key {
     }
 }
 
+impl ToCbor for Key {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.clone().into_cbor()
+    }
+}
+
 impl FromCbor for Key {
     fn from_cbor(val: Cbor) -> Result<Key> {
         use std::str::from_utf8;
@@ -978,6 +1875,7 @@ impl FromCbor for Key {
             }
             Cbor::Major7(_, SimpleValue::True) => Key::Bool(true),
             Cbor::Major7(_, SimpleValue::False) => Key::Bool(false),
+            Cbor::Major7(_, SimpleValue::F16(key)) => Key::F32(f16_to_f32(key)),
             Cbor::Major7(_, SimpleValue::F32(key)) => Key::F32(key),
             Cbor::Major7(_, SimpleValue::F64(key)) => Key::F64(key),
             _ => err_at!(FailCbor, msg: "cbor not a valid key")?,
@@ -987,15 +1885,29 @@ impl FromCbor for Key {
     }
 }
 
+impl<T, const N: usize> ToCbor for [T; N]
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let info = err_at!(FailConvert, u64::try_from(self.len()))?.into();
+        let mut val: Vec<Cbor> = vec![];
+        for item in self.iter() {
+            val.push(item.to_cbor()?)
+        }
+        Ok(Cbor::Major4(info, val))
+    }
+}
+
 impl<T, const N: usize> IntoCbor for [T; N]
 where
-    T: Clone + IntoCbor,
+    T: IntoCbor,
 {
     fn into_cbor(self) -> Result<Cbor> {
         let info = err_at!(FailConvert, u64::try_from(self.len()))?.into();
         let mut val: Vec<Cbor> = vec![];
-        for item in self.iter() {
-            val.push(item.clone().into_cbor()?)
+        for item in IntoIterator::into_iter(self) {
+            val.push(item.into_cbor()?)
         }
         Ok(Cbor::Major4(info, val))
     }
@@ -1023,9 +1935,9 @@ where
     }
 }
 
-impl IntoCbor for bool {
-    fn into_cbor(self) -> Result<Cbor> {
-        match self {
+impl ToCbor for bool {
+    fn to_cbor(&self) -> Result<Cbor> {
+        match *self {
             true => SimpleValue::True.into_cbor(),
             false => SimpleValue::False.into_cbor(),
         }
@@ -1042,41 +1954,77 @@ impl FromCbor for bool {
     }
 }
 
-impl IntoCbor for f32 {
-    fn into_cbor(self) -> Result<Cbor> {
-        SimpleValue::F32(self).into_cbor()
+impl ToCbor for f32 {
+    fn to_cbor(&self) -> Result<Cbor> {
+        SimpleValue::F32(*self).into_cbor()
     }
 }
 
 impl FromCbor for f32 {
     fn from_cbor(val: Cbor) -> Result<f32> {
         match val {
+            Cbor::Major7(_, SimpleValue::F16(val)) => Ok(f16_to_f32(val)),
             Cbor::Major7(_, SimpleValue::F32(val)) => Ok(val),
             _ => err_at!(FailConvert, msg: "not f32"),
         }
     }
 }
 
-impl IntoCbor for f64 {
-    fn into_cbor(self) -> Result<Cbor> {
-        SimpleValue::F64(self).into_cbor()
+impl ToCbor for f64 {
+    fn to_cbor(&self) -> Result<Cbor> {
+        SimpleValue::F64(*self).into_cbor()
     }
 }
 
 impl FromCbor for f64 {
     fn from_cbor(val: Cbor) -> Result<f64> {
         match val {
+            Cbor::Major7(_, SimpleValue::F16(val)) => Ok(f16_to_f64(val)),
+            Cbor::Major7(_, SimpleValue::F32(val)) => Ok(f64::from(val)),
             Cbor::Major7(_, SimpleValue::F64(val)) => Ok(val),
             _ => err_at!(FailConvert, msg: "not f64"),
         }
     }
 }
+impl<T> ToCbor for Arc<T>
+where
+    T: ToCbor + ?Sized,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        cbor_share::Share::from_arc(self).convert(|| self.as_ref().to_cbor())
+    }
+}
+
 impl<T> IntoCbor for Arc<T>
 where
     T: IntoCbor + Clone,
 {
     fn into_cbor(self) -> Result<Cbor> {
-        match Arc::try_unwrap(self) {
+        cbor_share::Share::from_arc(&self).convert(move || match Arc::try_unwrap(self) {
+            Ok(s) => s.into_cbor(),
+            Err(s) => {
+                let s: T = s.as_ref().clone();
+                s.into_cbor()
+            }
+        })
+    }
+}
+
+impl<T> ToCbor for Rc<T>
+where
+    T: ToCbor + ?Sized,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_ref().to_cbor()
+    }
+}
+
+impl<T> IntoCbor for Rc<T>
+where
+    T: IntoCbor + Clone,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        match Rc::try_unwrap(self) {
             Ok(s) => s.into_cbor(),
             Err(s) => {
                 let s: T = s.as_ref().clone();
@@ -1086,6 +2034,71 @@ where
     }
 }
 
+impl<T> FromCbor for Rc<T>
+where
+    T: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<Self> {
+        T::from_cbor(val).map(Rc::new)
+    }
+}
+
+impl<T> ToCbor for Box<T>
+where
+    T: ToCbor + ?Sized,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_ref().to_cbor()
+    }
+}
+
+impl<T> IntoCbor for Box<T>
+where
+    T: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        (*self).into_cbor()
+    }
+}
+
+impl<T> FromCbor for Box<T>
+where
+    T: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<Self> {
+        T::from_cbor(val).map(Box::new)
+    }
+}
+
+impl<'a, T> ToCbor for Cow<'a, T>
+where
+    T: ToCbor + ToOwned + ?Sized,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_ref().to_cbor()
+    }
+}
+
+impl<'a, T> IntoCbor for Cow<'a, T>
+where
+    T: ToOwned + ?Sized,
+    T::Owned: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        self.into_owned().into_cbor()
+    }
+}
+
+impl<'a, T> FromCbor for Cow<'a, T>
+where
+    T: ToOwned + ?Sized,
+    T::Owned: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<Self> {
+        T::Owned::from_cbor(val).map(Cow::Owned)
+    }
+}
+
 macro_rules! convert_neg_num {
     ($($t:ty)*) => {$(
         impl FromCbor for $t {
@@ -1097,9 +2110,10 @@ macro_rules! convert_neg_num {
                         let val: result::Result<$t, _> = val.try_into();
                         err_at!(FailConvert, val)?
                     }
+                    // magnitude of negative integers is encoded as `-1 - val`.
                     Cbor::Major1(_, val) => {
-                        let val: result::Result<$t, _> = (val + 1).try_into();
-                        -err_at!(FailConvert, val)?
+                        let val: result::Result<$t, _> = val.try_into();
+                        -1 - err_at!(FailConvert, val)?
                     }
                     _ => err_at!(FailConvert, msg: "not a number")?,
                 };
@@ -1107,18 +2121,20 @@ macro_rules! convert_neg_num {
             }
         }
 
-        impl IntoCbor for $t {
-            fn into_cbor(self) -> Result<Cbor> {
-                let val: i64 = self.into();
+        impl ToCbor for $t {
+            fn to_cbor(&self) -> Result<Cbor> {
+                let val: i64 = (*self).into();
                 if val >= 0 {
                     Ok(err_at!(FailConvert, u64::try_from(val))?.into_cbor()?)
                 } else {
-                    let val = err_at!(FailConvert, u64::try_from(val.abs() - 1))?;
+                    let val = err_at!(FailConvert, u64::try_from(-1 - val))?;
                     let info = val.into();
                     Ok(Cbor::Major1(info, val))
                 }
             }
         }
+
+        into_cbor_by_ref! {$t}
     )*}
 }
 
@@ -1135,20 +2151,22 @@ macro_rules! convert_pos_num {
             }
         }
 
-        impl IntoCbor for $t {
-            fn into_cbor(self) -> Result<Cbor> {
-                let val = u64::from(self);
+        impl ToCbor for $t {
+            fn to_cbor(&self) -> Result<Cbor> {
+                let val = u64::from(*self);
                 Ok(Cbor::Major0(val.into(), val))
             }
         }
+
+        into_cbor_by_ref! {$t}
     )*}
 }
 
 convert_pos_num! {u64 u32 u16 u8}
 
-impl IntoCbor for usize {
-    fn into_cbor(self) -> Result<Cbor> {
-        let val = err_at!(FailConvert, u64::try_from(self))?;
+impl ToCbor for usize {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = err_at!(FailConvert, u64::try_from(*self))?;
         val.into_cbor()
     }
 }
@@ -1162,9 +2180,9 @@ impl FromCbor for usize {
     }
 }
 
-impl IntoCbor for isize {
-    fn into_cbor(self) -> Result<Cbor> {
-        err_at!(FailConvert, i64::try_from(self))?.into_cbor()
+impl ToCbor for isize {
+    fn to_cbor(&self) -> Result<Cbor> {
+        err_at!(FailConvert, i64::try_from(*self))?.into_cbor()
     }
 }
 
@@ -1172,15 +2190,88 @@ impl FromCbor for isize {
     fn from_cbor(val: Cbor) -> Result<isize> {
         let val = match val {
             Cbor::Major0(_, val) => err_at!(FailConvert, isize::try_from(val))?,
-            Cbor::Major1(_, val) => -err_at!(FailConvert, isize::try_from(val + 1))?,
+            Cbor::Major1(_, val) => -1 - err_at!(FailConvert, isize::try_from(val))?,
             _ => err_at!(FailConvert, msg: "not a number")?,
         };
         Ok(val)
     }
 }
 
-impl<'a> IntoCbor for &'a [u8] {
-    fn into_cbor(self) -> Result<Cbor> {
+// big-endian magnitude without leading zeros, for bignum encoding.
+fn u128_to_bignum(val: u128) -> Vec<u8> {
+    let bytes = val.to_be_bytes();
+    let n = bytes.iter().take_while(|b| **b == 0).count();
+    bytes[n..].to_vec()
+}
+
+fn bignum_to_u128(bytes: &[u8]) -> Result<u128> {
+    let n = bytes.iter().take_while(|b| **b == 0).count();
+    match &bytes[n..] {
+        bytes if bytes.len() > 16 => err_at!(FailConvert, msg: "bignum overflow u128"),
+        bytes => Ok(bytes
+            .iter()
+            .fold(0_u128, |acc, b| (acc << 8) | u128::from(*b))),
+    }
+}
+
+/// Encoded as major type 0 if value fits within 64-bits, otherwise as
+/// positive [BigNum], tag 2.
+impl ToCbor for u128 {
+    fn to_cbor(&self) -> Result<Cbor> {
+        match u64::try_from(*self) {
+            Ok(val) => val.into_cbor(),
+            Err(_) => BigNum::Pos(u128_to_bignum(*self)).into_cbor(),
+        }
+    }
+}
+
+impl FromCbor for u128 {
+    fn from_cbor(val: Cbor) -> Result<u128> {
+        match val {
+            Cbor::Major0(_, val) => Ok(u128::from(val)),
+            val @ Cbor::Major6(_, _) => match BigNum::from_cbor(val)? {
+                BigNum::Pos(bytes) => bignum_to_u128(&bytes),
+                BigNum::Neg(_) => err_at!(FailConvert, msg: "negative bignum for u128"),
+            },
+            _ => err_at!(FailConvert, msg: "not a number"),
+        }
+    }
+}
+
+/// Encoded as major type 0 or 1 if value fits within 64-bits, otherwise as
+/// [BigNum], tag 2 or tag 3.
+impl ToCbor for i128 {
+    fn to_cbor(&self) -> Result<Cbor> {
+        match *self {
+            val if val >= 0 => (val as u128).into_cbor(),
+            // magnitude of negative integers is encoded as `-1 - val`.
+            val => match u64::try_from(!(val as u128)) {
+                Ok(num) => Ok(Cbor::Major1(num.into(), num)),
+                Err(_) => BigNum::Neg(u128_to_bignum(!(val as u128))).into_cbor(),
+            },
+        }
+    }
+}
+
+impl FromCbor for i128 {
+    fn from_cbor(val: Cbor) -> Result<i128> {
+        let (neg, num) = match val {
+            Cbor::Major0(_, val) => (false, u128::from(val)),
+            Cbor::Major1(_, val) => (true, u128::from(val)),
+            val @ Cbor::Major6(_, _) => match BigNum::from_cbor(val)? {
+                BigNum::Pos(bytes) => (false, bignum_to_u128(&bytes)?),
+                BigNum::Neg(bytes) => (true, bignum_to_u128(&bytes)?),
+            },
+            _ => err_at!(FailConvert, msg: "not a number")?,
+        };
+        let val = err_at!(FailConvert, i128::try_from(num))?;
+        Ok(if neg { -1 - val } else { val })
+    }
+}
+
+/// Encoded as byte-string, `&[u8]` is converted into [Cbor] via [ToCbor].
+impl ToCbor for [u8] {
+    fn to_cbor(&self) -> Result<Cbor> {
         let n = err_at!(FailConvert, u64::try_from(self.len()))?;
         Ok(Cbor::Major2(n.into(), self.to_vec()))
     }
@@ -1200,6 +2291,20 @@ where
     }
 }
 
+impl<T> ToCbor for Vec<T>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let n = err_at!(FailConvert, u64::try_from(self.len()))?;
+        let mut arr = vec![];
+        for item in self.iter() {
+            arr.push(item.to_cbor()?)
+        }
+        Ok(Cbor::Major4(n.into(), arr))
+    }
+}
+
 impl<T> FromCbor for Vec<T>
 where
     T: FromCbor + Sized,
@@ -1218,8 +2323,230 @@ where
     }
 }
 
-impl<'a> IntoCbor for &'a str {
+impl<T> IntoCbor for VecDeque<T>
+where
+    T: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        Vec::from(self).into_cbor()
+    }
+}
+
+impl<T> ToCbor for VecDeque<T>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let mut arr = vec![];
+        for item in self.iter() {
+            arr.push(item.to_cbor()?)
+        }
+        arr.into_cbor()
+    }
+}
+
+impl<T> FromCbor for VecDeque<T>
+where
+    T: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<VecDeque<T>> {
+        Ok(Vec::<T>::from_cbor(val)?.into())
+    }
+}
+
+/// Sets are encoded as list, items of HashSet are sorted by their
+/// canonical encoding, for deterministic encoding.
+impl<T, S> IntoCbor for HashSet<T, S>
+where
+    T: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        let mut items = vec![];
+        for item in self.into_iter() {
+            items.push(item.into_cbor()?)
+        }
+        sorted_set_into_cbor(items)
+    }
+}
+
+impl<T, S> ToCbor for HashSet<T, S>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let mut items = vec![];
+        for item in self.iter() {
+            items.push(item.to_cbor()?)
+        }
+        sorted_set_into_cbor(items)
+    }
+}
+
+fn sorted_set_into_cbor(items: Vec<Cbor>) -> Result<Cbor> {
+    let mut items: Vec<(Vec<u8>, Cbor)> = items
+        .into_iter()
+        .map(|item| {
+            let mut buf: Vec<u8> = vec![];
+            item.encode_canonical(&mut buf)?;
+            Ok((buf, item))
+        })
+        .collect::<Result<_>>()?;
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    let items: Vec<Cbor> = items.into_iter().map(|(_, item)| item).collect();
+    items.into_cbor()
+}
+
+impl<T, S> FromCbor for HashSet<T, S>
+where
+    T: FromCbor + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn from_cbor(val: Cbor) -> Result<HashSet<T, S>> {
+        let mut set = HashSet::default();
+        for item in Vec::<T>::from_cbor(val)?.into_iter() {
+            if !set.insert(item) {
+                err_at!(FailConvert, msg: "duplicate item in set")?
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl<T> IntoCbor for BTreeSet<T>
+where
+    T: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        self.into_iter().collect::<Vec<T>>().into_cbor()
+    }
+}
+
+impl<T> ToCbor for BTreeSet<T>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let mut items = vec![];
+        for item in self.iter() {
+            items.push(item.to_cbor()?)
+        }
+        items.into_cbor()
+    }
+}
+
+impl<T> FromCbor for BTreeSet<T>
+where
+    T: FromCbor + Ord,
+{
+    fn from_cbor(val: Cbor) -> Result<BTreeSet<T>> {
+        let mut set = BTreeSet::new();
+        for item in Vec::<T>::from_cbor(val)?.into_iter() {
+            if !set.insert(item) {
+                err_at!(FailConvert, msg: "duplicate item in set")?
+            }
+        }
+        Ok(set)
+    }
+}
+
+/// Map keys are converted through [Key], entries of HashMap are sorted
+/// in [Key] order, for deterministic encoding.
+impl<K, V, S> IntoCbor for HashMap<K, V, S>
+where
+    K: IntoCbor,
+    V: IntoCbor,
+{
     fn into_cbor(self) -> Result<Cbor> {
+        let mut map = vec![];
+        for (key, val) in self.into_iter() {
+            map.push((Key::from_cbor(key.into_cbor()?)?, val.into_cbor()?))
+        }
+        map.sort_by(|a, b| a.0.cmp(&b.0));
+        map.into_cbor()
+    }
+}
+
+impl<K, V, S> ToCbor for HashMap<K, V, S>
+where
+    K: ToCbor,
+    V: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let mut map = vec![];
+        for (key, val) in self.iter() {
+            map.push((Key::from_cbor(key.to_cbor()?)?, val.to_cbor()?))
+        }
+        map.sort_by(|a, b| a.0.cmp(&b.0));
+        map.into_cbor()
+    }
+}
+
+impl<K, V, S> FromCbor for HashMap<K, V, S>
+where
+    K: FromCbor + Eq + Hash,
+    V: FromCbor,
+    S: BuildHasher + Default,
+{
+    fn from_cbor(val: Cbor) -> Result<HashMap<K, V, S>> {
+        let mut map = HashMap::default();
+        for (key, val) in Vec::<(Key, Cbor)>::from_cbor(val)?.into_iter() {
+            let key = K::from_cbor(key.into_cbor()?)?;
+            if map.insert(key, V::from_cbor(val)?).is_some() {
+                err_at!(FailConvert, msg: "duplicate key in map")?
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Map keys are converted through [Key].
+impl<K, V> IntoCbor for BTreeMap<K, V>
+where
+    K: IntoCbor,
+    V: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        let mut map = vec![];
+        for (key, val) in self.into_iter() {
+            map.push((Key::from_cbor(key.into_cbor()?)?, val.into_cbor()?))
+        }
+        map.into_cbor()
+    }
+}
+
+impl<K, V> ToCbor for BTreeMap<K, V>
+where
+    K: ToCbor,
+    V: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let mut map = vec![];
+        for (key, val) in self.iter() {
+            map.push((Key::from_cbor(key.to_cbor()?)?, val.to_cbor()?))
+        }
+        map.into_cbor()
+    }
+}
+
+impl<K, V> FromCbor for BTreeMap<K, V>
+where
+    K: FromCbor + Ord,
+    V: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<BTreeMap<K, V>> {
+        let mut map = BTreeMap::new();
+        for (key, val) in Vec::<(Key, Cbor)>::from_cbor(val)?.into_iter() {
+            let key = K::from_cbor(key.into_cbor()?)?;
+            if map.insert(key, V::from_cbor(val)?).is_some() {
+                err_at!(FailConvert, msg: "duplicate key in map")?
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl ToCbor for str {
+    fn to_cbor(&self) -> Result<Cbor> {
         let n = err_at!(FailConvert, u64::try_from(self.len()))?;
         Ok(Cbor::Major3(n.into(), self.as_bytes().to_vec()))
     }
@@ -1232,6 +2559,12 @@ impl IntoCbor for String {
     }
 }
 
+impl ToCbor for String {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_str().to_cbor()
+    }
+}
+
 impl FromCbor for String {
     fn from_cbor(val: Cbor) -> Result<String> {
         use std::str::from_utf8;
@@ -1251,6 +2584,19 @@ impl IntoCbor for ffi::OsString {
     }
 }
 
+impl ToCbor for ffi::OsStr {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let n = err_at!(FailConvert, u64::try_from(self.len()))?;
+        Ok(Cbor::Major3(n.into(), self.as_bytes().to_vec()))
+    }
+}
+
+impl ToCbor for ffi::OsString {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_os_str().to_cbor()
+    }
+}
+
 impl FromCbor for ffi::OsString {
     fn from_cbor(val: Cbor) -> Result<ffi::OsString> {
         match val {
@@ -1260,6 +2606,47 @@ impl FromCbor for ffi::OsString {
     }
 }
 
+impl IntoCbor for path::PathBuf {
+    fn into_cbor(self) -> Result<Cbor> {
+        self.into_os_string().into_cbor()
+    }
+}
+
+impl ToCbor for path::Path {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_os_str().to_cbor()
+    }
+}
+
+impl ToCbor for path::PathBuf {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.as_path().to_cbor()
+    }
+}
+
+impl FromCbor for path::PathBuf {
+    fn from_cbor(val: Cbor) -> Result<path::PathBuf> {
+        Ok(ffi::OsString::from_cbor(val)?.into())
+    }
+}
+
+/// Duration is encoded as two item list, `[seconds, nanoseconds]`.
+impl ToCbor for time::Duration {
+    fn to_cbor(&self) -> Result<Cbor> {
+        (self.as_secs(), self.subsec_nanos()).into_cbor()
+    }
+}
+
+impl FromCbor for time::Duration {
+    fn from_cbor(val: Cbor) -> Result<time::Duration> {
+        let (secs, nanos) = <(u64, u32)>::from_cbor(val)?;
+        if nanos >= 1_000_000_000 {
+            err_at!(FailConvert, msg: "duration nanos {} out of range", nanos)?
+        }
+        Ok(time::Duration::new(secs, nanos))
+    }
+}
+
 impl IntoCbor for Vec<Cbor> {
     fn into_cbor(self) -> Result<Cbor> {
         let n = err_at!(FailConvert, u64::try_from(self.len()))?;
@@ -1267,6 +2654,12 @@ impl IntoCbor for Vec<Cbor> {
     }
 }
 
+impl ToCbor for Vec<Cbor> {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.clone().into_cbor()
+    }
+}
+
 impl FromCbor for Vec<Cbor> {
     fn from_cbor(val: Cbor) -> Result<Vec<Cbor>> {
         match val {
@@ -1283,6 +2676,12 @@ impl IntoCbor for Vec<(Key, Cbor)> {
     }
 }
 
+impl ToCbor for Vec<(Key, Cbor)> {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.clone().into_cbor()
+    }
+}
+
 impl FromCbor for Vec<(Key, Cbor)> {
     fn from_cbor(val: Cbor) -> Result<Vec<(Key, Cbor)>> {
         match val {
@@ -1304,6 +2703,18 @@ where
     }
 }
 
+impl<T> ToCbor for Option<T>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        match self {
+            Some(val) => val.to_cbor(),
+            None => SimpleValue::Null.into_cbor(),
+        }
+    }
+}
+
 impl<T> FromCbor for Option<T>
 where
     T: FromCbor + Sized,
@@ -1316,6 +2727,174 @@ where
     }
 }
 
+impl ToCbor for () {
+    fn to_cbor(&self) -> Result<Cbor> {
+        SimpleValue::Null.into_cbor()
+    }
+}
+
+impl FromCbor for () {
+    fn from_cbor(val: Cbor) -> Result<()> {
+        match val {
+            Cbor::Major7(_, SimpleValue::Null) => Ok(()),
+            _ => err_at!(FailConvert, msg: "not a unit"),
+        }
+    }
+}
+
+impl ToCbor for char {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.to_string().into_cbor()
+    }
+}
+
+impl FromCbor for char {
+    fn from_cbor(val: Cbor) -> Result<char> {
+        let s = String::from_cbor(val)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(ch),
+            (_, _) => err_at!(FailConvert, msg: "not a char {:?}", s),
+        }
+    }
+}
+
+macro_rules! convert_tuple {
+    ($n:expr, $($t:ident $i:tt),+) => {
+        impl<$($t),+> IntoCbor for ($($t,)+)
+        where
+            $($t: IntoCbor),+
+        {
+            fn into_cbor(self) -> Result<Cbor> {
+                let items: Vec<Cbor> = vec![$(self.$i.into_cbor()?),+];
+                items.into_cbor()
+            }
+        }
+
+        impl<$($t),+> ToCbor for ($($t,)+)
+        where
+            $($t: ToCbor),+
+        {
+            fn to_cbor(&self) -> Result<Cbor> {
+                let items: Vec<Cbor> = vec![$(self.$i.to_cbor()?),+];
+                items.into_cbor()
+            }
+        }
+
+        impl<$($t),+> FromCbor for ($($t,)+)
+        where
+            $($t: FromCbor),+
+        {
+            fn from_cbor(val: Cbor) -> Result<Self> {
+                let mut items = Vec::<Cbor>::from_cbor(val)?;
+                if items.len() != $n {
+                    err_at!(FailConvert, msg: "different tuple arity {} {}", $n, items.len())?
+                }
+                Ok(($(<$t as FromCbor>::from_cbor(items.remove(0))?,)+))
+            }
+        }
+    };
+}
+
+convert_tuple!(1, A 0);
+convert_tuple!(2, A 0, B 1);
+convert_tuple!(3, A 0, B 1, C 2);
+convert_tuple!(4, A 0, B 1, C 2, D 3);
+convert_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+convert_tuple!(6, A 0, B 1, C 2, D 3, E 4, F 5);
+convert_tuple!(7, A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+convert_tuple!(8, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+convert_tuple!(9, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+convert_tuple!(10, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+convert_tuple!(11, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+convert_tuple!(12, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Result is encoded as two item list, `["Ok", value]` or `["Err", err]`,
+/// same as an enum variant with single field.
+impl<T, E> IntoCbor for result::Result<T, E>
+where
+    T: IntoCbor,
+    E: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        let items: Vec<Cbor> = match self {
+            Ok(val) => vec!["Ok".into_cbor()?, val.into_cbor()?],
+            Err(err) => vec!["Err".into_cbor()?, err.into_cbor()?],
+        };
+        items.into_cbor()
+    }
+}
+
+impl<T, E> ToCbor for result::Result<T, E>
+where
+    T: ToCbor,
+    E: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        let items: Vec<Cbor> = match self {
+            Ok(val) => vec!["Ok".into_cbor()?, val.to_cbor()?],
+            Err(err) => vec!["Err".into_cbor()?, err.to_cbor()?],
+        };
+        items.into_cbor()
+    }
+}
+
+impl<T, E> FromCbor for result::Result<T, E>
+where
+    T: FromCbor,
+    E: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<Self> {
+        let mut items = Vec::<Cbor>::from_cbor(val)?;
+        if items.len() != 2 {
+            err_at!(FailConvert, msg: "bad arity {}", items.len())?
+        }
+        let variant = String::from_cbor(items.remove(0))?;
+        let val = items.remove(0);
+        match variant.as_str() {
+            "Ok" => Ok(Ok(T::from_cbor(val)?)),
+            "Err" => Ok(Err(E::from_cbor(val)?)),
+            _ => err_at!(FailConvert, msg: "invalid variant_name {}", variant),
+        }
+    }
+}
+
+/// Error is encoded as three item list, `[variant, prefix, msg]`.
+impl ToCbor for Error {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let (variant, prefix, msg) = match self {
+            Error::Fatal(p, m) => ("Fatal", p, m),
+            Error::FailConvert(p, m) => ("FailConvert", p, m),
+            Error::IOError(p, m) => ("IOError", p, m),
+            Error::FailCbor(p, m) => ("FailCbor", p, m),
+            Error::IPCFail(p, m) => ("IPCFail", p, m),
+            Error::ThreadFail(p, m) => ("ThreadFail", p, m),
+        };
+        (variant, prefix, msg).into_cbor()
+    }
+}
+
+impl FromCbor for Error {
+    fn from_cbor(val: Cbor) -> Result<Error> {
+        let (variant, p, m) = <(String, String, String)>::from_cbor(val)?;
+        let err = match variant.as_str() {
+            "Fatal" => Error::Fatal(p, m),
+            "FailConvert" => Error::FailConvert(p, m),
+            "IOError" => Error::IOError(p, m),
+            "FailCbor" => Error::FailCbor(p, m),
+            "IPCFail" => Error::IPCFail(p, m),
+            "ThreadFail" => Error::ThreadFail(p, m),
+            _ => err_at!(FailConvert, msg: "invalid variant_name {}", variant)?,
+        };
+        Ok(err)
+    }
+}
+
+into_cbor_by_ref! {
+    SimpleValue, bool, f32, f64, usize, isize, u128, i128, time::Duration, (), char,
+    Error,
+}
+
 #[cfg(test)]
 #[path = "cbor_test.rs"]
 mod cbor_test;
@@ -0,0 +1,743 @@
+//! Module implement encoding and decoding of rust-native values, directly
+//! into and from CBOR bytes.
+//!
+//! [IntoCbor] and [ToCbor] convert the value into a [Cbor] tree, which is
+//! then walked again by [Cbor::encode], allocating for every item on the
+//! way. [CborEncode] instead writes the value straight into `io::Write`
+//! and [CborDecode] reads the value straight from `io::Read`. Both produce
+//! and accept the same bytes as that of the tree based conversion, and are
+//! implemented for types deriving `Cborize`.
+//!
+//! Types without a direct encoding, like sets, maps and tagged values, are
+//! encoded and decoded via the [Cbor] tree.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+    ffi, io, path,
+    rc::Rc,
+    result,
+    sync::Arc,
+    time,
+};
+
+#[allow(unused_imports)]
+use crate::cbor::{IntoCbor, ToCbor};
+use crate::{
+    cbor::{
+        decode_addnl, decode_hdr, encode_addnl, encode_hdr, f16_to_f32, f16_to_f64, Cbor,
+        FromCbor, Info, Key, STRING_CHUNK,
+    },
+    Error, Result,
+};
+
+/// Encode rust-native value into CBOR bytes, without building the [Cbor]
+/// value. Encoded bytes are same as that of [ToCbor] followed by
+/// [Cbor::encode].
+pub trait CborEncode {
+    /// Serialize value into `w`, return the number of bytes written.
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write;
+}
+
+/// Decode rust-native value from CBOR bytes, without building the [Cbor]
+/// value. Accepts the same input as [Cbor::decode] followed by [FromCbor],
+/// except for string references, refer [DecodeOptions::stringref], and
+/// shared values, refer [from_cbor_shared], which are not resolved.
+///
+/// Length prefixes in the input are trusted as is.
+///
+/// [DecodeOptions::stringref]: crate::cbor::DecodeOptions::stringref
+/// [from_cbor_shared]: crate::cbor::from_cbor_shared
+pub trait CborDecode: Sized {
+    /// Deserialize value from `r`, return the value and the number of bytes
+    /// read.
+    fn decode_from<R>(r: &mut R) -> Result<(Self, usize)>
+    where
+        R: io::Read,
+    {
+        let (major, info, n) = decode_hdr(r)?;
+        let (val, m) = Self::decode_after_hdr(major, info, r)?;
+        Ok((val, n + m))
+    }
+
+    /// Same as `decode_from`, but the header, `major` type and additional
+    /// `info`, is already read from `r`. Return the value and the number of
+    /// bytes read after the header.
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+    where
+        R: io::Read;
+}
+
+impl Cbor {
+    /// Serialize the header for a definite length list of `len` items,
+    /// items shall follow. Used by [CborEncode] implementations.
+    pub fn encode_list_hdr<W>(len: usize, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        encode_head(4, err_at!(FailConvert, u64::try_from(len))?, w)
+    }
+
+    /// Serialize the header for tag number `num`, enclosed data item shall
+    /// follow. Used by [CborEncode] implementations.
+    pub fn encode_tag_hdr<W>(num: u64, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        encode_head(6, num, w)
+    }
+
+    /// Deserialize the length of a list, whose header is already read from
+    /// `r`. Length is None for indefinite encoding, in which case the items
+    /// shall be followed by a break stop, refer to [Cbor::decode_break].
+    /// Used by [CborDecode] implementations.
+    pub fn decode_list_len<R>(
+        major: u8,
+        info: Info,
+        r: &mut R,
+    ) -> Result<(Option<u64>, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (4, Info::Indefinite) => Ok((None, 0)),
+            (4, info) => {
+                let (len, n) = decode_addnl(info, r)?;
+                Ok((Some(len), n))
+            }
+            (_, _) => err_at!(FailConvert, msg: "not a list, major {}", major),
+        }
+    }
+
+    /// Deserialize the break stop, that ends an indefinite length list.
+    pub fn decode_break<R>(r: &mut R) -> Result<usize>
+    where
+        R: io::Read,
+    {
+        match decode_hdr(r)? {
+            (7, Info::Indefinite, n) => Ok(n),
+            (major, _, _) => err_at!(FailConvert, msg: "expected break, major {}", major),
+        }
+    }
+}
+
+// serialize header and additional info for `num`, in its shortest form.
+fn encode_head<W>(major: u8, num: u64, w: &mut W) -> Result<usize>
+where
+    W: io::Write,
+{
+    Ok(encode_hdr(major, num.into(), w)? + encode_addnl(num, w)?)
+}
+
+fn encode_string<W>(major: u8, data: &[u8], w: &mut W) -> Result<usize>
+where
+    W: io::Write,
+{
+    let n = encode_head(major, err_at!(FailConvert, u64::try_from(data.len()))?, w)?;
+    err_at!(IOError, w.write_all(data))?;
+    Ok(n + data.len())
+}
+
+// read `len` bytes of string, without trusting `len` for pre-allocation.
+fn read_string<R>(len: u64, r: &mut R) -> Result<Vec<u8>>
+where
+    R: io::Read,
+{
+    let n: usize = err_at!(FailConvert, usize::try_from(len))?;
+    let mut data = Vec::with_capacity(std::cmp::min(n, STRING_CHUNK));
+    let m = err_at!(
+        IOError,
+        io::Read::read_to_end(&mut io::Read::take(r, len), &mut data)
+    )?;
+    if m != n {
+        err_at!(IOError, msg: "insufficient bytes {}/{}", m, n)?
+    }
+    Ok(data)
+}
+
+fn read_float<R, const N: usize>(r: &mut R) -> Result<[u8; N]>
+where
+    R: io::Read,
+{
+    let mut scratch = [0_u8; N];
+    err_at!(IOError, r.read_exact(&mut scratch))?;
+    Ok(scratch)
+}
+
+impl CborEncode for Cbor {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        self.encode(w)
+    }
+}
+
+/// Values are decoded via [Cbor::decode], after replaying the header.
+impl CborDecode for Cbor {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Cbor, usize)>
+    where
+        R: io::Read,
+    {
+        let mut hdr: Vec<u8> = Vec::with_capacity(1);
+        let n = encode_hdr(major, info, &mut hdr)?;
+        let (val, m) = Cbor::decode(&mut io::Read::chain(hdr.as_slice(), r))?;
+        Ok((val, m - n))
+    }
+}
+
+impl<T> CborEncode for &T
+where
+    T: CborEncode + ?Sized,
+{
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        (**self).encode_to(w)
+    }
+}
+
+macro_rules! codec_pointer {
+    ($($p:ident)*) => {$(
+        impl<T> CborEncode for $p<T>
+        where
+            T: CborEncode + ?Sized,
+        {
+            fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+            where
+                W: io::Write,
+            {
+                (**self).encode_to(w)
+            }
+        }
+
+        impl<T> CborDecode for $p<T>
+        where
+            T: CborDecode,
+        {
+            fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+            where
+                R: io::Read,
+            {
+                let (val, n) = T::decode_after_hdr(major, info, r)?;
+                Ok(($p::new(val), n))
+            }
+        }
+    )*}
+}
+
+codec_pointer! {Box Arc Rc}
+
+macro_rules! codec_pos_num {
+    ($($t:ty)*) => {$(
+        impl CborEncode for $t {
+            fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+            where
+                W: io::Write,
+            {
+                encode_head(0, u64::from(*self), w)
+            }
+        }
+
+        impl CborDecode for $t {
+            fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<($t, usize)>
+            where
+                R: io::Read,
+            {
+                match major {
+                    0 => {
+                        let (num, n) = decode_addnl(info, r)?;
+                        Ok((err_at!(FailConvert, <$t>::try_from(num))?, n))
+                    }
+                    _ => err_at!(FailConvert, msg: "not a number"),
+                }
+            }
+        }
+    )*}
+}
+
+codec_pos_num! {u64 u32 u16 u8}
+
+macro_rules! codec_neg_num {
+    ($($t:ty)*) => {$(
+        impl CborEncode for $t {
+            fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+            where
+                W: io::Write,
+            {
+                match i64::from(*self) {
+                    val if val >= 0 => encode_head(0, val as u64, w),
+                    // magnitude of negative integers is encoded as `-1 - val`.
+                    val => encode_head(1, (-1 - val) as u64, w),
+                }
+            }
+        }
+
+        impl CborDecode for $t {
+            fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<($t, usize)>
+            where
+                R: io::Read,
+            {
+                let (num, n) = match major {
+                    0 | 1 => decode_addnl(info, r)?,
+                    _ => err_at!(FailConvert, msg: "not a number")?,
+                };
+                let val = err_at!(FailConvert, i64::try_from(num))?;
+                let val = if major == 1 { -1 - val } else { val };
+                Ok((err_at!(FailConvert, <$t>::try_from(val))?, n))
+            }
+        }
+    )*}
+}
+
+codec_neg_num! {i64 i32 i16 i8}
+
+impl CborEncode for usize {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        err_at!(FailConvert, u64::try_from(*self))?.encode_to(w)
+    }
+}
+
+impl CborDecode for usize {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(usize, usize)>
+    where
+        R: io::Read,
+    {
+        let (val, n) = u64::decode_after_hdr(major, info, r)?;
+        Ok((err_at!(FailConvert, usize::try_from(val))?, n))
+    }
+}
+
+impl CborEncode for isize {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        err_at!(FailConvert, i64::try_from(*self))?.encode_to(w)
+    }
+}
+
+impl CborDecode for isize {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(isize, usize)>
+    where
+        R: io::Read,
+    {
+        let (val, n) = i64::decode_after_hdr(major, info, r)?;
+        Ok((err_at!(FailConvert, isize::try_from(val))?, n))
+    }
+}
+
+impl CborEncode for bool {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        match *self {
+            true => encode_hdr(7, Info::Tiny(20), w),
+            false => encode_hdr(7, Info::Tiny(21), w),
+        }
+    }
+}
+
+impl CborDecode for bool {
+    fn decode_after_hdr<R>(major: u8, info: Info, _r: &mut R) -> Result<(bool, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (7, Info::Tiny(20)) => Ok((true, 0)),
+            (7, Info::Tiny(21)) => Ok((false, 0)),
+            (_, _) => err_at!(FailConvert, msg: "not a bool"),
+        }
+    }
+}
+
+impl CborEncode for f32 {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        let n = encode_hdr(7, Info::U32, w)?;
+        err_at!(IOError, w.write_all(&self.to_be_bytes()))?;
+        Ok(n + 4)
+    }
+}
+
+impl CborDecode for f32 {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(f32, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (7, Info::U16) => Ok((f16_to_f32(u16::from_be_bytes(read_float(r)?)), 2)),
+            (7, Info::U32) => Ok((f32::from_be_bytes(read_float(r)?), 4)),
+            (_, _) => err_at!(FailConvert, msg: "not f32"),
+        }
+    }
+}
+
+impl CborEncode for f64 {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        let n = encode_hdr(7, Info::U64, w)?;
+        err_at!(IOError, w.write_all(&self.to_be_bytes()))?;
+        Ok(n + 8)
+    }
+}
+
+impl CborDecode for f64 {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(f64, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (7, Info::U16) => Ok((f16_to_f64(u16::from_be_bytes(read_float(r)?)), 2)),
+            (7, Info::U32) => Ok((f64::from(f32::from_be_bytes(read_float(r)?)), 4)),
+            (7, Info::U64) => Ok((f64::from_be_bytes(read_float(r)?), 8)),
+            (_, _) => err_at!(FailConvert, msg: "not f64"),
+        }
+    }
+}
+
+impl CborEncode for () {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        encode_hdr(7, Info::Tiny(22), w)
+    }
+}
+
+impl CborDecode for () {
+    fn decode_after_hdr<R>(major: u8, info: Info, _r: &mut R) -> Result<((), usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (7, Info::Tiny(22)) => Ok(((), 0)),
+            (_, _) => err_at!(FailConvert, msg: "not a unit"),
+        }
+    }
+}
+
+impl<T> CborEncode for Option<T>
+where
+    T: CborEncode,
+{
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        match self {
+            Some(val) => val.encode_to(w),
+            None => ().encode_to(w),
+        }
+    }
+}
+
+impl<T> CborDecode for Option<T>
+where
+    T: CborDecode,
+{
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            (7, Info::Tiny(22)) => Ok((None, 0)),
+            (major, info) => {
+                let (val, n) = T::decode_after_hdr(major, info, r)?;
+                Ok((Some(val), n))
+            }
+        }
+    }
+}
+
+/// Encoded as byte-string, same as `&[u8]` converted via [IntoCbor].
+impl CborEncode for [u8] {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        encode_string(2, self, w)
+    }
+}
+
+impl CborEncode for str {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        encode_string(3, self.as_bytes(), w)
+    }
+}
+
+impl CborEncode for String {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        self.as_str().encode_to(w)
+    }
+}
+
+impl CborDecode for String {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(String, usize)>
+    where
+        R: io::Read,
+    {
+        match (major, info) {
+            // chunks are concatenated by the tree decoder.
+            (3, Info::Indefinite) => {
+                let (val, n) = Cbor::decode_after_hdr(major, info, r)?;
+                Ok((String::from_cbor(val)?, n))
+            }
+            (3, info) => {
+                let (len, n) = decode_addnl(info, r)?;
+                let data = read_string(len, r)?;
+                let m = data.len();
+                Ok((err_at!(FailConvert, String::from_utf8(data))?, n + m))
+            }
+            (_, _) => err_at!(FailConvert, msg: "not utf8-string"),
+        }
+    }
+}
+
+impl CborEncode for char {
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        let mut scratch = [0_u8; 4];
+        self.encode_utf8(&mut scratch).encode_to(w)
+    }
+}
+
+impl CborDecode for char {
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(char, usize)>
+    where
+        R: io::Read,
+    {
+        let (s, n) = String::decode_after_hdr(major, info, r)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok((ch, n)),
+            (_, _) => err_at!(FailConvert, msg: "not a char {:?}", s),
+        }
+    }
+}
+
+impl<T> CborEncode for Vec<T>
+where
+    T: CborEncode,
+{
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        let mut n = Cbor::encode_list_hdr(self.len(), w)?;
+        for item in self.iter() {
+            n += item.encode_to(w)?;
+        }
+        Ok(n)
+    }
+}
+
+impl<T> CborDecode for Vec<T>
+where
+    T: CborDecode,
+{
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+    where
+        R: io::Read,
+    {
+        let (len, mut n) = Cbor::decode_list_len(major, info, r)?;
+
+        let mut arr = vec![];
+        match len {
+            Some(len) => {
+                for _ in 0..len {
+                    let (item, m) = T::decode_from(r)?;
+                    arr.push(item);
+                    n += m;
+                }
+            }
+            None => loop {
+                let (major, info, m) = decode_hdr(r)?;
+                n += m;
+                if let (7, Info::Indefinite) = (major, info) {
+                    break;
+                }
+                let (item, m) = T::decode_after_hdr(major, info, r)?;
+                arr.push(item);
+                n += m;
+            },
+        }
+        Ok((arr, n))
+    }
+}
+
+impl<T, const N: usize> CborEncode for [T; N]
+where
+    T: CborEncode,
+{
+    fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+    where
+        W: io::Write,
+    {
+        let mut n = Cbor::encode_list_hdr(N, w)?;
+        for item in self.iter() {
+            n += item.encode_to(w)?;
+        }
+        Ok(n)
+    }
+}
+
+impl<T, const N: usize> CborDecode for [T; N]
+where
+    T: CborDecode,
+{
+    fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+    where
+        R: io::Read,
+    {
+        let (arr, n) = Vec::<T>::decode_after_hdr(major, info, r)?;
+        match <[T; N]>::try_from(arr) {
+            Ok(arr) => Ok((arr, n)),
+            Err(arr) => {
+                err_at!(FailConvert, msg: "different array arity {} {}", N, arr.len())
+            }
+        }
+    }
+}
+
+macro_rules! codec_tuple {
+    ($n:expr, $($t:ident $i:tt),+) => {
+        impl<$($t),+> CborEncode for ($($t,)+)
+        where
+            $($t: CborEncode),+
+        {
+            fn encode_to<W>(&self, w: &mut W) -> Result<usize>
+            where
+                W: io::Write,
+            {
+                let mut n = Cbor::encode_list_hdr($n, w)?;
+                $(n += self.$i.encode_to(w)?;)+
+                Ok(n)
+            }
+        }
+
+        impl<$($t),+> CborDecode for ($($t,)+)
+        where
+            $($t: CborDecode),+
+        {
+            fn decode_after_hdr<R>(major: u8, info: Info, r: &mut R) -> Result<(Self, usize)>
+            where
+                R: io::Read,
+            {
+                let (len, mut n) = Cbor::decode_list_len(major, info, r)?;
+                if let Some(len) = len {
+                    if len != $n {
+                        err_at!(FailConvert, msg: "different tuple arity {} {}", $n, len)?
+                    }
+                }
+                let val = ($(
+                    {
+                        let (item, m) = <$t as CborDecode>::decode_from(r)?;
+                        n += m;
+                        item
+                    },
+                )+);
+                if len.is_none() {
+                    n += Cbor::decode_break(r)?;
+                }
+                Ok((val, n))
+            }
+        }
+    };
+}
+
+codec_tuple!(1, A 0);
+codec_tuple!(2, A 0, B 1);
+codec_tuple!(3, A 0, B 1, C 2);
+codec_tuple!(4, A 0, B 1, C 2, D 3);
+codec_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+codec_tuple!(6, A 0, B 1, C 2, D 3, E 4, F 5);
+codec_tuple!(7, A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+codec_tuple!(8, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+codec_tuple!(9, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+codec_tuple!(10, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+codec_tuple!(11, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+codec_tuple!(12, A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Implement [CborEncode] and [CborDecode] via the [Cbor] tree, for types
+/// that implement [ToCbor] and [FromCbor] but do not have a direct encoding.
+/// Generic parameters, if any, are supplied within `[]`.
+///
+/// Types used as fields of `Cborize` types must implement all four traits,
+/// for example:
+///
+/// ```ignore
+/// mkit::cbor::codec_by_tree! {[] MyType, [T] MyList<T>}
+/// ```
+///
+/// [Cbor]: crate::cbor::Cbor
+/// [ToCbor]: crate::cbor::ToCbor
+/// [FromCbor]: crate::cbor::FromCbor
+#[macro_export]
+macro_rules! codec_by_tree {
+    ($([$($g:tt)*] $t:ty),* $(,)?) => {$(
+        impl<$($g)*> $crate::cbor::CborEncode for $t
+        where
+            $t: $crate::cbor::ToCbor,
+        {
+            fn encode_to<W>(&self, w: &mut W) -> $crate::Result<usize>
+            where
+                W: std::io::Write,
+            {
+                $crate::cbor::ToCbor::to_cbor(self)?.encode(w)
+            }
+        }
+
+        impl<$($g)*> $crate::cbor::CborDecode for $t
+        where
+            $t: $crate::cbor::FromCbor,
+        {
+            fn decode_after_hdr<R>(
+                major: u8,
+                info: $crate::cbor::Info,
+                r: &mut R,
+            ) -> $crate::Result<(Self, usize)>
+            where
+                R: std::io::Read,
+            {
+                use $crate::cbor::{Cbor, CborDecode, FromCbor};
+
+                let (val, n) = <Cbor as CborDecode>::decode_after_hdr(major, info, r)?;
+                Ok((<$t as FromCbor>::from_cbor(val)?, n))
+            }
+        }
+    )*}
+}
+
+codec_by_tree! {
+    [] Key, [] u128, [] i128,
+    [T] VecDeque<T>, [T, S] HashSet<T, S>, [T] BTreeSet<T>,
+    [K, V, S] HashMap<K, V, S>, [K, V] BTreeMap<K, V>,
+    ['a, T: ToOwned + ?Sized] Cow<'a, T>,
+    [] ffi::OsString, [] path::PathBuf, [] time::Duration,
+    [T, E] result::Result<T, E>, [] Error,
+}
+
+#[cfg(test)]
+#[path = "cbor_codec_test.rs"]
+mod cbor_codec_test;
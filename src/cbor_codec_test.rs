@@ -0,0 +1,285 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::*;
+
+use crate::{
+    cbor::{Tag, Uuid},
+    db::{Delta, Entry, Value},
+    LocalCborize,
+};
+
+#[derive(Clone, Debug, PartialEq, LocalCborize)]
+enum Shape<T> {
+    Empty,
+    Point(T, T),
+    Named {
+        label: String,
+        data: Vec<u8>,
+        n: Option<u32>,
+    },
+}
+
+impl<T> Shape<T> {
+    const ID: &'static str = "shape/0.0.1";
+}
+
+#[derive(Clone, Debug, PartialEq, LocalCborize)]
+struct Canvas {
+    name: String,
+    blob: Vec<u8>,
+    shapes: Vec<Shape<i64>>,
+    scale: (f32, f64),
+    tags: BTreeMap<String, u64>,
+}
+
+impl Canvas {
+    const ID: u32 = 10;
+}
+
+// assert that direct encoding is same as tree encoding, and decodes back.
+fn check<T>(val: T)
+where
+    T: Clone + PartialEq + std::fmt::Debug + ToCbor + FromCbor + CborEncode + CborDecode,
+{
+    let mut tree: Vec<u8> = vec![];
+    let n = val.to_cbor().unwrap().encode(&mut tree).unwrap();
+    let mut buf: Vec<u8> = vec![];
+    assert_eq!(val.encode_to(&mut buf).unwrap(), n, "{:?}", val);
+    assert_eq!(buf, tree, "{:?}", val);
+
+    let (dval, m) = T::decode_from(&mut buf.as_slice()).unwrap();
+    assert_eq!((dval, m), (val.clone(), n));
+
+    let (tval, _) = Cbor::decode(&mut tree.as_slice()).unwrap();
+    assert_eq!(T::from_cbor(tval).unwrap(), val);
+}
+
+#[test]
+fn test_codec_scalars() {
+    for val in [
+        0_u64,
+        23,
+        24,
+        255,
+        256,
+        65535,
+        65536,
+        u32::MAX as u64,
+        u64::MAX,
+    ] {
+        check(val);
+    }
+    for val in [0_i64, -1, -24, -25, -256, -257, i64::MIN, i64::MAX] {
+        check(val);
+    }
+    check(u8::MAX);
+    check(u16::MAX);
+    check(i8::MIN);
+    check(i32::MIN);
+    check(usize::MAX);
+    check(isize::MIN);
+    // -1 - u64::MAX doesn't fit any of the signed types.
+    let val = Cbor::Major1(Info::U64, u64::MAX);
+    assert!(i64::from_cbor(val.clone()).is_err());
+    assert!(isize::from_cbor(val).is_err());
+    check(u128::MAX);
+    check(i128::MIN);
+    check(true);
+    check(false);
+    check(10.5_f32);
+    check(-10.5_f64);
+    check(());
+    check('ह');
+    check("hello world".to_string());
+    check(String::new());
+    check("x".repeat(300));
+
+    // f16 is accepted for f32 and f64.
+    let buf = [0xf9, 0x3c, 0x00];
+    assert_eq!(f32::decode_from(&mut &buf[..]).unwrap(), (1.0, 3));
+    assert_eq!(f64::decode_from(&mut &buf[..]).unwrap(), (1.0, 3));
+
+    // f32, from canonical encoding, is accepted for f64.
+    let mut buf: Vec<u8> = vec![];
+    100000.0_f64
+        .into_cbor()
+        .unwrap()
+        .encode_canonical(&mut buf)
+        .unwrap();
+    assert_eq!(buf, [0xfa, 0x47, 0xc3, 0x50, 0x00]);
+    assert_eq!(f64::decode_from(&mut &buf[..]).unwrap(), (100000.0, 5));
+
+    // bytes.
+    let data: &[u8] = b"hello";
+    let mut buf: Vec<u8> = vec![];
+    data.encode_to(&mut buf).unwrap();
+    let mut tree: Vec<u8> = vec![];
+    data.into_cbor().unwrap().encode(&mut tree).unwrap();
+    assert_eq!(buf, tree);
+}
+
+#[test]
+fn test_codec_containers() {
+    check(vec![1_u64, 2, 3]);
+    check(Vec::<String>::new());
+    check(vec![vec![-1_i32], vec![], vec![1, 2]]);
+    check([10_u16, 20, 30]);
+    check(Some(10_u8));
+    check(Option::<u8>::None);
+    check(Box::new("boxed".to_string()));
+    check(Arc::new(vec![true, false]));
+    check(Rc::new(Some(1.5_f64)));
+    check((1_u8,));
+    check((1_u8, "two".to_string(), Some(-3_i16)));
+    check((
+        1_u8,
+        2_u16,
+        3_u32,
+        4_u64,
+        5_i8,
+        6_i16,
+        7_i32,
+        8_i64,
+        9_usize,
+        (),
+        'a',
+        true,
+    ));
+
+    // via tree.
+    let mut map = BTreeMap::new();
+    map.insert("b".to_string(), vec![2_u64]);
+    map.insert("a".to_string(), vec![]);
+    check(map);
+    check([1_u64, 2, 3].iter().cloned().collect::<HashSet<u64>>());
+    check(Uuid::from_bytes([7; 16]));
+    check(time::Duration::new(10, 20));
+    check(result::Result::<u64, String>::Err("fail".to_string()));
+    check(vec![Key::U64(10).to_cbor().unwrap()]);
+
+    // indefinite encoding.
+    let items = vec![1_u64.into_cbor().unwrap(), 2_u64.into_cbor().unwrap()];
+    let mut buf: Vec<u8> = vec![];
+    Cbor::Major4(Info::Indefinite, items)
+        .encode(&mut buf)
+        .unwrap();
+    let val = Vec::<u64>::decode_from(&mut buf.as_slice()).unwrap();
+    assert_eq!(val, (vec![1, 2], buf.len()));
+    let val = <(u8, u8)>::decode_from(&mut buf.as_slice()).unwrap();
+    assert_eq!(val, ((1, 2), buf.len()));
+    assert!(<(u8, u8, u8)>::decode_from(&mut buf.as_slice()).is_err());
+
+    let buf = b"\x7f\x62ab\x61c\xff";
+    let val = String::decode_from(&mut &buf[..]).unwrap();
+    assert_eq!(val, ("abc".to_string(), buf.len()));
+
+    // mismatch and truncated input.
+    let mut buf: Vec<u8> = vec![];
+    vec![1_u64, 2, 3].encode_to(&mut buf).unwrap();
+    assert!(<[u64; 2]>::decode_from(&mut buf.as_slice()).is_err());
+    assert!(<(u64, u64)>::decode_from(&mut buf.as_slice()).is_err());
+    assert!(String::decode_from(&mut buf.as_slice()).is_err());
+    assert!(Vec::<u64>::decode_from(&mut &buf[..3]).is_err());
+    assert!(u8::decode_from(&mut &b"\x19\x01\x00"[..]).is_err());
+    assert!(i8::decode_from(&mut &b"\x38\x80"[..]).is_err());
+    assert!(bool::decode_from(&mut &b"\xf6"[..]).is_err());
+}
+
+#[test]
+fn test_codec_derive() {
+    let mut tags = BTreeMap::new();
+    tags.insert("z".to_string(), 1);
+    let canvas = Canvas {
+        name: "canvas".to_string(),
+        blob: vec![1, 2, 3],
+        shapes: vec![
+            Shape::Empty,
+            Shape::Point(-1, 1),
+            Shape::Named {
+                label: "x".to_string(),
+                data: vec![0xff; 30],
+                n: None,
+            },
+        ],
+        scale: (1.5, -2.5),
+        tags,
+    };
+    check(canvas.clone());
+    check(Shape::<u8>::Named {
+        label: "y".to_string(),
+        data: vec![],
+        n: Some(10),
+    });
+
+    // indefinite encoding of the message.
+    let val = canvas.to_cbor().unwrap();
+    let items = match val {
+        Cbor::Major4(_, items) => items,
+        _ => unreachable!(),
+    };
+    let mut buf: Vec<u8> = vec![];
+    Cbor::Major4(Info::Indefinite, items.clone())
+        .encode(&mut buf)
+        .unwrap();
+    let val = Canvas::decode_from(&mut buf.as_slice()).unwrap();
+    assert_eq!(val, (canvas, buf.len()));
+
+    // bad arity and bad identifier.
+    let mut buf: Vec<u8> = vec![];
+    items[..5]
+        .to_vec()
+        .into_cbor()
+        .unwrap()
+        .encode(&mut buf)
+        .unwrap();
+    assert!(Canvas::decode_from(&mut buf.as_slice()).is_err());
+
+    let mut bad = items;
+    bad[0] = Tag::from_identifier(11_u32.into_cbor().unwrap()).into();
+    let mut buf: Vec<u8> = vec![];
+    bad.into_cbor().unwrap().encode(&mut buf).unwrap();
+    assert!(Canvas::decode_from(&mut buf.as_slice()).is_err());
+
+    let mut buf: Vec<u8> = vec![];
+    let val = vec![
+        Tag::from_identifier(Shape::<u8>::ID.into_cbor().unwrap()).into(),
+        "Point".into_cbor().unwrap(),
+        1_u64.into_cbor().unwrap(),
+    ];
+    val.into_cbor().unwrap().encode(&mut buf).unwrap();
+    assert!(Shape::<u8>::decode_from(&mut buf.as_slice()).is_err());
+}
+
+#[test]
+fn test_codec_entry() {
+    let seed: u128 = random();
+    println!("test_codec_entry {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    for _ in 0..100 {
+        let value = match rng.gen::<u8>() % 4 {
+            0 => Value::D { seqno: rng.gen() },
+            _ => Value::U {
+                value: rng.gen::<u64>(),
+                seqno: rng.gen(),
+            },
+        };
+        let deltas = (0..(rng.gen::<usize>() % 4))
+            .map(|_| match rng.gen::<bool>() {
+                true => Delta::U {
+                    delta: rng.gen::<i64>(),
+                    seqno: rng.gen(),
+                },
+                false => Delta::D { seqno: rng.gen() },
+            })
+            .collect();
+        let entry: Entry<String, u64, i64> = Entry {
+            key: format!("key-{}", rng.gen::<u32>()),
+            value,
+            deltas,
+        };
+        check(entry);
+    }
+}
@@ -0,0 +1,241 @@
+//! Module implement an incremental, sans-IO, decoder for CBOR data.
+//!
+//! [Cbor::decode] pulls bytes from a blocking reader, and a short read
+//! fails the decoding. [CborDecoder] is instead pushed with bytes as and
+//! when they arrive, say from a non-blocking socket, and tells whether
+//! the data item is complete or more bytes are needed. It does not do
+//! any IO by itself, hence can be driven by any event loop or transport.
+
+use std::convert::TryFrom;
+
+use crate::{
+    cbor::{decode_addnl, Cbor, DecodeOptions, Info},
+    Error, Result,
+};
+
+/// Outcome of pushing bytes into [CborDecoder].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Progress {
+    /// Data item is incomplete, at least these many more bytes are needed.
+    NeedMore(usize),
+    /// Data item is complete, along with the number of bytes consumed from
+    /// the latest input. Unconsumed bytes belong to the next data item.
+    Complete(Cbor, usize),
+}
+
+// Open container, tag or indefinite length string, whose items are yet
+// to be scanned.
+#[derive(Debug)]
+enum Frame {
+    // number of remaining items, for maps it is twice the number of entries.
+    Definite(u64),
+    // list or map, terminated by break.
+    Indefinite,
+    // chunks of byte-string or text-string, terminated by break.
+    Chunks(u8),
+}
+
+/// Incremental decoder, to decode one data item at a time from bytes that
+/// arrive in arbitrary chunks.
+///
+/// Bytes are buffered until the data item is complete, while its structure
+/// is scanned as they arrive, so that each byte is scanned only once. Once
+/// complete, the data item is decoded using [Cbor::decode_with] and the
+/// decoder is ready for the next data item.
+///
+/// Limits in [DecodeOptions] are enforced while scanning, hence a data item
+/// exceeding them fails early, without buffering all of its bytes.
+pub struct CborDecoder {
+    opts: DecodeOptions,
+    buf: Vec<u8>,
+    // offset into buf, upto which the data item is scanned.
+    pos: usize,
+    stack: Vec<Frame>,
+}
+
+impl Default for CborDecoder {
+    fn default() -> CborDecoder {
+        CborDecoder::new()
+    }
+}
+
+impl CborDecoder {
+    /// Create a new decoder, with default [DecodeOptions].
+    pub fn new() -> CborDecoder {
+        CborDecoder::with_options(DecodeOptions::default())
+    }
+
+    /// Create a new decoder, data items are decoded with `opts`.
+    pub fn with_options(opts: DecodeOptions) -> CborDecoder {
+        CborDecoder {
+            opts,
+            buf: Vec::default(),
+            pos: 0,
+            stack: Vec::default(),
+        }
+    }
+
+    /// Return the number of bytes buffered for the incomplete data item.
+    pub fn to_buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Discard the incomplete data item, if any.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.pos = 0;
+        self.stack.clear();
+    }
+
+    /// Push the next chunk of bytes. On [Progress::Complete], bytes beyond
+    /// the consumed count shall be pushed again, for the next data item.
+    /// On error, the incomplete data item is discarded.
+    pub fn feed(&mut self, data: &[u8]) -> Result<Progress> {
+        let m = self.buf.len();
+        self.buf.extend_from_slice(data);
+
+        let res = match self.scan() {
+            Ok(Some(n)) => Ok(Progress::NeedMore(n)),
+            Ok(None) => {
+                let consumed = self.pos - m;
+                let res = Cbor::decode_with(&mut &self.buf[..self.pos], self.opts);
+                res.map(|(val, _)| Progress::Complete(val, consumed))
+            }
+            Err(err) => Err(err),
+        };
+
+        match &res {
+            Ok(Progress::NeedMore(_)) if self.buf.len() > self.opts.max_size => {
+                let (limit, len) = (self.opts.max_size, self.buf.len());
+                self.reset();
+                err_at!(FailCbor, msg: "decode max_size {} exceeded: {}", limit, len)
+            }
+            Ok(Progress::NeedMore(_)) => res,
+            Ok(Progress::Complete(_, _)) | Err(_) => {
+                self.reset();
+                res
+            }
+        }
+    }
+
+    // Scan buffered bytes, return None if data item is complete, else
+    // minimum number of bytes needed to make progress.
+    fn scan(&mut self) -> Result<Option<usize>> {
+        loop {
+            if let Some(n) = self.scan_item()? {
+                return Ok(Some(n));
+            }
+            while let Some(Frame::Definite(0)) = self.stack.last() {
+                self.stack.pop();
+            }
+            if self.stack.is_empty() {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Scan the next header, and string payload if any. Return the number
+    // of bytes needed, without updating the state, if buffer is short.
+    fn scan_item(&mut self) -> Result<Option<usize>> {
+        let avail = self.buf.len() - self.pos;
+
+        let (major, info) = match self.buf.get(self.pos) {
+            Some(b) => (b >> 5, Info::try_from(b & 0x1f)?),
+            None => return Ok(Some(1)),
+        };
+        let a = match info {
+            Info::Tiny(_) | Info::Indefinite => 0,
+            Info::U8 => 1,
+            Info::U16 => 2,
+            Info::U32 => 4,
+            Info::U64 => 8,
+            _ => err_at!(FailCbor, msg: "reserved additional info {:?}", info)?,
+        };
+        if avail < 1 + a {
+            return Ok(Some(1 + a - avail));
+        }
+        let num = match major {
+            7 => 0,
+            _ => decode_addnl(info, &mut &self.buf[self.pos + 1..])?.0,
+        };
+
+        let n = match (major, info) {
+            (2, Info::Indefinite) | (3, Info::Indefinite) => 1,
+            (2, _) | (3, _) => {
+                if num > (self.opts.max_string as u64) {
+                    let limit = self.opts.max_string;
+                    err_at!(FailCbor, msg: "decode max_string {} exceeded: {}", limit, num)?
+                }
+                let n = err_at!(FailConvert, usize::try_from(num))?;
+                match (1 + a).checked_add(n) {
+                    Some(n) if avail < n => return Ok(Some(n - avail)),
+                    Some(n) => n,
+                    None => err_at!(FailCbor, msg: "string length {} overflow", num)?,
+                }
+            }
+            (_, _) => 1 + a,
+        };
+
+        match (self.stack.last_mut(), major, info) {
+            (Some(Frame::Chunks(_)), 7, Info::Indefinite) => {
+                self.stack.pop();
+            }
+            (Some(Frame::Chunks(m)), major, info) if *m == major => {
+                if let Info::Indefinite = info {
+                    err_at!(FailCbor, msg: "nested chunk at {}", self.pos)?
+                }
+            }
+            (Some(Frame::Chunks(_)), _, _) => {
+                err_at!(FailCbor, msg: "expected chunk at {}", self.pos)?
+            }
+            (Some(Frame::Indefinite), 7, Info::Indefinite) => {
+                self.stack.pop();
+            }
+            (_, 7, Info::Indefinite) => {
+                err_at!(FailCbor, msg: "unexpected break at {}", self.pos)?
+            }
+            (Some(Frame::Definite(k)), _, _) => *k -= 1,
+            (Some(Frame::Indefinite), _, _) | (None, _, _) => (),
+        }
+
+        let frame = match (major, info) {
+            (2, Info::Indefinite) | (3, Info::Indefinite) => Some(Frame::Chunks(major)),
+            (4, Info::Indefinite) | (5, Info::Indefinite) => Some(Frame::Indefinite),
+            (4, _) => {
+                self.check_items(num)?;
+                Some(Frame::Definite(num))
+            }
+            (5, _) => {
+                self.check_items(num)?;
+                match num.checked_mul(2) {
+                    Some(k) => Some(Frame::Definite(k)),
+                    None => err_at!(FailCbor, msg: "map length {} overflow", num)?,
+                }
+            }
+            (6, _) => Some(Frame::Definite(1)),
+            (_, _) => None,
+        };
+        if let Some(frame) = frame {
+            if self.stack.len() >= (self.opts.max_depth as usize) {
+                let (limit, len) = (self.opts.max_depth, self.stack.len() + 1);
+                err_at!(FailCbor, msg: "decode max_depth {} exceeded: {}", limit, len)?
+            }
+            self.stack.push(frame);
+        }
+
+        self.pos += n;
+        Ok(None)
+    }
+
+    fn check_items(&self, num: u64) -> Result<()> {
+        if num > (self.opts.max_items as u64) {
+            let limit = self.opts.max_items;
+            err_at!(FailCbor, msg: "decode max_items {} exceeded: {}", limit, num)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_decoder_test.rs"]
+mod cbor_decoder_test;
@@ -0,0 +1,149 @@
+use arbitrary::Unstructured;
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+
+use crate::cbor::IntoCbor;
+
+#[test]
+fn test_decoder_chunks() {
+    let seed: u128 = random();
+    println!("test_decoder_chunks {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut dec = CborDecoder::new();
+    for _ in 0..1000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+        let mut buf: Vec<u8> = vec![];
+        val.encode(&mut buf).unwrap();
+
+        // feed in random sized chunks.
+        let mut off = 0;
+        loop {
+            let n = std::cmp::min(rng.gen::<usize>() % 16, buf.len() - off);
+            match dec.feed(&buf[off..off + n]).unwrap() {
+                Progress::NeedMore(k) => {
+                    assert!(k > 0);
+                    assert!(off + n + k <= buf.len(), "{} {} {}", off, n, k);
+                    off += n;
+                }
+                Progress::Complete(nval, m) => {
+                    assert_eq!(off + m, buf.len());
+                    assert_eq!(nval, val);
+                    break;
+                }
+            }
+        }
+        assert_eq!(dec.to_buffered(), 0);
+    }
+}
+
+#[test]
+fn test_decoder_progress() {
+    // ["hello", 1] followed by the next item.
+    let buf = b"\x82\x65hello\x01\x18\x64";
+
+    let mut dec = CborDecoder::new();
+    assert_eq!(dec.feed(&[]).unwrap(), Progress::NeedMore(1));
+    assert_eq!(dec.feed(&buf[..1]).unwrap(), Progress::NeedMore(1));
+    assert_eq!(dec.feed(&buf[1..3]).unwrap(), Progress::NeedMore(4));
+    assert_eq!(dec.to_buffered(), 3);
+    assert_eq!(dec.feed(&buf[3..7]).unwrap(), Progress::NeedMore(1));
+    let val = vec!["hello".into_cbor().unwrap(), 1_u64.into_cbor().unwrap()];
+    let val = val.into_cbor().unwrap();
+    assert_eq!(dec.feed(&buf[7..]).unwrap(), Progress::Complete(val, 1));
+    assert_eq!(dec.to_buffered(), 0);
+
+    // next item, header needs one more byte.
+    assert_eq!(dec.feed(&buf[8..9]).unwrap(), Progress::NeedMore(1));
+    let val = 100_u64.into_cbor().unwrap();
+    assert_eq!(dec.feed(&buf[9..]).unwrap(), Progress::Complete(val, 1));
+
+    // indefinite containers and strings.
+    let buf = b"\x9f\x5f\x41\x01\x42\x02\x03\xff\xbf\x61a\x01\xff\xff";
+    for i in 1..buf.len() {
+        assert!(matches!(
+            dec.feed(&buf[i - 1..i]),
+            Ok(Progress::NeedMore(_))
+        ));
+    }
+    match dec.feed(&buf[buf.len() - 1..]).unwrap() {
+        Progress::Complete(val, 1) => {
+            let mut out: Vec<u8> = vec![];
+            val.encode(&mut out).unwrap();
+            assert_eq!(out, b"\x9f\x5f\x43\x01\x02\x03\xff\xbf\x61a\x01\xff\xff");
+        }
+        res => panic!("{:?}", res),
+    }
+
+    // reset discards the partial item.
+    assert!(matches!(dec.feed(b"\x82\x01"), Ok(Progress::NeedMore(1))));
+    dec.reset();
+    let val = 1_u64.into_cbor().unwrap();
+    assert_eq!(dec.feed(b"\x01").unwrap(), Progress::Complete(val, 1));
+}
+
+#[test]
+fn test_decoder_errors() {
+    let bad: Vec<&[u8]> = vec![
+        b"\x1c",         // reserved additional info
+        b"\xff",         // break at top level
+        b"\x82\x01\xff", // break in definite list
+        b"\x5f\x61a",    // text chunk in byte-string
+        b"\x5f\x5f",     // nested chunk
+        b"\xc1\xff",     // break after tag
+    ];
+    for buf in bad.into_iter() {
+        let mut dec = CborDecoder::new();
+        assert!(dec.feed(buf).is_err(), "{:?}", buf);
+        assert_eq!(dec.to_buffered(), 0);
+    }
+
+    // limits fail before the item is buffered.
+    let opts = DecodeOptions {
+        max_string: 10,
+        ..DecodeOptions::default()
+    };
+    let mut dec = CborDecoder::with_options(opts);
+    assert!(dec.feed(b"\x5a\x00\x01\x00\x00").is_err());
+
+    let opts = DecodeOptions {
+        max_items: 10,
+        ..DecodeOptions::default()
+    };
+    let mut dec = CborDecoder::with_options(opts);
+    assert!(dec.feed(b"\x9a\x00\x01\x00\x00").is_err());
+    assert!(dec.feed(b"\xba\x00\x01\x00\x00").is_err());
+
+    let opts = DecodeOptions {
+        max_depth: 3,
+        ..DecodeOptions::default()
+    };
+    let mut dec = CborDecoder::with_options(opts);
+    match dec.feed(b"\x81\x81\x81\x81") {
+        Err(Error::FailCbor(_, err)) => {
+            assert!(err.contains("decode max_depth 3 exceeded: 4"), "{}", err)
+        }
+        res => panic!("{:?}", res),
+    }
+
+    let opts = DecodeOptions {
+        max_size: 4,
+        ..DecodeOptions::default()
+    };
+    let mut dec = CborDecoder::with_options(opts);
+    assert!(matches!(dec.feed(b"\x9f\x01"), Ok(Progress::NeedMore(1))));
+    match dec.feed(b"\x02\x03\x04") {
+        Err(Error::FailCbor(_, err)) => {
+            assert!(err.contains("decode max_size 4 exceeded: 5"), "{}", err)
+        }
+        res => panic!("{:?}", res),
+    }
+    assert_eq!(dec.to_buffered(), 0);
+}
@@ -0,0 +1,444 @@
+//! Module implement CBOR diagnostic notation, refer to [RFC 8949][rfc] §8.
+//!
+//! [Cbor] values render as human readable text, like
+//! `[1, h'0a0b', {"k": 2.5}, 39("id")]`, through its [fmt::Display]
+//! implementation, and text can be parsed back using [Cbor::from_diagnostic].
+//!
+//! Floating point values carry an encoding indicator for their width,
+//! `1.5_1` is half-precision, `1.5_2` is single-precision, and float
+//! literals without an indicator are double-precision. Integers, strings
+//! and containers are always rendered and parsed in their shortest form.
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc8949.html#section-8
+
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use crate::{
+    cbor::{
+        cbor_lexer::{Lexer, Syntax},
+        f16_to_f64, f64_to_f16, Cbor, FromCbor, Info, IntoCbor, Key, SimpleValue, Tag,
+        RECURSION_LIMIT,
+    },
+    Error, Result,
+};
+
+impl fmt::Display for Cbor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cbor::Major0(_, val) => write!(f, "{}", val),
+            Cbor::Major1(_, val) => write!(f, "{}", -i128::from(*val) - 1),
+            Cbor::Major2(Info::Indefinite, val) => {
+                write!(f, "(_ ")?;
+                write_bytes(f, val)?;
+                write!(f, ")")
+            }
+            Cbor::Major2(_, val) => write_bytes(f, val),
+            Cbor::Major3(Info::Indefinite, val) => {
+                write!(f, "(_ ")?;
+                write_text(f, &String::from_utf8_lossy(val))?;
+                write!(f, ")")
+            }
+            Cbor::Major3(_, val) => write_text(f, &String::from_utf8_lossy(val)),
+            Cbor::Major4(info, list) => {
+                match info {
+                    Info::Indefinite => write!(f, "[_ ")?,
+                    _ => write!(f, "[")?,
+                }
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Cbor::Major5(info, map) => {
+                match info {
+                    Info::Indefinite => write!(f, "{{_ ")?,
+                    _ => write!(f, "{{")?,
+                }
+                for (i, (key, val)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, val)?;
+                }
+                write!(f, "}}")
+            }
+            Cbor::Major6(_, tag) => {
+                write!(f, "{}({})", tag.to_tag_value(), tag.as_item())
+            }
+            Cbor::Major7(_, sval) => write_simple(f, sval),
+            Cbor::Binary(data) => match Cbor::decode(&mut data.as_slice()) {
+                Ok((val, _)) => write!(f, "{}", val),
+                Err(_) => write_bytes(f, data),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Bool(val) => write!(f, "{}", val),
+            Key::N64(val) => write!(f, "{}", val),
+            Key::U64(val) => write!(f, "{}", val),
+            Key::F32(val) => write_f32(f, *val),
+            Key::F64(val) => write_float(f, *val, ""),
+            Key::Bytes(val) => write_bytes(f, val),
+            Key::Text(val) => write_text(f, val),
+        }
+    }
+}
+
+fn write_simple(f: &mut fmt::Formatter, sval: &SimpleValue) -> fmt::Result {
+    use SimpleValue::*;
+
+    match sval {
+        Unassigned => write!(f, "/unassigned/"),
+        True => write!(f, "true"),
+        False => write!(f, "false"),
+        Null => write!(f, "null"),
+        Undefined => write!(f, "undefined"),
+        Reserved24(num) => write!(f, "simple({})", num),
+        F16(val) => write_float(f, f16_to_f64(*val), "_1"),
+        F32(val) => write_f32(f, *val),
+        F64(val) => write_float(f, *val, ""),
+        Break => write!(f, "/break/"),
+    }
+}
+
+fn write_f32(f: &mut fmt::Formatter, val: f32) -> fmt::Result {
+    match val.is_finite() {
+        // shortest text that round-trips through f32.
+        true => write!(f, "{:?}_2", val),
+        false => write_float(f, f64::from(val), "_2"),
+    }
+}
+
+fn write_float(f: &mut fmt::Formatter, val: f64, suffix: &str) -> fmt::Result {
+    if val.is_nan() {
+        write!(f, "NaN{}", suffix)
+    } else if val.is_infinite() && val > 0.0 {
+        write!(f, "Infinity{}", suffix)
+    } else if val.is_infinite() {
+        write!(f, "-Infinity{}", suffix)
+    } else {
+        write!(f, "{:?}{}", val, suffix)
+    }
+}
+
+fn write_bytes(f: &mut fmt::Formatter, val: &[u8]) -> fmt::Result {
+    write!(f, "h'")?;
+    for b in val.iter() {
+        write!(f, "{:02x}", b)?;
+    }
+    write!(f, "'")
+}
+
+fn write_text(f: &mut fmt::Formatter, val: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in val.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if ch.is_control() => {
+                let mut buf = [0_u16; 2];
+                for unit in ch.encode_utf16(&mut buf).iter() {
+                    write!(f, "\\u{:04x}", unit)?;
+                }
+            }
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Cbor {
+    /// Render this value in CBOR diagnostic notation, same as its
+    /// [fmt::Display] implementation.
+    pub fn to_diagnostic(&self) -> String {
+        self.to_string()
+    }
+
+    /// Parse a single data item from CBOR diagnostic notation. Comments,
+    /// enclosed within `/`, are treated as white-space.
+    pub fn from_diagnostic(text: &str) -> Result<Cbor> {
+        let mut p = Parser {
+            lex: Lexer::new(text, Syntax::Diagnostic),
+        };
+        let val = p.parse_item(1)?;
+        p.lex.skip_ws()?;
+        match p.lex.peek() {
+            None => Ok(val),
+            Some(_) => p.lex.error("unexpected trailing text"),
+        }
+    }
+}
+
+struct Parser<'a> {
+    lex: Lexer<'a>,
+}
+
+impl<'a> Parser<'a> {
+    // consume `_` for indefinite length items.
+    fn indefinite(&mut self) -> bool {
+        match self.lex.peek() {
+            Some(b'_') => {
+                self.lex.off += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_item(&mut self, depth: u32) -> Result<Cbor> {
+        if depth > RECURSION_LIMIT {
+            return self.lex.error("recursion limit exceeded");
+        }
+
+        self.lex.skip_ws()?;
+        match self.lex.peek() {
+            Some(b'[') => {
+                self.lex.off += 1;
+                let indef = self.indefinite();
+                let mut list: Vec<Cbor> = vec![];
+                while !self.lex.close(b']', list.is_empty())? {
+                    list.push(self.parse_item(depth + 1)?);
+                }
+                let info = match indef {
+                    true => Info::Indefinite,
+                    false => err_at!(FailConvert, Info::try_from(list.len()))?,
+                };
+                Ok(Cbor::Major4(info, list))
+            }
+            Some(b'{') => {
+                self.lex.off += 1;
+                let indef = self.indefinite();
+                let mut map: Vec<(Key, Cbor)> = vec![];
+                while !self.lex.close(b'}', map.is_empty())? {
+                    let key = Key::from_cbor(self.parse_item(depth + 1)?)?;
+                    self.lex.expect(b':')?;
+                    map.push((key, self.parse_item(depth + 1)?));
+                }
+                let info = match indef {
+                    true => Info::Indefinite,
+                    false => err_at!(FailConvert, Info::try_from(map.len()))?,
+                };
+                Ok(Cbor::Major5(info, map))
+            }
+            Some(b'(') => {
+                self.lex.off += 1;
+                if !self.indefinite() {
+                    return self.lex.error("expected indefinite string");
+                }
+                self.parse_chunks(depth)
+            }
+            Some(b'"') => self.parse_text(),
+            Some(b'h') if self.lex.rest().starts_with("h'") => self.parse_bytes(),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(depth),
+            Some(b'N') | Some(b'I') => self.parse_number(depth),
+            Some(b'a'..=b'z') => self.parse_keyword(),
+            Some(_) => self.lex.error("unexpected character"),
+            None => self.lex.error("unexpected end of text"),
+        }
+    }
+
+    fn parse_chunks(&mut self, depth: u32) -> Result<Cbor> {
+        let mut chunks: Vec<Cbor> = vec![];
+        while !self.lex.close(b')', chunks.is_empty())? {
+            chunks.push(self.parse_item(depth + 1)?);
+        }
+
+        let mut iter = chunks.into_iter();
+        match iter.next() {
+            Some(Cbor::Major2(_, mut data)) => {
+                for chunk in iter {
+                    match chunk {
+                        Cbor::Major2(_, val) => data.extend_from_slice(&val),
+                        _ => self.lex.error("expected byte chunk")?,
+                    }
+                }
+                Ok(Cbor::Major2(Info::Indefinite, data))
+            }
+            Some(Cbor::Major3(_, mut text)) => {
+                for chunk in iter {
+                    match chunk {
+                        Cbor::Major3(_, val) => text.extend_from_slice(&val),
+                        _ => self.lex.error("expected text chunk")?,
+                    }
+                }
+                Ok(Cbor::Major3(Info::Indefinite, text))
+            }
+            _ => self.lex.error("expected string chunk"),
+        }
+    }
+
+    fn parse_text(&mut self) -> Result<Cbor> {
+        let text = self.lex.parse_text()?;
+        let n = err_at!(FailConvert, Info::try_from(text.len()))?;
+        Ok(Cbor::Major3(n, text.into_bytes()))
+    }
+
+    fn parse_bytes(&mut self) -> Result<Cbor> {
+        self.lex.off += 2;
+
+        let hex: Vec<u8> = match self.lex.rest().find('\'') {
+            Some(n) => {
+                let hex = self.lex.rest()[..n]
+                    .bytes()
+                    .filter(|b| !b.is_ascii_whitespace());
+                self.lex.off += n + 1;
+                hex.collect()
+            }
+            None => self.lex.error("unterminated byte string")?,
+        };
+        let mut data = Vec::with_capacity(hex.len() / 2);
+        for pair in hex.chunks(2) {
+            if pair.len() != 2 {
+                return self.lex.error("odd number of hex digits");
+            }
+            let s = std::str::from_utf8(pair).unwrap_or("");
+            match u8::from_str_radix(s, 16) {
+                Ok(b) => data.push(b),
+                Err(_) => self.lex.error("invalid hex digit")?,
+            }
+        }
+
+        let n = err_at!(FailConvert, Info::try_from(data.len()))?;
+        Ok(Cbor::Major2(n, data))
+    }
+
+    fn parse_keyword(&mut self) -> Result<Cbor> {
+        let n = self
+            .lex
+            .rest()
+            .bytes()
+            .take_while(|b| b.is_ascii_lowercase())
+            .count();
+        let word = &self.lex.rest()[..n];
+        self.lex.off += n;
+
+        let val = match word {
+            "true" => SimpleValue::True.into_cbor()?,
+            "false" => SimpleValue::False.into_cbor()?,
+            "null" => SimpleValue::Null.into_cbor()?,
+            "undefined" => Cbor::Major7(Info::Tiny(23), SimpleValue::Undefined),
+            "simple" => {
+                self.lex.expect(b'(')?;
+                self.lex.skip_ws()?;
+                let n = self
+                    .lex
+                    .rest()
+                    .bytes()
+                    .take_while(u8::is_ascii_digit)
+                    .count();
+                let num = match u8::from_str(&self.lex.rest()[..n]) {
+                    Ok(num) if num >= 32 => num,
+                    _ => self.lex.error("invalid simple value")?,
+                };
+                self.lex.off += n;
+                self.lex.expect(b')')?;
+                Cbor::Major7(Info::U8, SimpleValue::Reserved24(num))
+            }
+            _ => self.lex.error("unknown keyword")?,
+        };
+
+        Ok(val)
+    }
+
+    fn parse_number(&mut self, depth: u32) -> Result<Cbor> {
+        let start = self.lex.off;
+        let neg = self.lex.peek() == Some(b'-');
+        if neg {
+            self.lex.off += 1;
+        }
+
+        let (text, is_float) = if self.lex.rest().starts_with("NaN") {
+            self.lex.off += 3;
+            ("NaN", true)
+        } else if self.lex.rest().starts_with("Infinity") {
+            self.lex.off += 8;
+            (&self.lex.text[start..self.lex.off], true)
+        } else {
+            let mut is_float = false;
+            let bytes = self.lex.text.as_bytes();
+            while let Some(b) = bytes.get(self.lex.off) {
+                match b {
+                    b'0'..=b'9' => (),
+                    b'.' => is_float = true,
+                    b'e' | b'E' => {
+                        is_float = true;
+                        if let Some(b'+') | Some(b'-') = bytes.get(self.lex.off + 1) {
+                            self.lex.off += 1;
+                        }
+                    }
+                    _ => break,
+                }
+                self.lex.off += 1;
+            }
+            (&self.lex.text[start..self.lex.off], is_float)
+        };
+
+        let width = match (self.lex.peek(), self.lex.rest().as_bytes().get(1)) {
+            (Some(b'_'), Some(b @ b'0'..=b'3')) => {
+                self.lex.off += 2;
+                Some(b - b'0')
+            }
+            _ => None,
+        };
+
+        if is_float {
+            let val = match width {
+                Some(1) => match f64::from_str(text) {
+                    Ok(val) => SimpleValue::F16(f64_to_f16(val)),
+                    Err(_) => self.lex.error("invalid float")?,
+                },
+                Some(2) => match f32::from_str(text) {
+                    Ok(val) => SimpleValue::F32(val),
+                    Err(_) => self.lex.error("invalid float")?,
+                },
+                None | Some(3) => match f64::from_str(text) {
+                    Ok(val) => SimpleValue::F64(val),
+                    Err(_) => self.lex.error("invalid float")?,
+                },
+                Some(_) => self.lex.error("invalid float width")?,
+            };
+            return val.into_cbor();
+        } else if width.is_some() {
+            return self.lex.error("encoding indicator on integer");
+        }
+
+        let num = match u128::from_str(&text[neg as usize..]) {
+            Ok(num) => num,
+            Err(_) => self.lex.error("invalid integer")?,
+        };
+        let val = match neg {
+            false if self.lex.peek() == Some(b'(') => {
+                let num = err_at!(FailConvert, u64::try_from(num))?;
+                self.lex.off += 1;
+                let item = self.parse_item(depth + 1)?;
+                self.lex.expect(b')')?;
+                Tag::from_value(num, item).into()
+            }
+            false => {
+                let num = err_at!(FailConvert, u64::try_from(num))?;
+                Cbor::Major0(num.into(), num)
+            }
+            true if num == 0 => Cbor::Major0(Info::Tiny(0), 0),
+            true => {
+                let num = err_at!(FailConvert, u64::try_from(num - 1))?;
+                Cbor::Major1(num.into(), num)
+            }
+        };
+
+        Ok(val)
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_diag_test.rs"]
+mod cbor_diag_test;
@@ -0,0 +1,186 @@
+use arbitrary::Unstructured;
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+
+#[test]
+fn test_diagnostic() {
+    // examples from RFC 8949, appendix A.
+    let testcases: Vec<(&str, Vec<u8>)> = vec![
+        ("0", vec![0x00]),
+        ("23", vec![0x17]),
+        ("1000000", vec![0x1a, 0x00, 0x0f, 0x42, 0x40]),
+        (
+            "18446744073709551615",
+            vec![0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ),
+        ("-1", vec![0x20]),
+        ("-1000", vec![0x39, 0x03, 0xe7]),
+        (
+            "-18446744073709551616",
+            vec![0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ),
+        ("-0.0_1", vec![0xf9, 0x80, 0x00]),
+        ("1.5_1", vec![0xf9, 0x3e, 0x00]),
+        ("100000.0_2", vec![0xfa, 0x47, 0xc3, 0x50, 0x00]),
+        (
+            "1.1",
+            vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a],
+        ),
+        (
+            "-4.1",
+            vec![0xfb, 0xc0, 0x10, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66],
+        ),
+        ("Infinity_1", vec![0xf9, 0x7c, 0x00]),
+        ("NaN_1", vec![0xf9, 0x7e, 0x00]),
+        ("-Infinity_2", vec![0xfa, 0xff, 0x80, 0x00, 0x00]),
+        ("false", vec![0xf5]),
+        ("true", vec![0xf4]),
+        ("null", vec![0xf6]),
+        ("h''", vec![0x40]),
+        ("h'01020304'", vec![0x44, 0x01, 0x02, 0x03, 0x04]),
+        ("\"\"", vec![0x60]),
+        ("\"\\\"\\\\\"", vec![0x62, 0x22, 0x5c]),
+        ("\"\u{fc}\"", vec![0x62, 0xc3, 0xbc]),
+        ("\"\\u0001\"", vec![0x61, 0x01]),
+        ("[]", vec![0x80]),
+        (
+            "[1, [2, 3], [4, 5]]",
+            vec![0x83, 0x01, 0x82, 0x02, 0x03, 0x82, 0x04, 0x05],
+        ),
+        ("{1: 2, 3: 4}", vec![0xa2, 0x01, 0x02, 0x03, 0x04]),
+        (
+            "{\"a\": 1, \"b\": [2, 3]}",
+            vec![0xa2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x02, 0x03],
+        ),
+        ("0(\"2013-03-21T20:04:00Z\")", {
+            let mut buf = vec![0xc0, 0x74];
+            buf.extend_from_slice(b"2013-03-21T20:04:00Z");
+            buf
+        }),
+        ("1(1363896240)", vec![0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]),
+        ("23(h'01020304')", vec![0xd7, 0x44, 0x01, 0x02, 0x03, 0x04]),
+        ("39(\"id\")", vec![0xd8, 0x27, 0x62, 0x69, 0x64]),
+        (
+            "(_ h'0102030405')",
+            vec![0x5f, 0x42, 0x01, 0x02, 0x43, 0x03, 0x04, 0x05, 0xff],
+        ),
+        ("(_ \"streaming\")", {
+            let mut buf = vec![0x7f, 0x65];
+            buf.extend_from_slice(b"strea");
+            buf.push(0x64);
+            buf.extend_from_slice(b"ming");
+            buf.push(0xff);
+            buf
+        }),
+        (
+            "[_ 1, [2, 3], [_ 4, 5]]",
+            vec![0x9f, 0x01, 0x82, 0x02, 0x03, 0x9f, 0x04, 0x05, 0xff, 0xff],
+        ),
+        (
+            "{_ \"a\": 1, \"b\": [_ 2, 3]}",
+            vec![
+                0xbf, 0x61, 0x61, 0x01, 0x61, 0x62, 0x9f, 0x02, 0x03, 0xff, 0xff,
+            ],
+        ),
+    ];
+
+    for (text, buf) in testcases.into_iter() {
+        let (val, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(val.to_diagnostic(), text, "{:?}", buf);
+
+        let val = Cbor::from_diagnostic(text).unwrap();
+        let mut out: Vec<u8> = vec![];
+        val.encode(&mut out).unwrap();
+        let out = match val {
+            // chunks are not preserved, compare the decoded value.
+            Cbor::Major2(Info::Indefinite, _) | Cbor::Major3(Info::Indefinite, _) => {
+                let (ref_val, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+                assert_eq!(val, ref_val, "{}", text);
+                continue;
+            }
+            _ => out,
+        };
+        assert_eq!(out, buf, "{}", text);
+    }
+}
+
+#[test]
+fn test_diagnostic_parse() {
+    let val = Cbor::from_diagnostic(
+        r#" [ 1, / comment / h'0a 0b', {"k": 2.5, -2: "\u00fc\ud83d\ude00\n"},
+            39("id"), 1.5_3, 2.5e-3, -0, simple(32), undefined ] "#,
+    )
+    .unwrap();
+    let text = val.to_diagnostic();
+    assert_eq!(
+        text,
+        "[1, h'0a0b', {\"k\": 2.5, -2: \"\u{fc}\u{1f600}\\n\"}, \
+         39(\"id\"), 1.5, 0.0025, 0, simple(32), undefined]"
+    );
+    assert_eq!(format!("{}", val), text);
+
+    match val {
+        Cbor::Major4(_, items) => {
+            assert_eq!(
+                items[3],
+                Tag::from_identifier("id".into_cbor().unwrap()).into()
+            );
+        }
+        val => panic!("{:?}", val),
+    }
+
+    let errs = [
+        "",
+        "[1, 2",
+        "[1 2]",
+        "{1}",
+        "{[1]: 2}",
+        "h'0'",
+        "h'zz'",
+        "\"abc",
+        "\"\\q\"",
+        "\"\\ud800\"",
+        "1_1",
+        "1.0_4",
+        "-1(2)",
+        "(1)",
+        "(_ h'00', \"a\")",
+        "18446744073709551616",
+        "-18446744073709551617",
+        "simple(10)",
+        "nil",
+        "1 2",
+        "/ abc",
+    ];
+    for text in errs.iter() {
+        assert!(Cbor::from_diagnostic(text).is_err(), "{}", text);
+    }
+
+    // lazily decoded values render their content.
+    let mut buf: Vec<u8> = vec![];
+    "hello".into_cbor().unwrap().encode(&mut buf).unwrap();
+    assert_eq!(Cbor::Binary(buf).to_diagnostic(), "\"hello\"");
+}
+
+#[test]
+fn test_diagnostic_arbitrary() {
+    let seed: u128 = random();
+    println!("test_diagnostic_arbitrary {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    for _ in 0..1000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+
+        // NaN payloads are not preserved, compare the rendered text.
+        let text = val.to_diagnostic();
+        let nval = Cbor::from_diagnostic(&text).unwrap();
+        assert_eq!(nval.to_diagnostic(), text);
+    }
+}
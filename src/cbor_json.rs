@@ -0,0 +1,430 @@
+//! Module implement conversion between [Cbor] values and JSON text, following
+//! the mapping in [RFC 8949][rfc] §6.
+//!
+//! CBOR to JSON conversion is lossy:
+//!
+//! * Byte strings are encoded as base64url text, without padding. Unless
+//!   enclosed by tag 22 (base64) or tag 23 (base16).
+//! * Map keys, that are not text, are stringified.
+//! * NaN and Infinity floating point values are converted to `null`.
+//! * `undefined` is converted to `null`.
+//! * Tags are handled as per [TagMode].
+//!
+//! JSON to CBOR conversion maps integers to major type 0 and 1, when they
+//! fit within 64-bits, integers within 128-bits to bignums, tag 2 and 3,
+//! and all other numbers to double precision floats.
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc8949.html#section-6
+
+use std::{convert::TryFrom, fmt::Write, str::FromStr};
+
+use crate::{
+    cbor::{
+        bignum_to_u128,
+        cbor_lexer::{Lexer, Syntax},
+        f16_to_f64, BigNum, Cbor, Info, IntoCbor, Key, SimpleValue, Tag, RECURSION_LIMIT,
+    },
+    Error, Result,
+};
+
+/// Tag number for expected conversion to base64url.
+const TAG_BASE64URL: u64 = 21;
+/// Tag number for expected conversion to base64.
+const TAG_BASE64: u64 = 22;
+/// Tag number for expected conversion to base16.
+const TAG_BASE16: u64 = 23;
+
+/// How tagged values are converted to and from JSON.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TagMode {
+    /// Tags are dropped and only the enclosed data item is converted.
+    /// Bignums, tag 2 and 3, that fit within 128-bits are converted to
+    /// JSON numbers.
+    Unwrap,
+    /// Tags are converted to JSON object `{"@tag": num, "@value": item}`,
+    /// and such objects are converted back to tags.
+    Object,
+}
+
+/// Options to convert between [Cbor] and JSON.
+#[derive(Clone, Copy, Debug)]
+pub struct JsonOptions {
+    /// Handling of tagged values, default is [TagMode::Unwrap].
+    pub tags: TagMode,
+}
+
+impl Default for JsonOptions {
+    fn default() -> JsonOptions {
+        JsonOptions {
+            tags: TagMode::Unwrap,
+        }
+    }
+}
+
+// Encoding for byte strings, as expected by tags 21, 22 and 23.
+#[derive(Clone, Copy)]
+enum Base {
+    Base64Url,
+    Base64,
+    Base16,
+}
+
+impl Cbor {
+    /// Convert this value into JSON text, using default [JsonOptions].
+    pub fn to_json(&self) -> Result<String> {
+        self.to_json_with(JsonOptions::default())
+    }
+
+    /// Convert this value into JSON text.
+    pub fn to_json_with(&self, opts: JsonOptions) -> Result<String> {
+        let mut out = String::default();
+        write_json(&mut out, self, Base::Base64Url, 1, &opts)?;
+        Ok(out)
+    }
+
+    /// Parse JSON text into Cbor value, using default [JsonOptions].
+    pub fn from_json(text: &str) -> Result<Cbor> {
+        Cbor::from_json_with(text, JsonOptions::default())
+    }
+
+    /// Parse JSON text into Cbor value.
+    pub fn from_json_with(text: &str, opts: JsonOptions) -> Result<Cbor> {
+        let mut p = Parser {
+            lex: Lexer::new(text, Syntax::Json),
+            opts,
+        };
+        let val = p.parse_value(1)?;
+        p.lex.skip_ws()?;
+        match p.lex.peek() {
+            None => Ok(val),
+            Some(_) => p.lex.error("unexpected trailing text"),
+        }
+    }
+}
+
+fn write_json(
+    out: &mut String,
+    val: &Cbor,
+    base: Base,
+    depth: u32,
+    opts: &JsonOptions,
+) -> Result<()> {
+    if depth > RECURSION_LIMIT {
+        return err_at!(FailConvert, msg: "json recursion limit exceeded");
+    }
+
+    match val {
+        Cbor::Major0(_, num) => write!(out, "{}", num).unwrap(),
+        Cbor::Major1(_, num) => write!(out, "{}", -i128::from(*num) - 1).unwrap(),
+        Cbor::Major2(_, data) => write_bytes(out, data, base),
+        Cbor::Major3(_, text) => match std::str::from_utf8(text) {
+            Ok(text) => write_text(out, text),
+            Err(err) => err_at!(FailConvert, msg: "json invalid text {}", err)?,
+        },
+        Cbor::Major4(_, list) => {
+            out.push('[');
+            for (i, item) in list.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json(out, item, base, depth + 1, opts)?;
+            }
+            out.push(']');
+        }
+        Cbor::Major5(_, map) => {
+            out.push('{');
+            for (i, (key, val)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_key(out, key, base);
+                out.push(':');
+                write_json(out, val, base, depth + 1, opts)?;
+            }
+            out.push('}');
+        }
+        Cbor::Major6(_, tag) => {
+            let num = tag.to_tag_value();
+            let ibase = match num {
+                TAG_BASE64URL => Base::Base64Url,
+                TAG_BASE64 => Base::Base64,
+                TAG_BASE16 => Base::Base16,
+                _ => base,
+            };
+            match opts.tags {
+                TagMode::Unwrap => match bignum_to_json(num, tag.as_item()) {
+                    Some(text) => out.push_str(&text),
+                    None => write_json(out, tag.as_item(), ibase, depth + 1, opts)?,
+                },
+                TagMode::Object => {
+                    write!(out, "{{\"@tag\":{},\"@value\":", num).unwrap();
+                    write_json(out, tag.as_item(), ibase, depth + 1, opts)?;
+                    out.push('}');
+                }
+            }
+        }
+        Cbor::Major7(_, sval) => match sval {
+            SimpleValue::True => out.push_str("true"),
+            SimpleValue::False => out.push_str("false"),
+            SimpleValue::F16(val) => write_float(out, f16_to_f64(*val)),
+            SimpleValue::F32(val) if val.is_finite() => write!(out, "{:?}", val).unwrap(),
+            SimpleValue::F32(val) => write_float(out, f64::from(*val)),
+            SimpleValue::F64(val) => write_float(out, *val),
+            SimpleValue::Break => err_at!(FailConvert, msg: "json unexpected break")?,
+            _ => out.push_str("null"),
+        },
+        Cbor::Binary(data) => {
+            let (val, _) = Cbor::decode(&mut data.as_slice())?;
+            write_json(out, &val, base, depth, opts)?
+        }
+    }
+
+    Ok(())
+}
+
+fn bignum_to_json(num: u64, val: &Cbor) -> Option<String> {
+    match (num, val) {
+        (2, Cbor::Major2(_, data)) => Some(bignum_to_u128(data).ok()?.to_string()),
+        (3, Cbor::Major2(_, data)) => {
+            let num = bignum_to_u128(data).ok()?;
+            Some(format!("-{}", u128::checked_add(num, 1)?))
+        }
+        _ => None,
+    }
+}
+
+fn write_key(out: &mut String, key: &Key, base: Base) {
+    match key {
+        Key::Text(key) => write_text(out, key),
+        Key::Bytes(key) => write_bytes(out, key, base),
+        Key::Bool(key) => write!(out, "\"{}\"", key).unwrap(),
+        Key::N64(key) => write!(out, "\"{}\"", key).unwrap(),
+        Key::U64(key) => write!(out, "\"{}\"", key).unwrap(),
+        Key::F32(key) => write!(out, "\"{:?}\"", key).unwrap(),
+        Key::F64(key) => write!(out, "\"{:?}\"", key).unwrap(),
+    }
+}
+
+// non-finite numbers have no JSON representation.
+fn write_float(out: &mut String, val: f64) {
+    match val.is_finite() {
+        true => write!(out, "{:?}", val).unwrap(),
+        false => out.push_str("null"),
+    }
+}
+
+fn write_text(out: &mut String, val: &str) {
+    out.push('"');
+    for ch in val.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => write!(out, "\\u{:04x}", ch as u32).unwrap(),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn write_bytes(out: &mut String, val: &[u8], base: Base) {
+    const URL: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    const STD: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    out.push('"');
+    match base {
+        Base::Base16 => val.iter().for_each(|b| write!(out, "{:02x}", b).unwrap()),
+        Base::Base64Url | Base::Base64 => {
+            let table = match base {
+                Base::Base64 => STD,
+                _ => URL,
+            };
+            for chunk in val.chunks(3) {
+                let mut buf = [0_u8; 3];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                let n = (u32::from(buf[0]) << 16)
+                    | (u32::from(buf[1]) << 8)
+                    | u32::from(buf[2]);
+                let sextets = [(n >> 18) & 63, (n >> 12) & 63, (n >> 6) & 63, n & 63];
+                for s in sextets.iter().take(chunk.len() + 1) {
+                    out.push(table[*s as usize] as char);
+                }
+                // only base64, as against base64url, is padded.
+                if let Base::Base64 = base {
+                    (chunk.len()..3).for_each(|_| out.push('='));
+                }
+            }
+        }
+    }
+    out.push('"');
+}
+
+struct Parser<'a> {
+    lex: Lexer<'a>,
+    opts: JsonOptions,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_value(&mut self, depth: u32) -> Result<Cbor> {
+        if depth > RECURSION_LIMIT {
+            return self.lex.error("recursion limit exceeded");
+        }
+
+        self.lex.skip_ws()?;
+        match self.lex.peek() {
+            Some(b'[') => {
+                self.lex.off += 1;
+                let mut list: Vec<Cbor> = vec![];
+                while !self.lex.close(b']', list.is_empty())? {
+                    list.push(self.parse_value(depth + 1)?);
+                }
+                let info = err_at!(FailConvert, Info::try_from(list.len()))?;
+                Ok(Cbor::Major4(info, list))
+            }
+            Some(b'{') => {
+                self.lex.off += 1;
+                let mut map: Vec<(Key, Cbor)> = vec![];
+                while !self.lex.close(b'}', map.is_empty())? {
+                    self.lex.skip_ws()?;
+                    if self.lex.peek() != Some(b'"') {
+                        return self.lex.error("expected text key");
+                    }
+                    let key = Key::Text(self.lex.parse_text()?);
+                    self.lex.expect(b':')?;
+                    map.push((key, self.parse_value(depth + 1)?));
+                }
+                self.make_object(map)
+            }
+            Some(b'"') => self.lex.parse_text()?.into_cbor(),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            Some(b't') if self.lex.rest().starts_with("true") => {
+                self.lex.off += 4;
+                SimpleValue::True.into_cbor()
+            }
+            Some(b'f') if self.lex.rest().starts_with("false") => {
+                self.lex.off += 5;
+                SimpleValue::False.into_cbor()
+            }
+            Some(b'n') if self.lex.rest().starts_with("null") => {
+                self.lex.off += 4;
+                SimpleValue::Null.into_cbor()
+            }
+            Some(_) => self.lex.error("unexpected character"),
+            None => self.lex.error("unexpected end of text"),
+        }
+    }
+
+    fn make_object(&self, map: Vec<(Key, Cbor)>) -> Result<Cbor> {
+        let is_tag = match (self.opts.tags, map.as_slice()) {
+            (
+                TagMode::Object,
+                [(Key::Text(a), Cbor::Major0(_, _)), (Key::Text(b), _)],
+            ) => a == "@tag" && b == "@value",
+            (_, _) => false,
+        };
+
+        match is_tag {
+            true => {
+                let mut iter = map.into_iter();
+                let num = match iter.next() {
+                    Some((_, Cbor::Major0(_, num))) => num,
+                    _ => unreachable!(),
+                };
+                let (_, val) = iter.next().unwrap();
+                Ok(Tag::from_value(num, val).into())
+            }
+            false => {
+                let info = err_at!(FailConvert, Info::try_from(map.len()))?;
+                Ok(Cbor::Major5(info, map))
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Cbor> {
+        let start = self.lex.off;
+        let bytes = self.lex.text.as_bytes();
+        let digits = |off: usize| {
+            bytes[off..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count()
+        };
+
+        let neg = self.lex.peek() == Some(b'-');
+        if neg {
+            self.lex.off += 1;
+        }
+        match digits(self.lex.off) {
+            0 => return self.lex.error("invalid number"),
+            n if n > 1 && bytes[self.lex.off] == b'0' => {
+                return self.lex.error("leading zero")
+            }
+            n => self.lex.off += n,
+        }
+
+        let mut is_float = false;
+        if self.lex.peek() == Some(b'.') {
+            is_float = true;
+            match digits(self.lex.off + 1) {
+                0 => return self.lex.error("invalid fraction"),
+                n => self.lex.off += n + 1,
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.lex.peek() {
+            is_float = true;
+            self.lex.off += 1;
+            if let Some(b'+') | Some(b'-') = self.lex.peek() {
+                self.lex.off += 1;
+            }
+            match digits(self.lex.off) {
+                0 => return self.lex.error("invalid exponent"),
+                n => self.lex.off += n,
+            }
+        }
+
+        let text = &self.lex.text[start..self.lex.off];
+        if is_float {
+            return match f64::from_str(text) {
+                Ok(val) => SimpleValue::F64(val).into_cbor(),
+                Err(_) => self.lex.error("invalid number"),
+            };
+        }
+
+        // integers beyond 128-bits fall back to double precision, which
+        // also bounds the cost of parsing very long literals.
+        let mag = match u128::from_str(&text[neg as usize..]) {
+            Ok(mag) => mag,
+            Err(_) => match f64::from_str(text) {
+                Ok(val) => return SimpleValue::F64(val).into_cbor(),
+                Err(_) => return self.lex.error("invalid number"),
+            },
+        };
+
+        // as with major type 1, magnitude of negative integer is `-1 - n`.
+        let neg = neg && mag > 0;
+        let mag = if neg { mag - 1 } else { mag };
+
+        let val = match u64::try_from(mag) {
+            Ok(num) if neg => Cbor::Major1(num.into(), num),
+            Ok(num) => Cbor::Major0(num.into(), num),
+            Err(_) => {
+                let bytes = mag.to_be_bytes();
+                let n = bytes.iter().take_while(|b| **b == 0).count();
+                match neg {
+                    false => BigNum::Pos(bytes[n..].to_vec()).into_cbor()?,
+                    true => BigNum::Neg(bytes[n..].to_vec()).into_cbor()?,
+                }
+            }
+        };
+
+        Ok(val)
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_json_test.rs"]
+mod cbor_json_test;
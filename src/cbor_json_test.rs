@@ -0,0 +1,167 @@
+use super::*;
+
+fn diag(text: &str) -> Cbor {
+    Cbor::from_diagnostic(text).unwrap()
+}
+
+#[test]
+fn test_to_json() {
+    let testcases = [
+        ("0", "0"),
+        ("-18446744073709551616", "-18446744073709551616"),
+        ("1.5_1", "1.5"),
+        ("0.1_2", "0.1"),
+        ("-4.1", "-4.1"),
+        ("NaN_1", "null"),
+        ("Infinity", "null"),
+        ("-Infinity_2", "null"),
+        ("[true, false, null, undefined]", "[true,false,null,null]"),
+        ("\"a\\\"\\u0001\\n\u{fc}\"", "\"a\\\"\\u0001\\n\u{fc}\""),
+        ("h''", "\"\""),
+        ("h'fbff'", "\"-_8\""),
+        ("h'0102030405'", "\"AQIDBAU\""),
+        ("22(h'fbff')", "\"+/8=\""),
+        ("23(h'0a0b')", "\"0a0b\""),
+        (
+            "[22([h'01', {1: h'02'}]), h'03']",
+            "[[\"AQ==\",{\"1\":\"Ag==\"}],\"Aw\"]",
+        ),
+        (
+            "{1: 2, -1: 2, true: 3, 1.5: 4, h'01': 5, \"k\": 6}",
+            "{\"1\":2,\"-1\":2,\"true\":3,\"1.5\":4,\"AQ\":5,\"k\":6}",
+        ),
+        ("0(\"2013-03-21T20:04:00Z\")", "\"2013-03-21T20:04:00Z\""),
+        ("2(h'010000000000000000')", "18446744073709551616"),
+        ("3(h'010000000000000000')", "-18446744073709551617"),
+        (
+            "2(h'010000000000000000000000000000000000')",
+            "\"AQAAAAAAAAAAAAAAAAAAAAAA\"",
+        ),
+        ("[_ {_ \"a\": (_ \"xy\")}]", "[{\"a\":\"xy\"}]"),
+    ];
+
+    for (text, json) in testcases.iter() {
+        assert_eq!(diag(text).to_json().unwrap(), *json, "{}", text);
+    }
+
+    let opts = JsonOptions {
+        tags: TagMode::Object,
+    };
+    let val = diag("[1(1363896240), 23(h'0a')]");
+    assert_eq!(
+        val.to_json_with(opts).unwrap(),
+        "[{\"@tag\":1,\"@value\":1363896240},{\"@tag\":23,\"@value\":\"0a\"}]"
+    );
+
+    // lazily decoded values are converted from their content.
+    let mut buf: Vec<u8> = vec![];
+    diag("{\"a\": [1]}").encode(&mut buf).unwrap();
+    assert_eq!(Cbor::Binary(buf).to_json().unwrap(), "{\"a\":[1]}");
+
+    assert!(Cbor::Major3(Info::Tiny(1), vec![0xff]).to_json().is_err());
+}
+
+#[test]
+fn test_from_json() {
+    let testcases = [
+        ("0", "0"),
+        (" -1 ", "-1"),
+        ("-0", "0"),
+        ("18446744073709551615", "18446744073709551615"),
+        ("-18446744073709551616", "-18446744073709551616"),
+        ("18446744073709551616", "2(h'010000000000000000')"),
+        ("-18446744073709551617", "3(h'010000000000000000')"),
+        (
+            "340282366920938463463374607431768211455",
+            "2(h'ffffffffffffffffffffffffffffffff')",
+        ),
+        (
+            "-340282366920938463463374607431768211455",
+            "3(h'fffffffffffffffffffffffffffffffe')",
+        ),
+        ("1.5", "1.5"),
+        ("-2.5E-3", "-0.0025"),
+        ("1e2", "100.0"),
+        ("[]", "[]"),
+        ("{}", "{}"),
+        (
+            "[true, false, null, \"a\\/\\u00fc\\ud83d\\ude00\"]",
+            "[true, false, null, \"a/\u{fc}\u{1f600}\"]",
+        ),
+        (
+            "{\"a\": {\"b\": [1, 2]}, \"c\": \"d\"}",
+            "{\"a\": {\"b\": [1, 2]}, \"c\": \"d\"}",
+        ),
+        (
+            "{\"@tag\": 1, \"@value\": 1363896240}",
+            "{\"@tag\": 1, \"@value\": 1363896240}",
+        ),
+    ];
+
+    for (json, text) in testcases.iter() {
+        assert_eq!(Cbor::from_json(json).unwrap(), diag(text), "{}", json);
+    }
+
+    // integers beyond 128-bits fall back to double precision.
+    let val = Cbor::from_json("-340282366920938463463374607431768211456").unwrap();
+    assert_eq!(
+        val,
+        SimpleValue::F64(-(2_f64.powi(128))).into_cbor().unwrap()
+    );
+    let text = format!("1{}", "0".repeat(200_000));
+    match Cbor::from_json(&text).unwrap() {
+        Cbor::Major7(_, SimpleValue::F64(val)) => assert!(val.is_infinite()),
+        val => panic!("{:?}", val),
+    }
+
+    // bignums within 128-bits round trip via JSON numbers.
+    for text in [
+        "2(h'010000000000000000')",
+        "3(h'ff000000000000000000000000000000')",
+    ]
+    .iter()
+    {
+        let json = diag(text).to_json().unwrap();
+        assert_eq!(Cbor::from_json(&json).unwrap(), diag(text), "{}", json);
+    }
+
+    let opts = JsonOptions {
+        tags: TagMode::Object,
+    };
+    let json = "[{\"@tag\":1,\"@value\":1363896240},{\"@tag\":39,\"@value\":\"id\"}]";
+    let val = Cbor::from_json_with(json, opts).unwrap();
+    assert_eq!(val, diag("[1(1363896240), 39(\"id\")]"));
+    assert_eq!(val.to_json_with(opts).unwrap(), json);
+    // not a tag object.
+    let json = "{\"@value\":1,\"@tag\":1}";
+    let val = Cbor::from_json_with(json, opts).unwrap();
+    assert_eq!(val, diag(json));
+
+    let errs = [
+        "",
+        "[1, 2",
+        "[1 2]",
+        "[1,]",
+        "[,1]",
+        "{1: 2}",
+        "{\"a\" 2}",
+        "{\"a\": 1,}",
+        "01",
+        "1.",
+        ".5",
+        "1e",
+        "+1",
+        "\"abc",
+        "\"\\q\"",
+        "\"\\ud800\"",
+        "\"\t\"",
+        "NaN",
+        "Infinity",
+        "nil",
+        "h'00'",
+        "1 2",
+    ];
+    for json in errs.iter() {
+        assert!(Cbor::from_json(json).is_err(), "{}", json);
+    }
+}
@@ -0,0 +1,163 @@
+//! Module implement scanning of text, shared by the parsers for
+//! [diagnostic notation][Cbor::from_diagnostic] and [JSON][Cbor::from_json].
+//!
+//! [Cbor::from_diagnostic]: crate::cbor::Cbor::from_diagnostic
+//! [Cbor::from_json]: crate::cbor::Cbor::from_json
+
+use crate::{Error, Result};
+
+/// Syntax of the text being scanned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Syntax {
+    /// Diagnostic notation, allows `/ comments /` and control characters
+    /// within text strings.
+    Diagnostic,
+    /// JSON text, as per RFC 8259.
+    Json,
+}
+
+/// Cursor over text, with helpers common to both syntax.
+pub(crate) struct Lexer<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) off: usize,
+    syntax: Syntax,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(text: &'a str, syntax: Syntax) -> Lexer<'a> {
+        Lexer {
+            text,
+            off: 0,
+            syntax,
+        }
+    }
+
+    pub(crate) fn error<T>(&self, msg: &str) -> Result<T> {
+        let name = match self.syntax {
+            Syntax::Diagnostic => "diagnostic",
+            Syntax::Json => "json",
+        };
+        err_at!(FailCbor, msg: "{} {} at offset {}", name, msg, self.off)
+    }
+
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.off).copied()
+    }
+
+    pub(crate) fn rest(&self) -> &'a str {
+        &self.text[self.off..]
+    }
+
+    pub(crate) fn skip_ws(&mut self) -> Result<()> {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => self.off += 1,
+                Some(b'/') if self.syntax == Syntax::Diagnostic => {
+                    match self.rest()[1..].find('/') {
+                        Some(n) => self.off += n + 2,
+                        None => self.error("unterminated comment")?,
+                    }
+                }
+                _ => break Ok(()),
+            }
+        }
+    }
+
+    pub(crate) fn expect(&mut self, ch: u8) -> Result<()> {
+        self.skip_ws()?;
+        match self.peek() {
+            Some(c) if c == ch => {
+                self.off += 1;
+                Ok(())
+            }
+            _ => self.error(&format!("expected {:?}", ch as char)),
+        }
+    }
+
+    // between container items, return true if container is closed.
+    pub(crate) fn close(&mut self, ch: u8, first: bool) -> Result<bool> {
+        self.skip_ws()?;
+        match self.peek() {
+            Some(c) if c == ch => {
+                self.off += 1;
+                Ok(true)
+            }
+            _ if first => Ok(false),
+            Some(b',') => {
+                self.off += 1;
+                Ok(false)
+            }
+            _ => self.error(&format!("expected ',' or {:?}", ch as char)),
+        }
+    }
+
+    // parse double quoted text, with JSON escapes, current offset is at
+    // the opening quote.
+    pub(crate) fn parse_text(&mut self) -> Result<String> {
+        self.off += 1;
+
+        let mut text = String::default();
+        let mut chars = self.rest().char_indices();
+        loop {
+            let ch = match chars.next() {
+                Some((n, '"')) => {
+                    self.off += n + 1;
+                    break;
+                }
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, '"')) => '"',
+                    Some((_, '\\')) => '\\',
+                    Some((_, '/')) => '/',
+                    Some((_, 'b')) => '\u{8}',
+                    Some((_, 'f')) => '\u{c}',
+                    Some((_, 'n')) => '\n',
+                    Some((_, 'r')) => '\r',
+                    Some((_, 't')) => '\t',
+                    Some((n, 'u')) => {
+                        let hi = self.parse_u16_hex(n + 1)?;
+                        chars.nth(3);
+                        let code = match hi {
+                            0xd800..=0xdbff if chars.as_str().starts_with("\\u") => {
+                                let (n, _) = chars.next().unwrap();
+                                let lo = match self.parse_u16_hex(n + 2)? {
+                                    lo @ 0xdc00..=0xdfff => u32::from(lo) - 0xdc00,
+                                    _ => self.error("invalid surrogate pair")?,
+                                };
+                                chars.nth(4);
+                                0x10000 + ((u32::from(hi) - 0xd800) << 10) + lo
+                            }
+                            hi => u32::from(hi),
+                        };
+                        match std::char::from_u32(code) {
+                            Some(ch) => ch,
+                            None => self.error("invalid unicode escape")?,
+                        }
+                    }
+                    _ => self.error("invalid escape")?,
+                },
+                Some((_, ch)) if (ch as u32) < 0x20 && self.syntax == Syntax::Json => {
+                    self.error("control character")?
+                }
+                Some((_, ch)) => ch,
+                None => self.error("unterminated text")?,
+            };
+            text.push(ch);
+        }
+
+        Ok(text)
+    }
+
+    // parse 4 hex digits, at byte offset `n` from current offset.
+    fn parse_u16_hex(&self, n: usize) -> Result<u16> {
+        match self.rest().get(n..n + 4) {
+            Some(hex) if hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Ok(u16::from_str_radix(hex, 16).unwrap())
+            }
+            _ => self.error("invalid unicode escape"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_lexer_test.rs"]
+mod cbor_lexer_test;
@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn test_lexer_syntax() {
+    // comments are whitespace only in diagnostic notation.
+    let mut lex = Lexer::new(" / note / [", Syntax::Diagnostic);
+    lex.expect(b'[').unwrap();
+    assert_eq!(lex.peek(), None);
+    let mut lex = Lexer::new(" / note / [", Syntax::Json);
+    assert!(lex.expect(b'[').is_err());
+    assert!(Lexer::new("/ note", Syntax::Diagnostic).skip_ws().is_err());
+
+    // control characters are allowed only in diagnostic notation.
+    let mut lex = Lexer::new("\"a\tb\" ,", Syntax::Diagnostic);
+    assert_eq!(lex.parse_text().unwrap(), "a\tb");
+    assert!(!lex.close(b']', false).unwrap());
+    let mut lex = Lexer::new("\"a\tb\"", Syntax::Json);
+    match lex.parse_text() {
+        Err(err) => assert!(err.to_string().contains("json control character")),
+        Ok(_) => panic!("expected control character error"),
+    }
+
+    let mut lex = Lexer::new("\"\\u00fc\\ud83d\\ude00\\/\"]", Syntax::Json);
+    assert_eq!(lex.parse_text().unwrap(), "\u{fc}\u{1f600}/");
+    assert_eq!(lex.rest(), "]");
+    assert!(lex.close(b']', false).unwrap());
+}
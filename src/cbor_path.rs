@@ -0,0 +1,225 @@
+//! Module implement path based access into nested [Cbor] values.
+//!
+//! A path is a list of [PathSeg], each segment steps into a list,
+//! [Cbor::Major4], by index or into a map, [Cbor::Major5], by key. Paths
+//! can also be parsed from [JSON Pointer][rfc] text, like
+//! `"/entries/3/key"`.
+//!
+//! Tagged values and lazily decoded values, [Cbor::Binary], are not
+//! stepped into, refer to [Cbor::expand].
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc6901.html
+
+use std::{convert::TryFrom, mem};
+
+use crate::{
+    cbor::{Cbor, Info, Key},
+    Error, Result,
+};
+
+/// Single step in a path into a [Cbor] value.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PathSeg {
+    /// Step into a map entry by key.
+    Key(Key),
+    /// Step into a list item by index. On maps, it steps into the entry
+    /// whose key is `Key::U64(index)`, or its decimal text.
+    Index(usize),
+}
+
+impl From<Key> for PathSeg {
+    fn from(key: Key) -> PathSeg {
+        PathSeg::Key(key)
+    }
+}
+
+impl<'a> From<&'a str> for PathSeg {
+    fn from(key: &'a str) -> PathSeg {
+        PathSeg::Key(Key::Text(key.to_string()))
+    }
+}
+
+impl From<String> for PathSeg {
+    fn from(key: String) -> PathSeg {
+        PathSeg::Key(Key::Text(key))
+    }
+}
+
+impl From<usize> for PathSeg {
+    fn from(index: usize) -> PathSeg {
+        PathSeg::Index(index)
+    }
+}
+
+impl PathSeg {
+    /// Parse JSON Pointer text into path segments. Reference tokens that
+    /// are decimal numbers, without leading zeros, are parsed as
+    /// [PathSeg::Index], others as text keys.
+    pub fn from_pointer(ptr: &str) -> Result<Vec<PathSeg>> {
+        if ptr.is_empty() {
+            return Ok(vec![]);
+        } else if !ptr.starts_with('/') {
+            err_at!(FailConvert, msg: "pointer {:?} must start with '/'", ptr)?
+        }
+
+        let mut path = vec![];
+        for token in ptr[1..].split('/') {
+            let seg = match parse_index(token) {
+                Some(index) => PathSeg::Index(index),
+                None => PathSeg::Key(Key::Text(unescape(token)?)),
+            };
+            path.push(seg)
+        }
+
+        Ok(path)
+    }
+
+    fn to_key(&self) -> Result<Key> {
+        match self {
+            PathSeg::Key(key) => Ok(key.clone()),
+            PathSeg::Index(index) => {
+                Ok(Key::U64(err_at!(FailConvert, u64::try_from(*index))?))
+            }
+        }
+    }
+
+    fn is_match(&self, key: &Key) -> bool {
+        match (self, key) {
+            (PathSeg::Key(a), b) => a == b,
+            (PathSeg::Index(a), Key::U64(b)) => u64::try_from(*a) == Ok(*b),
+            (PathSeg::Index(a), Key::Text(b)) => parse_index(b) == Some(*a),
+            (PathSeg::Index(_), _) => false,
+        }
+    }
+}
+
+// Parse array index, as per RFC 6901, digits without leading zeros.
+fn parse_index(token: &str) -> Option<usize> {
+    let is_index = match token.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    match is_index {
+        true => token.parse::<usize>().ok(),
+        false => None,
+    }
+}
+
+impl Cbor {
+    /// Return a reference to the value at `path`, if present.
+    pub fn get_path(&self, path: &[PathSeg]) -> Option<&Cbor> {
+        let mut val = self;
+        for seg in path.iter() {
+            val = match (val, seg) {
+                (Cbor::Major4(_, list), PathSeg::Index(index)) => list.get(*index)?,
+                (Cbor::Major5(_, map), seg) => {
+                    &map.iter().find(|(key, _)| seg.is_match(key))?.1
+                }
+                (_, _) => return None,
+            };
+        }
+        Some(val)
+    }
+
+    /// Return a mutable reference to the value at `path`, if present.
+    pub fn get_path_mut(&mut self, path: &[PathSeg]) -> Option<&mut Cbor> {
+        let mut val = self;
+        for seg in path.iter() {
+            val = match (val, seg) {
+                (Cbor::Major4(_, list), PathSeg::Index(index)) => list.get_mut(*index)?,
+                (Cbor::Major5(_, map), seg) => {
+                    &mut map.iter_mut().find(|(key, _)| seg.is_match(key))?.1
+                }
+                (_, _) => return None,
+            };
+        }
+        Some(val)
+    }
+
+    /// Set the value at `path` and return the old value, if any.
+    ///
+    /// Missing map entries are added, missing containers along the path
+    /// are created, as map for [PathSeg::Key] and as list for
+    /// [PathSeg::Index]. Lists can only be appended to, that is, index
+    /// shall not be beyond the list's length.
+    pub fn set_path(&mut self, path: &[PathSeg], val: Cbor) -> Result<Option<Cbor>> {
+        let (seg, rest) = match path.split_first() {
+            Some((seg, rest)) => (seg, rest),
+            None => return Ok(Some(mem::replace(self, val))),
+        };
+
+        match (self, seg) {
+            (Cbor::Major4(info, list), PathSeg::Index(index)) => {
+                let n = list.len();
+                match *index {
+                    index if index < n => return list[index].set_path(rest, val),
+                    index if index == n => list.push(build_path(rest, val)?),
+                    index => {
+                        err_at!(FailConvert, msg: "index {} beyond list of {}", index, n)?
+                    }
+                }
+                update_info(info, list.len())?;
+            }
+            (Cbor::Major5(info, map), seg) => {
+                match map.iter_mut().find(|(key, _)| seg.is_match(key)) {
+                    Some((_, item)) => return item.set_path(rest, val),
+                    None => map.push((seg.to_key()?, build_path(rest, val)?)),
+                }
+                update_info(info, map.len())?;
+            }
+            (Cbor::Major4(_, _), seg) => {
+                err_at!(FailConvert, msg: "cannot step into list with {:?}", seg)?
+            }
+            (val, _) => {
+                let major = val.to_major_val();
+                err_at!(FailConvert, msg: "cannot step into major type {}", major)?
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Build containers for `path`, with `val` as the leaf.
+fn build_path(path: &[PathSeg], val: Cbor) -> Result<Cbor> {
+    let mut val = val;
+    for seg in path.iter().rev() {
+        val = match seg {
+            PathSeg::Key(key) => Cbor::Major5(Info::Tiny(1), vec![(key.clone(), val)]),
+            PathSeg::Index(0) => Cbor::Major4(Info::Tiny(1), vec![val]),
+            PathSeg::Index(index) => {
+                err_at!(FailConvert, msg: "index {} beyond new list", index)?
+            }
+        };
+    }
+    Ok(val)
+}
+
+// Definite length containers carry their length in additional info.
+fn update_info(info: &mut Info, len: usize) -> Result<()> {
+    if *info != Info::Indefinite {
+        *info = Info::try_from(len)?;
+    }
+    Ok(())
+}
+
+fn unescape(token: &str) -> Result<String> {
+    let mut out = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '~' => match chars.next() {
+                Some('0') => out.push('~'),
+                Some('1') => out.push('/'),
+                _ => err_at!(FailConvert, msg: "invalid escape in {:?}", token)?,
+            },
+            ch => out.push(ch),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "cbor_path_test.rs"]
+mod cbor_path_test;
@@ -0,0 +1,134 @@
+use super::*;
+
+use crate::cbor::IntoCbor;
+
+fn sample() -> Cbor {
+    let entry = |key: &str| {
+        let map = vec![(Key::Text("key".to_string()), key.into_cbor().unwrap())];
+        map.into_cbor().unwrap()
+    };
+    let entries: Vec<Cbor> = ["a", "b", "c", "d"].iter().map(|k| entry(k)).collect();
+    let map = vec![
+        (
+            Key::Text("entries".to_string()),
+            entries.into_cbor().unwrap(),
+        ),
+        (Key::U64(7), "seven".into_cbor().unwrap()),
+        (Key::Text("a/b~c".to_string()), true.into_cbor().unwrap()),
+    ];
+    map.into_cbor().unwrap()
+}
+
+#[test]
+fn test_get_path() {
+    let val = sample();
+
+    let path: Vec<PathSeg> = vec!["entries".into(), 3.into(), "key".into()];
+    assert_eq!(val.get_path(&path), Some(&"d".into_cbor().unwrap()));
+    assert_eq!(val.get_path(&[]), Some(&val));
+    assert_eq!(
+        val.get_path(&[7.into()]),
+        Some(&"seven".into_cbor().unwrap())
+    );
+
+    let path = PathSeg::from_pointer("/entries/0/key").unwrap();
+    assert_eq!(val.get_path(&path), Some(&"a".into_cbor().unwrap()));
+    let path = PathSeg::from_pointer("/a~1b~0c").unwrap();
+    assert_eq!(val.get_path(&path), Some(&true.into_cbor().unwrap()));
+
+    // missing and mismatched segments.
+    let missing: Vec<Vec<PathSeg>> = vec![
+        vec!["entries".into(), 4.into()],
+        vec!["entries".into(), "0".into()],
+        vec!["entries".into(), 0.into(), "key".into(), 0.into()],
+        vec!["nothing".into()],
+    ];
+    for path in missing.iter() {
+        assert_eq!(val.get_path(path), None, "{:?}", path);
+    }
+
+    // index matches text keys only in their canonical form.
+    let map = vec![
+        (Key::Text("03".to_string()), 1_u64.into_cbor().unwrap()),
+        (Key::Text("+3".to_string()), 2_u64.into_cbor().unwrap()),
+        (Key::Text("3".to_string()), 3_u64.into_cbor().unwrap()),
+    ];
+    let map = map.into_cbor().unwrap();
+    assert_eq!(map.get_path(&[3.into()]), Some(&3_u64.into_cbor().unwrap()));
+    let map = vec![(Key::Text("03".to_string()), 1_u64.into_cbor().unwrap())];
+    assert_eq!(map.into_cbor().unwrap().get_path(&[3.into()]), None);
+
+    let mut val = val;
+    let path: Vec<PathSeg> = vec!["entries".into(), 1.into(), "key".into()];
+    *val.get_path_mut(&path).unwrap() = "x".into_cbor().unwrap();
+    assert_eq!(val.get_path(&path), Some(&"x".into_cbor().unwrap()));
+    assert!(val.get_path_mut(&["none".into()]).is_none());
+}
+
+#[test]
+fn test_set_path() {
+    let mut val = sample();
+
+    // replace existing value.
+    let path = PathSeg::from_pointer("/entries/2/key").unwrap();
+    let old = val.set_path(&path, "z".into_cbor().unwrap()).unwrap();
+    assert_eq!(old, Some("c".into_cbor().unwrap()));
+    assert_eq!(val.get_path(&path), Some(&"z".into_cbor().unwrap()));
+
+    // append to list and build missing containers.
+    let path = PathSeg::from_pointer("/entries/4/key/0").unwrap();
+    assert_eq!(
+        val.set_path(&path, 10_u64.into_cbor().unwrap()).unwrap(),
+        None
+    );
+    assert_eq!(val.get_path(&path), Some(&10_u64.into_cbor().unwrap()));
+    let path = PathSeg::from_pointer("/new/x").unwrap();
+    assert_eq!(
+        val.set_path(&path, 20_u64.into_cbor().unwrap()).unwrap(),
+        None
+    );
+
+    // additional info tracks the new length.
+    let mut buf: Vec<u8> = vec![];
+    val.encode(&mut buf).unwrap();
+    let (nval, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(nval, val);
+    let entries = val.get_path(&["entries".into()]).unwrap();
+    assert!(matches!(entries, Cbor::Major4(Info::Tiny(5), _)));
+
+    // invalid paths.
+    let bad: Vec<Vec<PathSeg>> = vec![
+        vec!["entries".into(), 6.into()],
+        vec!["entries".into(), "key".into()],
+        vec!["entries".into(), 0.into(), "key".into(), "x".into()],
+        vec!["missing".into(), 1.into()],
+    ];
+    for path in bad.iter() {
+        let res = val.set_path(path, 0_u64.into_cbor().unwrap());
+        assert!(res.is_err(), "{:?}", path);
+    }
+
+    let mut val = 1_u64.into_cbor().unwrap();
+    let old = val.set_path(&[], 2_u64.into_cbor().unwrap()).unwrap();
+    assert_eq!(old, Some(1_u64.into_cbor().unwrap()));
+}
+
+#[test]
+fn test_from_pointer() {
+    assert_eq!(PathSeg::from_pointer("").unwrap(), vec![]);
+    assert_eq!(PathSeg::from_pointer("/").unwrap(), vec!["".into()]);
+    assert_eq!(
+        PathSeg::from_pointer("/a/0/10/01/-1/~01").unwrap(),
+        vec![
+            "a".into(),
+            0.into(),
+            10.into(),
+            "01".into(),
+            "-1".into(),
+            "~1".into()
+        ]
+    );
+    assert!(PathSeg::from_pointer("a").is_err());
+    assert!(PathSeg::from_pointer("/a~2").is_err());
+    assert!(PathSeg::from_pointer("/a~").is_err());
+}
@@ -0,0 +1,276 @@
+//! Module implement a pull-based reader for CBOR data.
+//!
+//! Unlike [Cbor::decode], [CborReader] does not build the value tree,
+//! instead it hands out one [Event] at a time. Memory footprint is bounded
+//! by the largest byte-string or text-string in the input, which makes it
+//! possible to scan huge documents, filter fields without building them,
+//! and implement custom decoders on top of it.
+
+use std::{
+    cmp,
+    convert::TryFrom,
+    io::{self, Read},
+    iter::FusedIterator,
+};
+
+#[allow(unused_imports)]
+use crate::cbor::Cbor;
+use crate::{
+    cbor::{decode_addnl, Info, SimpleValue, RECURSION_LIMIT},
+    Error, Result,
+};
+
+/// Events generated by [CborReader], one for each data item in the input.
+///
+/// Containers generate a start event followed by the events for each of
+/// its items. Indefinite length containers are terminated by an
+/// [Event::Break], while the end of definite length containers shall be
+/// tracked by the application using the length in the start event.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// Major type 0, unsigned integer.
+    UInt(u64),
+    /// Major type 1, negative integer, encoded as `-1 - n`.
+    NInt(u64),
+    /// Major type 2, byte-string. Indefinite length byte-strings are
+    /// concatenated into single event.
+    Bytes(Vec<u8>),
+    /// Major type 3, text-string. Indefinite length text-strings are
+    /// concatenated into single event.
+    Text(String),
+    /// Major type 4, start of list, length is None for indefinite encoding.
+    ArrayStart(Option<u64>),
+    /// Major type 5, start of map, length is None for indefinite encoding.
+    MapStart(Option<u64>),
+    /// Major type 6, tag number, followed by the events for the enclosed
+    /// data item.
+    Tag(u64),
+    /// Major type 7, simple value other than break.
+    Simple(SimpleValue),
+    /// Break stop for indefinite length list and map.
+    Break,
+}
+
+// Open container or tag, whose items are yet to be read.
+#[derive(Debug)]
+enum Frame {
+    // number of remaining items, for maps it is twice the number of entries.
+    Definite(u64),
+    // number of items read so far, used to validate the break position.
+    Indefinite { map: bool, n: u64 },
+}
+
+/// Pull-based reader, to read one [Event] at a time from `R`.
+///
+/// Handles definite and indefinite encoding, and enforces the same
+/// [RECURSION_LIMIT] as [Cbor::decode].
+pub struct CborReader<R> {
+    r: R,
+    stack: Vec<Frame>,
+    n: usize,
+    // iteration stops at end of input or after the first error.
+    done: bool,
+}
+
+impl<R> CborReader<R>
+where
+    R: io::Read,
+{
+    /// Create a new reader for CBOR data from `r`.
+    pub fn new(r: R) -> CborReader<R> {
+        CborReader {
+            r,
+            stack: Vec::default(),
+            n: 0,
+            done: false,
+        }
+    }
+
+    /// Return the nesting depth of the next data item to be read, top
+    /// level data item is at depth 1.
+    pub fn to_depth(&self) -> usize {
+        self.stack.len() + 1
+    }
+
+    /// Return the number of bytes read so far.
+    pub fn to_offset(&self) -> usize {
+        self.n
+    }
+
+    /// Return whether reader is at the boundary of a top level data item.
+    pub fn is_complete(&self) -> bool {
+        self.stack.iter().all(|f| matches!(f, Frame::Definite(0)))
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+
+    /// Read the next event. Return None if input is exhausted at the
+    /// boundary of top level data item, it is an error to hit end of input
+    /// in the middle of a data item.
+    pub fn next_event(&mut self) -> Result<Option<Event>> {
+        while let Some(Frame::Definite(0)) = self.stack.last() {
+            self.stack.pop();
+        }
+
+        let (major, info) = match self.read_hdr()? {
+            Some(hdr) => hdr,
+            None if self.stack.is_empty() => return Ok(None),
+            None => err_at!(IOError, msg: "unexpected eof at {}", self.n)?,
+        };
+
+        if let (7, Info::Indefinite) = (major, info) {
+            match self.stack.pop() {
+                Some(Frame::Indefinite { map: true, n }) if n % 2 == 1 => {
+                    err_at!(FailCbor, msg: "break after map key at {}", self.n)?
+                }
+                Some(Frame::Indefinite { .. }) => return Ok(Some(Event::Break)),
+                _ => err_at!(FailCbor, msg: "unexpected break at {}", self.n)?,
+            }
+        }
+
+        if self.to_depth() > (RECURSION_LIMIT as usize) {
+            err_at!(FailCbor, msg: "decode recursion limt exceeded")?
+        }
+        match self.stack.last_mut() {
+            Some(Frame::Definite(n)) => *n -= 1,
+            Some(Frame::Indefinite { n, .. }) => *n += 1,
+            None => (),
+        }
+
+        let event = match (major, info) {
+            (0, info) => Event::UInt(self.read_addnl(info)?),
+            (1, info) => Event::NInt(self.read_addnl(info)?),
+            (2, info) => Event::Bytes(self.read_string(2, info)?),
+            (3, info) => {
+                let text = self.read_string(3, info)?;
+                Event::Text(err_at!(FailConvert, String::from_utf8(text))?)
+            }
+            (4, Info::Indefinite) => {
+                self.stack.push(Frame::Indefinite { map: false, n: 0 });
+                Event::ArrayStart(None)
+            }
+            (4, info) => {
+                let len = self.read_addnl(info)?;
+                self.stack.push(Frame::Definite(len));
+                Event::ArrayStart(Some(len))
+            }
+            (5, Info::Indefinite) => {
+                self.stack.push(Frame::Indefinite { map: true, n: 0 });
+                Event::MapStart(None)
+            }
+            (5, info) => {
+                let len = self.read_addnl(info)?;
+                let n = match len.checked_mul(2) {
+                    Some(n) => n,
+                    None => err_at!(FailCbor, msg: "map length {} overflow", len)?,
+                };
+                self.stack.push(Frame::Definite(n));
+                Event::MapStart(Some(len))
+            }
+            (6, info) => {
+                let tag = self.read_addnl(info)?;
+                self.stack.push(Frame::Definite(1));
+                Event::Tag(tag)
+            }
+            (7, info) => {
+                let (sval, m) = SimpleValue::decode(info, &mut self.r)?;
+                self.n += m;
+                Event::Simple(sval)
+            }
+            _ => unreachable!(),
+        };
+
+        Ok(Some(event))
+    }
+
+    // Return None if input is exhausted before reading any byte.
+    fn read_hdr(&mut self) -> Result<Option<(u8, Info)>> {
+        let mut scratch = [0_u8; 1];
+        loop {
+            match self.r.read(&mut scratch) {
+                Ok(0) => break Ok(None),
+                Ok(_) => {
+                    self.n += 1;
+                    let (major, info) = ((scratch[0] & 0xe0) >> 5, scratch[0] & 0x1f);
+                    break Ok(Some((major, Info::try_from(info)?)));
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => err_at!(IOError, msg: "{}", err)?,
+            }
+        }
+    }
+
+    fn read_addnl(&mut self, info: Info) -> Result<u64> {
+        let (val, m) = decode_addnl(info, &mut self.r)?;
+        self.n += m;
+        Ok(val)
+    }
+
+    fn read_string(&mut self, major: u8, info: Info) -> Result<Vec<u8>> {
+        match info {
+            Info::Indefinite => {
+                let mut data: Vec<u8> = Vec::default();
+                loop {
+                    match self.read_hdr()? {
+                        Some((7, Info::Indefinite)) => break Ok(data),
+                        Some((m, Info::Indefinite)) if m == major => {
+                            err_at!(FailCbor, msg: "nested chunk at {}", self.n)?
+                        }
+                        Some((m, info)) if m == major => {
+                            data.extend_from_slice(&self.read_string(major, info)?)
+                        }
+                        Some(_) => err_at!(FailConvert, msg: "expected chunk")?,
+                        None => err_at!(IOError, msg: "unexpected eof at {}", self.n)?,
+                    }
+                }
+            }
+            info => {
+                let len = self.read_addnl(info)?;
+                let n: usize = err_at!(FailConvert, usize::try_from(len))?;
+                // length prefix is not trusted for pre-allocation.
+                let mut data = Vec::with_capacity(cmp::min(n, 64 * 1024));
+                let mut r = io::Read::take(&mut self.r, len);
+                let m = err_at!(IOError, r.read_to_end(&mut data))?;
+                if m != n {
+                    err_at!(IOError, msg: "insufficient bytes {}/{}", m, n)?
+                }
+                self.n += n;
+                Ok(data)
+            }
+        }
+    }
+}
+
+impl<R> Iterator for CborReader<R>
+where
+    R: io::Read,
+{
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<R> FusedIterator for CborReader<R> where R: io::Read {}
+
+#[cfg(test)]
+#[path = "cbor_reader_test.rs"]
+mod cbor_reader_test;
@@ -0,0 +1,143 @@
+use arbitrary::Unstructured;
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+use crate::cbor::{FromCbor, IntoCbor, Key, Tag};
+
+// Build Cbor value from events, to cross check the reader with Cbor::decode.
+fn build<R: io::Read>(reader: &mut CborReader<R>) -> Cbor {
+    let event = reader.next_event().unwrap().unwrap();
+    build_event(reader, event)
+}
+
+fn build_event<R: io::Read>(reader: &mut CborReader<R>, event: Event) -> Cbor {
+    match event {
+        Event::UInt(val) => Cbor::Major0(val.into(), val),
+        Event::NInt(val) => Cbor::Major1(val.into(), val),
+        Event::Bytes(val) => Cbor::bytes_into_cbor(val).unwrap(),
+        Event::Text(val) => val.into_cbor().unwrap(),
+        Event::ArrayStart(Some(n)) => {
+            let items: Vec<Cbor> = (0..n).map(|_| build(reader)).collect();
+            Cbor::Major4(n.into(), items)
+        }
+        Event::ArrayStart(None) => {
+            let mut items = vec![];
+            loop {
+                match reader.next_event().unwrap().unwrap() {
+                    Event::Break => break,
+                    event => items.push(build_event(reader, event)),
+                }
+            }
+            Cbor::Major4(Info::Indefinite, items)
+        }
+        Event::MapStart(Some(n)) => {
+            let items: Vec<(Key, Cbor)> = (0..n)
+                .map(|_| {
+                    let key = Key::from_cbor(build(reader)).unwrap();
+                    (key, build(reader))
+                })
+                .collect();
+            Cbor::Major5(n.into(), items)
+        }
+        Event::MapStart(None) => unreachable!(),
+        Event::Tag(val) => Tag::from_value(val, build(reader)).into(),
+        Event::Simple(sval) => sval.into_cbor().unwrap(),
+        Event::Break => unreachable!(),
+    }
+}
+
+#[test]
+fn test_cbor_reader() {
+    let seed: u128 = random();
+    println!("test_cbor_reader {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut buf: Vec<u8> = vec![];
+    let mut vals: Vec<Cbor> = vec![];
+    for _i in 0..1000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+        val.encode(&mut buf).unwrap();
+        vals.push(val);
+    }
+
+    let mut reader = CborReader::new(buf.as_slice());
+    for val in vals.into_iter() {
+        assert!(reader.is_complete());
+        assert_eq!(build(&mut reader), val);
+    }
+    assert!(reader.next_event().unwrap().is_none());
+    assert_eq!(reader.to_offset(), buf.len());
+}
+
+#[test]
+fn test_cbor_reader_indefinite() {
+    // [_ 1, [2, 3], (_ h'0102', h'03'), "a"]
+    let buf = [
+        0x9f, 0x01, 0x82, 0x02, 0x03, 0x5f, 0x42, 0x01, 0x02, 0x41, 0x03, 0xff, 0x61,
+        0x61, 0xff,
+    ];
+    let events: Vec<Event> = CborReader::new(&buf[..]).map(|e| e.unwrap()).collect();
+    let refs = vec![
+        Event::ArrayStart(None),
+        Event::UInt(1),
+        Event::ArrayStart(Some(2)),
+        Event::UInt(2),
+        Event::UInt(3),
+        Event::Bytes(vec![1, 2, 3]),
+        Event::Text("a".to_string()),
+        Event::Break,
+    ];
+    assert_eq!(events, refs);
+
+    // {_ "a": 1}
+    let buf = [0xbf, 0x61, 0x61, 0x01, 0xff];
+    let mut reader = CborReader::new(&buf[..]);
+    assert_eq!(reader.next_event().unwrap(), Some(Event::MapStart(None)));
+    assert_eq!(reader.to_depth(), 2);
+    assert_eq!(
+        reader.next_event().unwrap(),
+        Some(Event::Text("a".to_string()))
+    );
+    assert_eq!(reader.next_event().unwrap(), Some(Event::UInt(1)));
+    assert_eq!(reader.next_event().unwrap(), Some(Event::Break));
+    assert!(reader.is_complete());
+    assert_eq!(reader.next_event().unwrap(), None);
+
+    // break in key position is invalid.
+    let buf = [0xbf, 0x61, 0x61, 0xff];
+    assert!(CborReader::new(&buf[..]).any(|e| e.is_err()));
+    // break outside of indefinite container.
+    assert!(CborReader::new(&[0xff][..]).next_event().is_err());
+    // truncated input.
+    assert!(CborReader::new(&[0x82, 0x01][..]).any(|e| e.is_err()));
+
+    // iteration stops after the first error, or at end of input.
+    let mut reader = CborReader::new(&[0xff, 0x01][..]);
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+    assert!(reader.next().is_none());
+    let mut reader = CborReader::new(&[0x01][..]);
+    assert_eq!(reader.next().unwrap().unwrap(), Event::UInt(1));
+    assert!(reader.next().is_none());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_cbor_reader_recursion() {
+    let limit = RECURSION_LIMIT as usize;
+
+    let buf = vec![0x81; limit];
+    let mut reader = CborReader::new(&buf[..]);
+    for _ in 0..limit {
+        reader.next_event().unwrap();
+    }
+
+    let buf = vec![0x81; limit + 1];
+    assert!(CborReader::new(&buf[..]).any(|e| e.is_err()));
+}
@@ -0,0 +1,340 @@
+//! Module implement zero-copy decoding of CBOR data from byte-slice.
+//!
+//! [CborRef] is the borrowed counterpart of [Cbor], byte-strings and
+//! text-strings are handed out as slices into the input buffer, instead
+//! of copying them into freshly allocated vectors.
+//!
+//! Types deriving `Cborize` with a lifetime parameter implement
+//! [FromCborRef], in place of [FromCbor] and `CborDecode`, hence fields
+//! like `&str` and `&[u8]` borrow from the input buffer.
+
+use std::{borrow::Cow, convert::TryFrom, str::from_utf8};
+
+use crate::{
+    cbor::{
+        decode_addnl, decode_hdr, Cbor, FromCbor, Info, IntoCbor, Key, SimpleValue, Tag,
+        RECURSION_LIMIT,
+    },
+    Error, Result,
+};
+
+/// Convert from borrowed [CborRef] value, the value is typically obtained by
+/// decoding it from a byte-slice using [CborRef::decode].
+///
+/// This is the borrowed counterpart of [FromCbor], types like `&str` and
+/// `&[u8]` can refer directly into the input buffer.
+pub trait FromCborRef<'a>: Sized {
+    /// Convert value from [CborRef] into type's value.
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Self>;
+}
+
+/// Borrowed variant of [Cbor], enumerated over its major variants.
+///
+/// Definite length byte-strings and text-strings are borrowed from the
+/// input buffer. Indefinite length strings are encoded as chunks, hence
+/// they are concatenated into owned values.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CborRef<'a> {
+    Major0(Info, u64),                             // uint 0-23,24,25,26,27
+    Major1(Info, u64),                             // nint 0-23,24,25,26,27
+    Major2(Info, Cow<'a, [u8]>),                   // byts 0-23,24,25,26,27,31
+    Major3(Info, Cow<'a, str>),                    // text 0-23,24,25,26,27,31
+    Major4(Info, Vec<CborRef<'a>>),                // list 0-23,24,25,26,27,31
+    Major5(Info, Vec<(CborRef<'a>, CborRef<'a>)>), // dict 0-23,24,25,26,27,31
+    Major6(Info, TagRef<'a>),                      // tags similar to major0
+    Major7(Info, SimpleValue),                     // type refer SimpleValue
+}
+
+/// Borrowed variant of [Tag](crate::cbor::Tag).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TagRef<'a> {
+    /// Tag 39, used as identifier marker.
+    Identifier(Box<CborRef<'a>>),
+    /// Catch all tag-value, followed by the enclosed data item.
+    Value(u64, Box<CborRef<'a>>),
+}
+
+impl<'a> CborRef<'a> {
+    /// Deserialize bytes from `buf` to CborRef value, return the value and
+    /// number of bytes consumed from `buf` to construct the value.
+    pub fn decode(buf: &'a [u8]) -> Result<(CborRef<'a>, usize)> {
+        let mut buf = buf;
+        CborRef::do_decode(&mut buf, 1)
+    }
+
+    fn do_decode(buf: &mut &'a [u8], depth: u32) -> Result<(CborRef<'a>, usize)> {
+        if depth > RECURSION_LIMIT {
+            return err_at!(FailCbor, msg: "decode recursion limt exceeded");
+        }
+
+        let (major, info, n) = decode_hdr(buf)?;
+
+        let (val, m) = match (major, info) {
+            (0, info) => {
+                let (val, m) = decode_addnl(info, buf)?;
+                (CborRef::Major0(info, val), m)
+            }
+            (1, info) => {
+                let (val, m) = decode_addnl(info, buf)?;
+                (CborRef::Major1(info, val), m)
+            }
+            (2, Info::Indefinite) => {
+                let mut data: Vec<u8> = Vec::default();
+                let mut m = 0_usize;
+                loop {
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    m += k;
+                    match val {
+                        CborRef::Major2(_, chunk) => data.extend_from_slice(&chunk),
+                        CborRef::Major7(_, SimpleValue::Break) => break,
+                        _ => err_at!(FailConvert, msg: "expected byte chunk")?,
+                    }
+                }
+                (CborRef::Major2(info, Cow::Owned(data)), m)
+            }
+            (2, info) => {
+                let (val, m) = decode_addnl(info, buf)?;
+                let data = read_slice(buf, val)?;
+                (CborRef::Major2(info, Cow::Borrowed(data)), m + data.len())
+            }
+            (3, Info::Indefinite) => {
+                let mut text = String::default();
+                let mut m = 0_usize;
+                loop {
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    m += k;
+                    match val {
+                        CborRef::Major3(_, chunk) => text.push_str(&chunk),
+                        CborRef::Major7(_, SimpleValue::Break) => break,
+                        _ => err_at!(FailConvert, msg: "expected text chunk")?,
+                    }
+                }
+                (CborRef::Major3(info, Cow::Owned(text)), m)
+            }
+            (3, info) => {
+                let (val, m) = decode_addnl(info, buf)?;
+                let data = read_slice(buf, val)?;
+                let text = err_at!(FailConvert, from_utf8(data))?;
+                (CborRef::Major3(info, Cow::Borrowed(text)), m + data.len())
+            }
+            (4, Info::Indefinite) => {
+                let mut list: Vec<CborRef> = vec![];
+                let mut m = 0_usize;
+                loop {
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    m += k;
+                    match val {
+                        CborRef::Major7(_, SimpleValue::Break) => break,
+                        item => list.push(item),
+                    }
+                }
+                (CborRef::Major4(info, list), m)
+            }
+            (4, info) => {
+                let mut list: Vec<CborRef> = vec![];
+                let (len, mut m) = decode_addnl(info, buf)?;
+                for _ in 0..len {
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    list.push(val);
+                    m += k;
+                }
+                (CborRef::Major4(info, list), m)
+            }
+            (5, Info::Indefinite) => {
+                let mut map: Vec<(CborRef, CborRef)> = Vec::default();
+                let mut m = 0_usize;
+                loop {
+                    let (key, j) = CborRef::do_decode(buf, depth + 1)?;
+                    if let CborRef::Major7(_, SimpleValue::Break) = key {
+                        m += j;
+                        break;
+                    }
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    map.push((key, val));
+                    m += j + k;
+                }
+                (CborRef::Major5(info, map), m)
+            }
+            (5, info) => {
+                let mut map: Vec<(CborRef, CborRef)> = Vec::default();
+                let (len, mut m) = decode_addnl(info, buf)?;
+                for _ in 0..len {
+                    let (key, j) = CborRef::do_decode(buf, depth + 1)?;
+                    let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                    map.push((key, val));
+                    m += j + k;
+                }
+                (CborRef::Major5(info, map), m)
+            }
+            (6, info) => {
+                let (num, m) = decode_addnl(info, buf)?;
+                let (val, k) = CborRef::do_decode(buf, depth + 1)?;
+                if let CborRef::Major7(_, SimpleValue::Break) = val {
+                    err_at!(FailCbor, msg: "break after tag {}", num)?
+                }
+                let tag = match num {
+                    39 => TagRef::Identifier(Box::new(val)),
+                    num => TagRef::Value(num, Box::new(val)),
+                };
+                (CborRef::Major6(info, tag), m + k)
+            }
+            (7, info) => {
+                let (sval, m) = SimpleValue::decode(info, buf)?;
+                (CborRef::Major7(info, sval), m)
+            }
+            _ => unreachable!(),
+        };
+
+        Ok((val, (m + n)))
+    }
+
+    /// Return the borrowed byte-string, if this value is a definite length
+    /// Cbor major type-2.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            CborRef::Major2(_, Cow::Borrowed(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Return the borrowed text, if this value is a definite length Cbor
+    /// major type-3.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            CborRef::Major3(_, Cow::Borrowed(text)) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+fn read_slice<'a>(buf: &mut &'a [u8], len: u64) -> Result<&'a [u8]> {
+    let len = err_at!(FailConvert, usize::try_from(len))?;
+    if len > buf.len() {
+        err_at!(IOError, msg: "insufficient bytes {} > {}", len, buf.len())?
+    }
+    let (data, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(data)
+}
+
+impl<'a> IntoCbor for CborRef<'a> {
+    fn into_cbor(self) -> Result<Cbor> {
+        let val = match self {
+            CborRef::Major0(info, val) => Cbor::Major0(info, val),
+            CborRef::Major1(info, val) => Cbor::Major1(info, val),
+            CborRef::Major2(info, data) => Cbor::Major2(info, data.into_owned()),
+            CborRef::Major3(info, text) => {
+                Cbor::Major3(info, text.into_owned().into_bytes())
+            }
+            CborRef::Major4(info, list) => {
+                let mut items = vec![];
+                for item in list.into_iter() {
+                    items.push(item.into_cbor()?);
+                }
+                Cbor::Major4(info, items)
+            }
+            CborRef::Major5(info, map) => {
+                let mut items = vec![];
+                for (key, val) in map.into_iter() {
+                    items.push((Key::from_cbor(key.into_cbor()?)?, val.into_cbor()?));
+                }
+                Cbor::Major5(info, items)
+            }
+            CborRef::Major6(info, TagRef::Identifier(val)) => {
+                Cbor::Major6(info, Tag::from_identifier(val.into_cbor()?))
+            }
+            CborRef::Major6(info, TagRef::Value(num, val)) => {
+                Cbor::Major6(info, Tag::from_value(num, val.into_cbor()?))
+            }
+            CborRef::Major7(info, sval) => Cbor::Major7(info, sval),
+        };
+
+        Ok(val)
+    }
+}
+
+impl<'a> FromCborRef<'a> for CborRef<'a> {
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Self> {
+        Ok(val)
+    }
+}
+
+impl<'a> FromCborRef<'a> for Cbor {
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Self> {
+        val.into_cbor()
+    }
+}
+
+impl<'a> FromCborRef<'a> for &'a [u8] {
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Self> {
+        match val {
+            CborRef::Major2(_, Cow::Borrowed(data)) => Ok(data),
+            CborRef::Major2(_, Cow::Owned(_)) => {
+                err_at!(FailConvert, msg: "cannot borrow chunked bytes")
+            }
+            _ => err_at!(FailConvert, msg: "not bytes"),
+        }
+    }
+}
+
+impl<'a> FromCborRef<'a> for &'a str {
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Self> {
+        match val {
+            CborRef::Major3(_, Cow::Borrowed(text)) => Ok(text),
+            CborRef::Major3(_, Cow::Owned(_)) => {
+                err_at!(FailConvert, msg: "cannot borrow chunked text")
+            }
+            _ => err_at!(FailConvert, msg: "not utf8-string"),
+        }
+    }
+}
+
+impl<'a, T> FromCborRef<'a> for Vec<T>
+where
+    T: FromCborRef<'a>,
+{
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Vec<T>> {
+        match val {
+            CborRef::Major4(_, data) => {
+                let mut arr = vec![];
+                for item in data.into_iter() {
+                    arr.push(T::from_cbor_ref(item)?)
+                }
+                Ok(arr)
+            }
+            _ => err_at!(FailConvert, msg: "not a vector"),
+        }
+    }
+}
+
+impl<'a, T> FromCborRef<'a> for Option<T>
+where
+    T: FromCborRef<'a>,
+{
+    fn from_cbor_ref(val: CborRef<'a>) -> Result<Option<T>> {
+        match val {
+            CborRef::Major7(_, SimpleValue::Null) => Ok(None),
+            val => Ok(Some(T::from_cbor_ref(val)?)),
+        }
+    }
+}
+
+// Owned types are converted via [Cbor], scalar values don't allocate and
+// for others, allocation is anyway required.
+macro_rules! from_cbor_ref_via_cbor {
+    ($($t:ty)*) => {$(
+        impl<'a> FromCborRef<'a> for $t {
+            fn from_cbor_ref(val: CborRef<'a>) -> Result<$t> {
+                <$t as FromCbor>::from_cbor(val.into_cbor()?)
+            }
+        }
+    )*}
+}
+
+from_cbor_ref_via_cbor! {
+    bool f32 f64 u64 u32 u16 u8 usize i64 i32 i16 i8 isize String Key
+}
+
+#[cfg(test)]
+#[path = "cbor_ref_test.rs"]
+mod cbor_ref_test;
@@ -0,0 +1,145 @@
+use arbitrary::Unstructured;
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+
+use crate::{
+    cbor::{CborEncode, ToCbor},
+    LocalCborize,
+};
+
+#[test]
+fn test_cbor_ref() {
+    let seed: u128 = random();
+    println!("test_cbor_ref {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    for _i in 0..10000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+
+        let mut buf: Vec<u8> = vec![];
+        let n = val.encode(&mut buf).unwrap();
+        let (rval, m) = CborRef::decode(&buf).unwrap();
+        assert_eq!(n, m);
+        assert_eq!(val, rval.into_cbor().unwrap());
+    }
+}
+
+#[test]
+fn test_cbor_ref_borrow() {
+    let buf: Vec<u8> = {
+        let val: Vec<Cbor> = vec![
+            "hello world".into_cbor().unwrap(),
+            Cbor::bytes_into_cbor(vec![1, 2, 3, 4]).unwrap(),
+            SimpleValue::Null.into_cbor().unwrap(),
+            10_u64.into_cbor().unwrap(),
+        ];
+        let mut buf: Vec<u8> = vec![];
+        val.into_cbor().unwrap().encode(&mut buf).unwrap();
+        buf
+    };
+    let within = |ptr: *const u8| buf.as_ptr_range().contains(&ptr);
+
+    let (val, n) = CborRef::decode(&buf).unwrap();
+    assert_eq!(n, buf.len());
+
+    let mut items = Vec::<CborRef>::from_cbor_ref(val).unwrap();
+    assert_eq!(items.len(), 4);
+
+    let text = <&str>::from_cbor_ref(items.remove(0)).unwrap();
+    assert_eq!(text, "hello world");
+    assert!(within(text.as_ptr()));
+
+    let data = <&[u8]>::from_cbor_ref(items.remove(0)).unwrap();
+    assert_eq!(data, &[1, 2, 3, 4]);
+    assert!(within(data.as_ptr()));
+
+    assert_eq!(
+        Option::<&str>::from_cbor_ref(items.remove(0)).unwrap(),
+        None
+    );
+    assert_eq!(u64::from_cbor_ref(items.remove(0)).unwrap(), 10);
+
+    // chunked text cannot be borrowed.
+    let buf = [0x7f, 0x62, 0x68, 0x65, 0x62, 0x6c, 0x6c, 0xff];
+    let (val, n) = CborRef::decode(&buf).unwrap();
+    assert_eq!(n, buf.len());
+    assert!(<&str>::from_cbor_ref(val.clone()).is_err());
+    assert_eq!(String::from_cbor_ref(val).unwrap(), "hell");
+
+    // truncated input
+    assert!(CborRef::decode(&[0x6b, 0x68, 0x65]).is_err());
+}
+
+#[derive(Debug, PartialEq, LocalCborize)]
+struct Record<'a> {
+    key: &'a str,
+    data: &'a [u8],
+    tags: Vec<&'a str>,
+    note: Option<&'a str>,
+    seqno: u64,
+    value: Vec<u8>,
+}
+
+impl<'a> Record<'a> {
+    const ID: u32 = 10;
+}
+
+#[derive(Debug, PartialEq, LocalCborize)]
+enum Mutation<'a> {
+    Del,
+    Set { key: &'a str },
+    Put(&'a str, &'a [u8]),
+}
+
+impl<'a> Mutation<'a> {
+    const ID: u32 = 11;
+}
+
+#[test]
+fn test_cbor_ref_derive() {
+    let value = vec![5, 6];
+    let rec = Record {
+        key: "users/1",
+        data: &[1, 2, 3],
+        tags: vec!["x", "y"],
+        note: None,
+        seqno: 10,
+        value: value.clone(),
+    };
+
+    let mut buf: Vec<u8> = vec![];
+    rec.to_cbor().unwrap().encode(&mut buf).unwrap();
+    let mut out: Vec<u8> = vec![];
+    rec.encode_to(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    let within = |ptr: *const u8| buf.as_ptr_range().contains(&ptr);
+    let (val, n) = CborRef::decode(&buf).unwrap();
+    assert_eq!(n, buf.len());
+    let out = Record::from_cbor_ref(val).unwrap();
+    assert_eq!(out, rec);
+    assert!(within(out.key.as_ptr()));
+    assert!(within(out.data.as_ptr()));
+    assert!(within(out.tags[1].as_ptr()));
+
+    let muts = vec![
+        Mutation::Del,
+        Mutation::Set { key: "users/2" },
+        Mutation::Put("users/3", b"value"),
+    ];
+    let mut buf: Vec<u8> = vec![];
+    muts.to_cbor().unwrap().encode(&mut buf).unwrap();
+    let (val, _) = CborRef::decode(&buf).unwrap();
+    assert_eq!(Vec::<Mutation>::from_cbor_ref(val).unwrap(), muts);
+
+    // identifier of another type.
+    let (val, _) = CborRef::decode(&buf).unwrap();
+    assert!(Vec::<Record>::from_cbor_ref(val).is_err());
+}
@@ -0,0 +1,228 @@
+//! Module implement reader and writer for CBOR sequences, refer to
+//! [RFC 8742][rfc].
+//!
+//! A CBOR sequence is a concatenation of zero or more CBOR data items,
+//! without any framing, like log files and snapshot files that are
+//! appended one item at a time.
+//!
+//! [rfc]: https://www.rfc-editor.org/rfc/rfc8742.html
+
+use std::{io, iter::FusedIterator, marker::PhantomData};
+
+use crate::{
+    cbor::{Cbor, DecodeOptions, FromCbor, IntoCbor, SimpleValue},
+    Error, Result,
+};
+
+/// Outcome of reading the next data item from a CBOR sequence.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SeqItem<T> {
+    /// Complete data item.
+    Item(T),
+    /// Input is exhausted at the boundary of a data item.
+    Eof,
+    /// Input is exhausted in the middle of a data item, holds the number
+    /// of bytes read for the partial item. The partial item starts at
+    /// [CborSeqReader::to_offset].
+    Truncated(usize),
+}
+
+// Reader adapter that counts the bytes read from `r` and records whether
+// `r` was exhausted.
+struct Tracker<'a> {
+    r: &'a mut dyn io::Read,
+    n: usize,
+    eof: bool,
+}
+
+impl<'a> io::Read for Tracker<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.r.read(buf)?;
+        self.eof = self.eof || (n == 0 && !buf.is_empty());
+        self.n += n;
+        Ok(n)
+    }
+}
+
+/// Reader for CBOR sequence, to read one data item at a time from `R`.
+///
+/// For better performance wrap files and sockets with [io::BufReader].
+pub struct CborSeqReader<R> {
+    r: R,
+    opts: DecodeOptions,
+    n: usize,
+}
+
+impl<R> CborSeqReader<R>
+where
+    R: io::Read,
+{
+    /// Create a new reader for CBOR sequence from `r`.
+    pub fn new(r: R) -> CborSeqReader<R> {
+        Self::with_options(r, DecodeOptions::default())
+    }
+
+    /// Create a new reader for CBOR sequence from `r`, each data item
+    /// is decoded with `opts`, refer to [Cbor::decode_with].
+    pub fn with_options(r: R, opts: DecodeOptions) -> CborSeqReader<R> {
+        CborSeqReader { r, opts, n: 0 }
+    }
+
+    /// Return the number of bytes read so far, that is, the offset of the
+    /// next data item.
+    pub fn to_offset(&self) -> usize {
+        self.n
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+
+    /// Read the next data item. Clean end of input is returned as
+    /// [SeqItem::Eof] and a partial data item at the end of input is
+    /// returned as [SeqItem::Truncated]. Malformed data items, including
+    /// a break stop outside indefinite item, are returned as error.
+    pub fn read_value(&mut self) -> Result<SeqItem<Cbor>> {
+        let mut rr = Tracker {
+            r: &mut self.r,
+            n: 0,
+            eof: false,
+        };
+        match Cbor::decode_with(&mut rr, self.opts) {
+            Ok((Cbor::Major7(_, SimpleValue::Break), _)) => {
+                err_at!(FailCbor, msg: "break outside indefinite item at {}", self.n)
+            }
+            Ok((val, n)) => {
+                self.n += n;
+                Ok(SeqItem::Item(val))
+            }
+            Err(_) if rr.eof && rr.n == 0 => Ok(SeqItem::Eof),
+            Err(_) if rr.eof => Ok(SeqItem::Truncated(rr.n)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Same as [CborSeqReader::read_value], additionally convert the data
+    /// item to type `T`. Conversion failure is returned as error.
+    pub fn read_item<T>(&mut self) -> Result<SeqItem<T>>
+    where
+        T: FromCbor,
+    {
+        match self.read_value()? {
+            SeqItem::Item(val) => Ok(SeqItem::Item(T::from_cbor(val)?)),
+            SeqItem::Eof => Ok(SeqItem::Eof),
+            SeqItem::Truncated(n) => Ok(SeqItem::Truncated(n)),
+        }
+    }
+
+    /// Return an iterator over the data items, converted to type `T`.
+    /// Iteration stops at clean end of input, while a partial data item
+    /// at the end of input is returned as error. Iteration stops after
+    /// the first error.
+    pub fn items<T>(&mut self) -> SeqItems<'_, R, T>
+    where
+        T: FromCbor,
+    {
+        SeqItems {
+            seq: self,
+            done: false,
+            _item: PhantomData,
+        }
+    }
+}
+
+/// Iterator over the data items in CBOR sequence, refer to
+/// [CborSeqReader::items].
+pub struct SeqItems<'a, R, T> {
+    seq: &'a mut CborSeqReader<R>,
+    done: bool,
+    _item: PhantomData<T>,
+}
+
+impl<'a, R, T> Iterator for SeqItems<'a, R, T>
+where
+    R: io::Read,
+    T: FromCbor,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let item = match self.seq.read_item() {
+            Ok(SeqItem::Item(item)) => return Some(Ok(item)),
+            Ok(SeqItem::Eof) => None,
+            Ok(SeqItem::Truncated(n)) => {
+                let off = self.seq.to_offset();
+                Some(err_at!(FailCbor, msg: "truncated item at {}, {} bytes", off, n))
+            }
+            Err(err) => Some(Err(err)),
+        };
+        self.done = true;
+        item
+    }
+}
+
+impl<'a, R, T> FusedIterator for SeqItems<'a, R, T>
+where
+    R: io::Read,
+    T: FromCbor,
+{
+}
+
+/// Writer for CBOR sequence, to append one data item at a time into `W`.
+pub struct CborSeqWriter<W> {
+    w: W,
+    n: usize,
+}
+
+impl<W> CborSeqWriter<W>
+where
+    W: io::Write,
+{
+    /// Create a new writer for CBOR sequence into `w`.
+    pub fn new(w: W) -> CborSeqWriter<W> {
+        Self::with_offset(w, 0)
+    }
+
+    /// Create a new writer for CBOR sequence into `w`, that is already
+    /// holding `offset` bytes, like when appending to an existing file.
+    pub fn with_offset(w: W, offset: usize) -> CborSeqWriter<W> {
+        CborSeqWriter { w, n: offset }
+    }
+
+    /// Return the number of bytes written so far, that is, the offset of
+    /// the next data item.
+    pub fn to_offset(&self) -> usize {
+        self.n
+    }
+
+    /// Append a data item into the sequence, return the number of bytes
+    /// written.
+    pub fn write_item<T>(&mut self, item: T) -> Result<usize>
+    where
+        T: IntoCbor,
+    {
+        let n = item.into_cbor()?.encode(&mut self.w)?;
+        self.n += n;
+        Ok(n)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        err_at!(IOError, self.w.flush())
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.w)
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_seq_test.rs"]
+mod cbor_seq_test;
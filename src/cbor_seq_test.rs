@@ -0,0 +1,132 @@
+use super::*;
+
+#[test]
+fn test_cbor_seq() {
+    let mut seq = CborSeqWriter::new(vec![]);
+    assert_eq!(seq.write_item(10_u64).unwrap(), 1);
+    assert_eq!(seq.write_item("hello".to_string()).unwrap(), 6);
+    assert_eq!(seq.to_offset(), 7);
+    assert_eq!(seq.write_item(vec![1_u64, 2, 3]).unwrap(), 4);
+    assert_eq!(seq.to_offset(), 11);
+    let buf = seq.into_inner().unwrap();
+    assert_eq!(buf, b"\x0a\x65hello\x83\x01\x02\x03".to_vec());
+
+    // typed reads.
+    let mut seq = CborSeqReader::new(buf.as_slice());
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+    assert_eq!(seq.to_offset(), 1);
+    let item = seq.read_item::<String>().unwrap();
+    assert_eq!(item, SeqItem::Item("hello".to_string()));
+    assert_eq!(seq.to_offset(), 7);
+    let item = seq.read_item::<Vec<u64>>().unwrap();
+    assert_eq!(item, SeqItem::Item(vec![1, 2, 3]));
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Eof);
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Eof);
+    assert_eq!(seq.to_offset(), 11);
+
+    // raw values.
+    let mut seq = CborSeqReader::new(buf.as_slice());
+    let item = seq.read_value().unwrap();
+    assert_eq!(item, SeqItem::Item(10_u64.into_cbor().unwrap()));
+    let item = seq.items::<String>().next().unwrap().unwrap();
+    assert_eq!(item, "hello".to_string());
+    let item = seq.read_value().unwrap();
+    assert_eq!(item, SeqItem::Item(vec![1_u64, 2, 3].into_cbor().unwrap()));
+    assert_eq!(seq.read_value().unwrap(), SeqItem::Eof);
+
+    let items = CborSeqReader::new(&buf[1..7])
+        .items()
+        .collect::<Result<Vec<String>>>();
+    assert_eq!(items.unwrap(), vec!["hello".to_string()]);
+
+    // empty sequence.
+    let mut seq = CborSeqReader::new(&b""[..]);
+    assert_eq!(seq.read_value().unwrap(), SeqItem::Eof);
+    assert_eq!(seq.items::<u64>().count(), 0);
+
+    // conversion failure consumes the item.
+    let mut seq = CborSeqReader::new(buf.as_slice());
+    assert!(seq.read_item::<String>().is_err());
+    assert_eq!(seq.to_offset(), 1);
+    assert!(matches!(seq.read_item::<String>(), Ok(SeqItem::Item(_))));
+}
+
+#[test]
+fn test_cbor_seq_truncated() {
+    let buf = b"\x0a\x65hello\x83\x01\x02\x03".to_vec();
+
+    for n in 8..buf.len() {
+        let mut seq = CborSeqReader::new(&buf[..n]);
+        assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+        let item = seq.read_item::<String>().unwrap();
+        assert_eq!(item, SeqItem::Item("hello".to_string()));
+        let item = seq.read_item::<Vec<u64>>().unwrap();
+        assert_eq!(item, SeqItem::Truncated(n - 7), "{}", n);
+        assert_eq!(seq.to_offset(), 7);
+        assert_eq!(seq.read_value().unwrap(), SeqItem::Eof);
+
+        let mut seq = CborSeqReader::new(&buf[..n]);
+        assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+        let mut iter = seq.items::<String>();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    // truncated within text string.
+    let mut seq = CborSeqReader::new(&buf[..4]);
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+    let item = seq.read_item::<String>().unwrap();
+    assert_eq!(item, SeqItem::Truncated(3));
+
+    // malformed item is not truncation.
+    let mut seq = CborSeqReader::new(&b"\x0a\x1c\x00"[..]);
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+    assert!(seq.read_value().is_err());
+
+    // iteration stops after the first error.
+    let mut seq = CborSeqReader::new(&b"\x0a\x1c\x00\x0b"[..]);
+    let mut iter = seq.items::<u64>();
+    assert_eq!(iter.next().unwrap().unwrap(), 10);
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+
+    // break stop is not a data item, at top level.
+    let mut seq = CborSeqReader::new(&b"\xff"[..]);
+    assert!(seq.read_value().is_err());
+    let mut seq = CborSeqReader::new(&b"\x01\xff\x02"[..]);
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(1));
+    assert!(seq.read_value().is_err());
+    let mut seq = CborSeqReader::new(&b"\x9f\x01\xff"[..]);
+    assert_eq!(seq.read_item::<Vec<u64>>().unwrap(), SeqItem::Item(vec![1]));
+    assert_eq!(seq.read_value().unwrap(), SeqItem::Eof);
+
+    // decode options apply to each item.
+    let opts = DecodeOptions {
+        max_items: 2,
+        ..DecodeOptions::default()
+    };
+    let mut seq = CborSeqReader::with_options(buf.as_slice(), opts);
+    assert_eq!(seq.read_item::<u64>().unwrap(), SeqItem::Item(10));
+    assert!(matches!(seq.read_item::<String>(), Ok(SeqItem::Item(_))));
+    assert!(seq.read_item::<Vec<u64>>().is_err());
+}
+
+#[test]
+fn test_cbor_seq_append() {
+    let mut seq = CborSeqWriter::new(vec![]);
+    seq.write_item(true).unwrap();
+    let buf = seq.into_inner().unwrap();
+
+    let mut seq = CborSeqWriter::with_offset(buf, 1);
+    let off = seq.to_offset();
+    seq.write_item(-1_i64).unwrap();
+    assert_eq!((off, seq.to_offset()), (1, 2));
+    let buf = seq.into_inner().unwrap();
+
+    let mut seq = CborSeqReader::new(buf.as_slice());
+    assert_eq!(seq.read_item::<bool>().unwrap(), SeqItem::Item(true));
+    assert_eq!(seq.read_item::<i64>().unwrap(), SeqItem::Item(-1));
+    assert_eq!(seq.read_value().unwrap(), SeqItem::Eof);
+}
@@ -0,0 +1,669 @@
+//! Module implement serde [Serializer](ser::Serializer) and
+//! [Deserializer](de::Deserializer) backed by [Cbor] values.
+//!
+//! Types deriving `serde::Serialize` and `serde::Deserialize` are mapped
+//! through the [Cbor] enum, hence they can be mixed with types deriving
+//! `Cborize` in the same message. Mapping follows the externally tagged
+//! representation:
+//!
+//! * Structs and maps are encoded as cbor-map, struct fields as text keys.
+//! * Sequences, tuples and tuple-structs are encoded as cbor-list.
+//! * `None` and unit values are encoded as null.
+//! * Unit variants are encoded as text, other variants are encoded as
+//!   single entry cbor-map of `{variant-name: value}`.
+//! * 128-bit integers are encoded as bignums when they don't fit 64-bits.
+//!
+//! Wrap serde types in [Serde] to use them where [IntoCbor] and [FromCbor]
+//! are expected, like as fields of types deriving `Cborize`.
+//!
+//! Available with the `serde` feature.
+
+use serde::{de, ser, Serialize};
+
+use std::{convert::TryFrom, fmt, vec};
+
+use crate::{
+    cbor::{
+        codec_by_tree, f16_to_f32, BigNum, Cbor, FromCbor, IntoCbor, Key, SimpleValue,
+        Tag, ToCbor,
+    },
+    Error, Result,
+};
+
+/// Convert a value implementing `serde::Serialize` into [Cbor].
+pub fn to_cbor<T>(val: &T) -> Result<Cbor>
+where
+    T: Serialize + ?Sized,
+{
+    val.serialize(Serializer)
+}
+
+/// Convert [Cbor] into a value implementing `serde::Deserialize`.
+pub fn from_cbor<T>(val: Cbor) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    T::deserialize(Deserializer(val))
+}
+
+/// Wrapper for types implementing `serde::Serialize` and
+/// `serde::Deserialize`, converted via [to_cbor] and [from_cbor].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Serde<T>(pub T);
+
+impl<T> IntoCbor for Serde<T>
+where
+    T: Serialize,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        to_cbor(&self.0)
+    }
+}
+
+impl<T> ToCbor for Serde<T>
+where
+    T: Serialize,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        to_cbor(&self.0)
+    }
+}
+
+impl<T> FromCbor for Serde<T>
+where
+    T: de::DeserializeOwned,
+{
+    fn from_cbor(val: Cbor) -> Result<Serde<T>> {
+        Ok(Serde(from_cbor(val)?))
+    }
+}
+
+codec_by_tree! {[T] Serde<T>}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::FailConvert(format!("{}:{}", file!(), line!()), msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::FailConvert(format!("{}:{}", file!(), line!()), msg.to_string())
+    }
+}
+
+fn list_into_cbor(items: Vec<Cbor>) -> Result<Cbor> {
+    let n = err_at!(FailConvert, u64::try_from(items.len()))?;
+    Ok(Cbor::Major4(n.into(), items))
+}
+
+fn variant_into_cbor(variant: &'static str, val: Cbor) -> Result<Cbor> {
+    vec![(Key::Text(variant.to_string()), val)].into_cbor()
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Cbor;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_char(self, v: char) -> Result<Cbor> {
+        v.to_string().into_cbor()
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Cbor> {
+        v.into_cbor()
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Cbor> {
+        Cbor::bytes_into_cbor(v.to_vec())
+    }
+
+    fn serialize_none(self) -> Result<Cbor> {
+        SimpleValue::Null.into_cbor()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Cbor>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Cbor> {
+        SimpleValue::Null.into_cbor()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Cbor> {
+        SimpleValue::Null.into_cbor()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Cbor> {
+        variant.into_cbor()
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Cbor>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Cbor>
+    where
+        T: Serialize + ?Sized,
+    {
+        variant_into_cbor(variant, value.serialize(self)?)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList::new(None, len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        Ok(SerializeList::new(None, Some(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList::new(None, Some(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList::new(Some(variant), Some(len)))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap::new(None, len))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        Ok(SerializeMap::new(None, Some(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap> {
+        Ok(SerializeMap::new(Some(variant), Some(len)))
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    items: Vec<Cbor>,
+}
+
+impl SerializeList {
+    fn new(variant: Option<&'static str>, len: Option<usize>) -> SerializeList {
+        let items = Vec::with_capacity(len.unwrap_or(0));
+        SerializeList { variant, items }
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Cbor> {
+        let val = list_into_cbor(self.items)?;
+        match self.variant {
+            Some(variant) => variant_into_cbor(variant, val),
+            None => Ok(val),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+struct SerializeMap {
+    variant: Option<&'static str>,
+    items: Vec<(Key, Cbor)>,
+    key: Option<Key>,
+}
+
+impl SerializeMap {
+    fn new(variant: Option<&'static str>, len: Option<usize>) -> SerializeMap {
+        let items = Vec::with_capacity(len.unwrap_or(0));
+        SerializeMap {
+            variant,
+            items,
+            key: None,
+        }
+    }
+
+    fn finish(self) -> Result<Cbor> {
+        let val = self.items.into_cbor()?;
+        match self.variant {
+            Some(variant) => variant_into_cbor(variant, val),
+            None => Ok(val),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(Key::from_cbor(key.serialize(Serializer)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self.key.take() {
+            Some(key) => self.items.push((key, value.serialize(Serializer)?)),
+            None => err_at!(FailConvert, msg: "map value without key")?,
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = Key::Text(key.to_string());
+        self.items.push((key, value.serialize(Serializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = Cbor;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Cbor> {
+        self.finish()
+    }
+}
+
+struct Deserializer(Cbor);
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.0 {
+            Cbor::Major0(_, val) => visitor.visit_u64(val),
+            Cbor::Major1(_, val) => match i64::try_from(val) {
+                Ok(val) => visitor.visit_i64(-1 - val),
+                Err(_) => visitor.visit_i128(-1 - i128::from(val)),
+            },
+            Cbor::Major2(_, val) => visitor.visit_byte_buf(val),
+            Cbor::Major3(_, val) => {
+                visitor.visit_string(err_at!(FailConvert, String::from_utf8(val))?)
+            }
+            Cbor::Major4(_, items) => visitor.visit_seq(SeqAccess(items.into_iter())),
+            Cbor::Major5(_, items) => visitor.visit_map(MapAccess {
+                items: items.into_iter(),
+                value: None,
+            }),
+            val @ Cbor::Major6(_, Tag::Value(2, _))
+            | val @ Cbor::Major6(_, Tag::Value(3, _)) => match BigNum::from_cbor(val)? {
+                val @ BigNum::Pos(_) => {
+                    visitor.visit_u128(u128::from_cbor(val.into_cbor()?)?)
+                }
+                val => visitor.visit_i128(i128::from_cbor(val.into_cbor()?)?),
+            },
+            // other tags are transparent.
+            Cbor::Major6(_, tag) => {
+                Deserializer(tag.into_item()).deserialize_any(visitor)
+            }
+            Cbor::Major7(_, SimpleValue::True) => visitor.visit_bool(true),
+            Cbor::Major7(_, SimpleValue::False) => visitor.visit_bool(false),
+            Cbor::Major7(_, SimpleValue::Null) => visitor.visit_unit(),
+            Cbor::Major7(_, SimpleValue::Undefined) => visitor.visit_unit(),
+            Cbor::Major7(_, SimpleValue::F16(val)) => visitor.visit_f32(f16_to_f32(val)),
+            Cbor::Major7(_, SimpleValue::F32(val)) => visitor.visit_f32(val),
+            Cbor::Major7(_, SimpleValue::F64(val)) => visitor.visit_f64(val),
+            Cbor::Major7(_, sval) => err_at!(FailConvert, msg: "serde {:?}", sval),
+            val @ Cbor::Binary(_) => Deserializer(val.expand()?).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.0 {
+            Cbor::Major7(_, SimpleValue::Null) => visitor.visit_none(),
+            val => visitor.visit_some(Deserializer(val)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.0 {
+            val @ Cbor::Major3(_, _) => visitor.visit_enum(EnumAccess(val, None)),
+            Cbor::Major5(_, items) if items.len() == 1 => {
+                let (key, val) = items.into_iter().next().unwrap();
+                visitor.visit_enum(EnumAccess(key.into_cbor()?, Some(val)))
+            }
+            _ => err_at!(FailConvert, msg: "expected text or single entry map for enum"),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct SeqAccess(vec::IntoIter<Cbor>);
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.0.next() {
+            Some(val) => Ok(Some(seed.deserialize(Deserializer(val))?)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapAccess {
+    items: vec::IntoIter<(Key, Cbor)>,
+    value: Option<Cbor>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.items.next() {
+            Some((key, val)) => {
+                self.value = Some(val);
+                Ok(Some(seed.deserialize(Deserializer(key.into_cbor()?))?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(val) => seed.deserialize(Deserializer(val)),
+            None => err_at!(FailConvert, msg: "map value without key"),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+// variant name, and its value for non-unit variants.
+struct EnumAccess(Cbor, Option<Cbor>);
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantAccess)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(Deserializer(self.0))?;
+        Ok((variant, VariantAccess(self.1)))
+    }
+}
+
+struct VariantAccess(Option<Cbor>);
+
+impl VariantAccess {
+    fn into_value(self) -> Result<Cbor> {
+        match self.0 {
+            Some(val) => Ok(val),
+            None => err_at!(FailConvert, msg: "expected value for enum variant"),
+        }
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.0 {
+            None | Some(Cbor::Major7(_, SimpleValue::Null)) => Ok(()),
+            Some(_) => err_at!(FailConvert, msg: "expected unit variant"),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(Deserializer(self.into_value()?))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(Deserializer(self.into_value()?), visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(Deserializer(self.into_value()?), visitor)
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_serde_test.rs"]
+mod cbor_serde_test;
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use super::*;
+use crate::{
+    cbor::{CborDecode, CborEncode},
+    LocalCborize,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Point(i32, i32),
+    Rect { w: u64, h: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Unit;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Meters(u32);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Msg {
+    name: String,
+    id: u128,
+    delta: i128,
+    flag: bool,
+    ch: char,
+    tag: Option<String>,
+    none: Option<u8>,
+    shapes: Vec<Shape>,
+    pair: (i8, String),
+    unit: Unit,
+    len: Meters,
+    attrs: BTreeMap<String, i64>,
+    #[serde(with = "bytes")]
+    blob: Vec<u8>,
+}
+
+mod bytes {
+    use serde::{de, Deserializer, Serializer};
+
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(val: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(val)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        d.deserialize_byte_buf(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "byte-string")
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, val: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(val)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, LocalCborize)]
+struct Envelope {
+    route: String,
+    body: Serde<Msg>,
+    attach: Vec<Serde<Shape>>,
+}
+
+impl Envelope {
+    const ID: u32 = 0;
+}
+
+fn msg() -> Msg {
+    let mut attrs = BTreeMap::new();
+    attrs.insert("a".to_string(), -10);
+    attrs.insert("b".to_string(), 20);
+    Msg {
+        name: "hello".to_string(),
+        id: u128::MAX,
+        delta: -1,
+        flag: true,
+        ch: 'λ',
+        tag: Some("tag".to_string()),
+        none: None,
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Point(-1, 2),
+            Shape::Rect { w: 10, h: 20 },
+        ],
+        pair: (-8, "x".to_string()),
+        unit: Unit,
+        len: Meters(100),
+        attrs,
+        blob: vec![1, 2, 3],
+    }
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let val = msg();
+    let cval = to_cbor(&val).unwrap();
+
+    let mut buf: Vec<u8> = vec![];
+    cval.encode(&mut buf).unwrap();
+    let (cval, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(from_cbor::<Msg>(cval).unwrap(), val);
+}
+
+#[test]
+fn test_serde_mapping() {
+    let enc = |val: Cbor| -> Vec<u8> {
+        let mut buf: Vec<u8> = vec![];
+        val.encode(&mut buf).unwrap();
+        buf
+    };
+
+    assert_eq!(enc(to_cbor(&Shape::Empty).unwrap()), b"\x65Empty".to_vec());
+    // {"Circle": 1.5}
+    let mut buf = b"\xa1\x66Circle\xfb".to_vec();
+    buf.extend_from_slice(&1.5_f64.to_be_bytes());
+    assert_eq!(enc(to_cbor(&Shape::Circle(1.5)).unwrap()), buf);
+    // {"Point": [-1, 2]}
+    assert_eq!(
+        enc(to_cbor(&Shape::Point(-1, 2)).unwrap()),
+        b"\xa1\x65Point\x82\x20\x02".to_vec()
+    );
+    assert_eq!(enc(to_cbor(&Unit).unwrap()), vec![0xf6]);
+    assert_eq!(enc(to_cbor(&Meters(1)).unwrap()), vec![0x01]);
+    assert_eq!(enc(to_cbor(&Option::<u8>::None).unwrap()), vec![0xf6]);
+    assert_eq!(enc(to_cbor("a").unwrap()), vec![0x61, 0x61]);
+
+    // integers decode into wider and narrower types, when in range.
+    assert_eq!(
+        from_cbor::<i8>((-128_i64).into_cbor().unwrap()).unwrap(),
+        -128
+    );
+    assert!(from_cbor::<i8>((-129_i64).into_cbor().unwrap()).is_err());
+    assert_eq!(from_cbor::<f64>(1.5_f32.into_cbor().unwrap()).unwrap(), 1.5);
+
+    // unknown variant and type mismatch.
+    assert!(from_cbor::<Shape>("Square".into_cbor().unwrap()).is_err());
+    assert!(from_cbor::<Msg>(10_u64.into_cbor().unwrap()).is_err());
+}
+
+#[test]
+fn test_serde_mixed() {
+    // serde values inside mkit-derived type.
+    let env = Envelope {
+        route: "a/b".to_string(),
+        body: Serde(msg()),
+        attach: vec![Serde(Shape::Empty), Serde(Shape::Point(1, -1))],
+    };
+    let val = env.to_cbor().unwrap();
+    let items = match &val {
+        Cbor::Major4(_, items) => items.clone(),
+        val => panic!("{:?}", val),
+    };
+    assert_eq!(items[2], to_cbor(&msg()).unwrap());
+
+    let mut buf: Vec<u8> = vec![];
+    val.encode(&mut buf).unwrap();
+    let mut out: Vec<u8> = vec![];
+    env.encode_to(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    let (val, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(Envelope::from_cbor(val).unwrap(), env);
+    let (nenv, n) = Envelope::decode_from(&mut buf.as_slice()).unwrap();
+    assert_eq!((nenv, n), (env, buf.len()));
+
+    // and mkit-derived type inside a list of serde values.
+    let val = Serde(vec![Shape::Empty]).into_cbor().unwrap();
+    let items = vec![val, Envelope::ID.into_cbor().unwrap()];
+    let val = items.into_cbor().unwrap();
+    let (shapes, id): (Serde<Vec<Shape>>, u32) = FromCbor::from_cbor(val).unwrap();
+    assert_eq!((shapes, id), (Serde(vec![Shape::Empty]), 0));
+}
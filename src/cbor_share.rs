@@ -0,0 +1,300 @@
+//! Module implement value-sharing for [Arc] values, using the
+//! [shareable][TAG_SHAREABLE] and [sharedref][TAG_SHAREDREF] tags.
+//!
+//! Sharing is opt-in, use [to_cbor_shared] and [from_cbor_shared] in place
+//! of [ToCbor] and [FromCbor]. Pointer-equal `Arc` values are converted
+//! once, as tag 28, and referred by their index thereafter, as tag 29.
+//! Indices are in the order of tag 28 in the encoded data. While converting
+//! back, each shareable value is converted into a single `Arc` value that
+//! is cloned for every reference to it.
+//!
+//! `Arc` values are matched by their pointer, hence they shall be kept
+//! alive while being converted. Encoding via [CborEncode] does not share
+//! values.
+//!
+//! [CborEncode]: crate::cbor::CborEncode
+
+use std::{any::Any, cell::RefCell, collections::HashMap, mem, sync::Arc};
+
+use crate::{
+    cbor::{
+        sort_canonical, Cbor, FromCbor, IntoCbor, SimpleValue, Tag, ToCbor,
+        TAG_SHAREABLE, TAG_SHAREDREF,
+    },
+    Error, Result,
+};
+
+// Sharing state, for a single top level conversion.
+#[derive(Default)]
+struct Sharing {
+    // pointer to `Arc` values and their provisional index.
+    ptrs: HashMap<usize, u64>,
+    // `Arc` values by index, None while the value is being converted.
+    values: HashMap<u64, Option<Box<dyn Any>>>,
+}
+
+thread_local! {
+    static SHARING: RefCell<Option<Sharing>> = const { RefCell::new(None) };
+}
+
+// Restore the sharing state of the enclosing conversion, if any.
+struct Guard(Option<Sharing>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let old = self.0.take();
+        SHARING.with(|s| *s.borrow_mut() = old);
+    }
+}
+
+fn with_sharing<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = Guard(SHARING.with(|s| s.borrow_mut().replace(Sharing::default())));
+    f()
+}
+
+/// Same as [ToCbor], but pointer-equal `Arc` values, within `val`, are
+/// converted once and referred thereafter. Map entries are sorted as per
+/// [Cbor::encode_canonical], hence the value can be encoded using either
+/// [Cbor::encode] or [Cbor::encode_canonical].
+pub fn to_cbor_shared<T>(val: &T) -> Result<Cbor>
+where
+    T: ToCbor + ?Sized,
+{
+    // values are converted with provisional indices, in the order of
+    // conversion, which need not be the order of encoding, like for sets.
+    let mut val = with_sharing(|| val.to_cbor())?;
+    let mut defs = HashMap::new();
+    extract(&mut val, &mut defs)?;
+    renumber(&mut val, &mut defs, &mut HashMap::new())?;
+    Ok(val)
+}
+
+/// Same as [FromCbor], but shared values are converted into a single `Arc`
+/// value, that is cloned for every reference to it.
+pub fn from_cbor_shared<T>(val: Cbor) -> Result<T>
+where
+    T: FromCbor,
+{
+    let mut val = val;
+    annotate(&mut val, &mut 0)?;
+    with_sharing(|| T::from_cbor(val))
+}
+
+/// Sharing of an `Arc` value, while converting it into [Cbor].
+pub(crate) enum Share {
+    // value sharing is disabled, or value is not shared.
+    None,
+    // first occurrence of the value, with its provisional index.
+    Value(u64),
+    // value is already converted, with its provisional index.
+    Ref(u64),
+}
+
+impl Share {
+    pub(crate) fn from_arc<T>(arc: &Arc<T>) -> Share
+    where
+        T: ?Sized,
+    {
+        let ptr = Arc::as_ptr(arc) as *const () as usize;
+        SHARING.with(|s| match s.borrow_mut().as_mut() {
+            Some(sharing) => match sharing.ptrs.get(&ptr) {
+                Some(index) => Share::Ref(*index),
+                // only reference, it can't be referred again.
+                None if Arc::strong_count(arc) == 1 => Share::None,
+                None => {
+                    let index = sharing.ptrs.len() as u64;
+                    sharing.ptrs.insert(ptr, index);
+                    Share::Value(index)
+                }
+            },
+            None => Share::None,
+        })
+    }
+
+    // Convert the value using `f`, unless it is a reference.
+    pub(crate) fn convert<F>(self, f: F) -> Result<Cbor>
+    where
+        F: FnOnce() -> Result<Cbor>,
+    {
+        match self {
+            Share::None => f(),
+            Share::Value(index) => {
+                let items = vec![index.into_cbor()?, f()?];
+                Ok(Tag::from_value(TAG_SHAREABLE, items.into_cbor()?).into())
+            }
+            Share::Ref(index) => sharedref(index),
+        }
+    }
+}
+
+pub(crate) fn arc_from_cbor<T>(val: Cbor) -> Result<Arc<T>>
+where
+    T: FromCbor + 'static,
+{
+    if SHARING.with(|s| s.borrow().is_none()) {
+        return T::from_cbor(val).map(Arc::new);
+    }
+
+    match val {
+        Cbor::Major6(_, Tag::Value(TAG_SHAREABLE, item)) => {
+            let mut items = Vec::<Cbor>::from_cbor(*item)?;
+            let (index, val) = match (items.pop(), items.pop(), items.pop()) {
+                (Some(val), Some(index), None) => (u64::from_cbor(index)?, val),
+                (_, _, _) => err_at!(FailConvert, msg: "bad shareable value")?,
+            };
+            let dup = SHARING.with(|s| match s.borrow_mut().as_mut() {
+                Some(sharing) => sharing.values.insert(index, None).is_some(),
+                None => false,
+            });
+            if dup {
+                err_at!(FailConvert, msg: "duplicate shareable value {}", index)?
+            }
+
+            let arc = Arc::new(T::from_cbor(val)?);
+            SHARING.with(|s| {
+                if let Some(sharing) = s.borrow_mut().as_mut() {
+                    let val: Box<dyn Any> = Box::new(Arc::clone(&arc));
+                    sharing.values.insert(index, Some(val));
+                }
+            });
+            Ok(arc)
+        }
+        Cbor::Major6(_, Tag::Value(TAG_SHAREDREF, item)) => {
+            let index = u64::from_cbor(*item)?;
+            SHARING.with(|s| {
+                let s = s.borrow();
+                match s.as_ref().and_then(|sharing| sharing.values.get(&index)) {
+                    Some(Some(val)) => match val.downcast_ref::<Arc<T>>() {
+                        Some(arc) => Ok(Arc::clone(arc)),
+                        None => err_at!(FailConvert, msg: "shared value {} type", index),
+                    },
+                    Some(None) => {
+                        err_at!(FailConvert, msg: "cyclic shared value {}", index)
+                    }
+                    None => err_at!(FailConvert, msg: "missing shared value {}", index),
+                }
+            })
+        }
+        val => T::from_cbor(val).map(Arc::new),
+    }
+}
+
+fn sharedref(index: u64) -> Result<Cbor> {
+    Ok(Tag::from_value(TAG_SHAREDREF, index.into_cbor()?).into())
+}
+
+// Move shareable values into `defs`, by their provisional index, leaving
+// behind a reference.
+fn extract(val: &mut Cbor, defs: &mut HashMap<u64, Cbor>) -> Result<()> {
+    match val {
+        Cbor::Major4(_, items) => {
+            for item in items.iter_mut() {
+                extract(item, defs)?;
+            }
+        }
+        Cbor::Major5(_, map) => {
+            for (_, item) in map.iter_mut() {
+                extract(item, defs)?;
+            }
+        }
+        Cbor::Major6(_, Tag::Value(TAG_SHAREABLE, item)) => {
+            let (index, mut def) = match item.as_mut() {
+                Cbor::Major4(_, items) if items.len() == 2 => {
+                    let def = items.pop().unwrap();
+                    (u64::from_cbor(items.pop().unwrap())?, def)
+                }
+                _ => err_at!(FailConvert, msg: "bad shareable value")?,
+            };
+            extract(&mut def, defs)?;
+            defs.insert(index, def);
+            *val = sharedref(index)?;
+        }
+        Cbor::Major6(_, tag) => match tag {
+            Tag::Identifier(item) | Tag::Value(_, item) => extract(item, defs)?,
+        },
+        _ => (),
+    }
+    Ok(())
+}
+
+// Replace references in the order of encoding, the first reference to a
+// value is replaced with the value itself.
+fn renumber(
+    val: &mut Cbor,
+    defs: &mut HashMap<u64, Cbor>,
+    indices: &mut HashMap<u64, u64>,
+) -> Result<()> {
+    match val {
+        Cbor::Major4(_, items) => {
+            for item in items.iter_mut() {
+                renumber(item, defs, indices)?;
+            }
+        }
+        Cbor::Major5(_, map) => {
+            sort_canonical(map)?;
+            for (_, item) in map.iter_mut() {
+                renumber(item, defs, indices)?;
+            }
+        }
+        Cbor::Major6(_, Tag::Value(TAG_SHAREDREF, item)) => {
+            let index = match item.as_ref() {
+                Cbor::Major0(_, index) => *index,
+                _ => err_at!(FailConvert, msg: "bad shared reference")?,
+            };
+            match indices.get(&index) {
+                Some(n) => *val = sharedref(*n)?,
+                None => {
+                    indices.insert(index, indices.len() as u64);
+                    let mut def = match defs.remove(&index) {
+                        Some(def) => def,
+                        None => {
+                            err_at!(FailConvert, msg: "missing shared value {}", index)?
+                        }
+                    };
+                    renumber(&mut def, defs, indices)?;
+                    *val = Tag::from_value(TAG_SHAREABLE, def).into();
+                }
+            }
+        }
+        Cbor::Major6(_, tag) => match tag {
+            Tag::Identifier(item) | Tag::Value(_, item) => renumber(item, defs, indices)?,
+        },
+        _ => (),
+    }
+    Ok(())
+}
+
+// Prefix shareable values with their index, in the order of encoding.
+fn annotate(val: &mut Cbor, n: &mut u64) -> Result<()> {
+    match val {
+        Cbor::Major4(_, items) => {
+            for item in items.iter_mut() {
+                annotate(item, n)?;
+            }
+        }
+        Cbor::Major5(_, map) => {
+            for (_, item) in map.iter_mut() {
+                annotate(item, n)?;
+            }
+        }
+        Cbor::Major6(_, Tag::Value(TAG_SHAREABLE, item)) => {
+            let index = *n;
+            *n += 1;
+            annotate(item, n)?;
+            let def = mem::replace(item.as_mut(), SimpleValue::Null.into_cbor()?);
+            **item = vec![index.into_cbor()?, def].into_cbor()?;
+        }
+        Cbor::Major6(_, tag) => match tag {
+            Tag::Identifier(item) | Tag::Value(_, item) => annotate(item, n)?,
+        },
+        _ => (),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "cbor_share_test.rs"]
+mod cbor_share_test;
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+fn encode(val: &Cbor) -> Vec<u8> {
+    let mut buf: Vec<u8> = vec![];
+    val.encode(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_share_list() {
+    let item = Arc::new("shared value".repeat(10));
+    let list: Vec<Arc<String>> = (0..100).map(|_| Arc::clone(&item)).collect();
+
+    let val = to_cbor_shared(&list).unwrap();
+    let plain = list.to_cbor().unwrap();
+    assert!(encode(&val).len() < encode(&plain).len() / 10);
+
+    let items = match &val {
+        Cbor::Major4(_, items) => items.clone(),
+        _ => unreachable!(),
+    };
+    let def: Cbor = Tag::from_value(TAG_SHAREABLE, item.to_cbor().unwrap()).into();
+    assert_eq!(items[0], def);
+    for item in items[1..].iter() {
+        assert_eq!(item, &sharedref(0).unwrap());
+    }
+
+    // round trip via encoded bytes.
+    let (val, _) = Cbor::decode(&mut encode(&val).as_slice()).unwrap();
+    let out: Vec<Arc<String>> = from_cbor_shared(val.clone()).unwrap();
+    assert_eq!(out, list);
+    assert!(out.iter().all(|x| Arc::ptr_eq(x, &out[0])));
+
+    // without sharing, references can't be resolved.
+    assert!(Vec::<Arc<String>>::from_cbor(val).is_err());
+
+    // unique values are not tagged.
+    let val = vec![Arc::new(10_u64), Arc::new(10_u64)];
+    assert_eq!(to_cbor_shared(&val).unwrap(), val.to_cbor().unwrap());
+}
+
+#[test]
+fn test_share_order() {
+    // map values are sorted after conversion, indices follow encoding.
+    let (a, b) = (Arc::new(vec![1_u64]), Arc::new(vec![2_u64]));
+    let mut map = HashMap::new();
+    for (i, key) in ["z", "y", "x", "w", "v", "u"].iter().enumerate() {
+        let val = if i % 2 == 0 {
+            Arc::clone(&a)
+        } else {
+            Arc::clone(&b)
+        };
+        map.insert(key.to_string(), val);
+    }
+
+    let val = to_cbor_shared(&map).unwrap();
+    let entries = match &val {
+        Cbor::Major5(_, entries) => entries.clone(),
+        _ => unreachable!(),
+    };
+    let refs: Vec<(bool, u64)> = entries
+        .into_iter()
+        .map(|(_, item)| match item {
+            Cbor::Major6(_, Tag::Value(TAG_SHAREABLE, _)) => (true, 0),
+            Cbor::Major6(_, Tag::Value(TAG_SHAREDREF, n)) => {
+                (false, u64::from_cbor(*n).unwrap())
+            }
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        refs,
+        vec![
+            (true, 0),
+            (true, 0),
+            (false, 0),
+            (false, 1),
+            (false, 0),
+            (false, 1)
+        ]
+    );
+
+    let out: HashMap<String, Arc<Vec<u64>>> = from_cbor_shared(val).unwrap();
+    assert_eq!(out, map);
+    assert!(Arc::ptr_eq(&out["z"], &out["x"]));
+    assert!(Arc::ptr_eq(&out["y"], &out["u"]));
+    assert!(!Arc::ptr_eq(&out["z"], &out["y"]));
+}
+
+#[test]
+fn test_share_canonical() {
+    let (one, two) = (Arc::new("one".to_string()), Arc::new("two".to_string()));
+    let mut map = BTreeMap::new();
+    map.insert("aa".to_string(), Arc::clone(&one));
+    map.insert("b".to_string(), Arc::clone(&two));
+    map.insert("c".to_string(), Arc::clone(&one));
+
+    // canonical order of keys, "b" and "c" sort before "aa".
+    let val = to_cbor_shared(&map).unwrap();
+    for canonical in [false, true].iter() {
+        let mut buf: Vec<u8> = vec![];
+        match canonical {
+            true => val.encode_canonical(&mut buf).unwrap(),
+            false => val.encode(&mut buf).unwrap(),
+        };
+        let (val, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+        let out: BTreeMap<String, Arc<String>> = from_cbor_shared(val).unwrap();
+        assert_eq!(out, map);
+        assert!(Arc::ptr_eq(&out["aa"], &out["c"]));
+    }
+}
+
+type Tree = BTreeMap<u64, (Arc<Vec<Arc<String>>>, Arc<String>)>;
+
+#[test]
+fn test_share_nested() {
+    let leaf = Arc::new("leaf".to_string());
+    let node = Arc::new(vec![Arc::clone(&leaf), Arc::clone(&leaf)]);
+    let mut tree = BTreeMap::new();
+    tree.insert(1_u64, (Arc::clone(&node), Arc::clone(&leaf)));
+    tree.insert(2_u64, (Arc::clone(&node), Arc::clone(&leaf)));
+
+    let val = to_cbor_shared(&tree).unwrap();
+    let out: Tree = from_cbor_shared(val.clone()).unwrap();
+    assert_eq!(out, tree);
+    assert!(Arc::ptr_eq(&out[&1].0, &out[&2].0));
+    assert!(Arc::ptr_eq(&out[&1].1, &out[&1].0[1]));
+    assert!(Arc::ptr_eq(&out[&2].1, &out[&1].0[0]));
+
+    // converting by value shares the same.
+    assert_eq!(to_cbor_shared(&tree).unwrap(), val);
+    let mut val = with_sharing(|| tree.into_cbor()).unwrap();
+    let mut defs = HashMap::new();
+    extract(&mut val, &mut defs).unwrap();
+    assert_eq!(defs.len(), 2);
+}
+
+#[test]
+fn test_share_errors() {
+    let item = Arc::new(10_u64);
+    let list = vec![Arc::clone(&item), Arc::clone(&item)];
+    let val = to_cbor_shared(&list).unwrap();
+
+    // type mismatch between value and reference.
+    let items = match val {
+        Cbor::Major4(_, items) => items,
+        _ => unreachable!(),
+    };
+    let res: Result<(Arc<u64>, Arc<u32>)> =
+        from_cbor_shared(items.clone().into_cbor().unwrap());
+    assert!(res.is_err());
+
+    // reference before value.
+    let rev: Vec<Cbor> = items.into_iter().rev().collect();
+    let res: Result<Vec<Arc<u64>>> = from_cbor_shared(rev.into_cbor().unwrap());
+    assert!(res.is_err());
+
+    // reference to a missing value.
+    let val: Cbor = sharedref(1).unwrap();
+    assert!(from_cbor_shared::<Arc<u64>>(val).is_err());
+
+    // sharing state is restored after failure.
+    assert!(SHARING.with(|s| s.borrow().is_none()));
+}
@@ -0,0 +1,159 @@
+//! Module implement [string references][stringref], to compress repeated
+//! byte and text strings within a value.
+//!
+//! Using [Cbor::into_stringref] the value is enclosed in a namespace, tag
+//! [TAG_STRINGREF_NAMESPACE], and repeated strings are replaced with a
+//! reference, tag [TAG_STRINGREF], to their index in the namespace's string
+//! table. Decoding with [DecodeOptions::stringref] resolves them back into
+//! strings, otherwise they are decoded as tags. Strings are added to the
+//! table only if they are longer than their reference would be.
+//!
+//! Map keys are added to the string table, but not replaced, refer
+//! [Key][crate::cbor::Key]. Values already enclosed in a namespace are
+//! left as is.
+//!
+//! [stringref]: http://cbor.schmorp.de/stringref
+//! [DecodeOptions::stringref]: crate::cbor::DecodeOptions::stringref
+
+use std::{collections::HashMap, convert::TryFrom, mem};
+
+use crate::{
+    cbor::{
+        sort_canonical, Cbor, Ctx, Info, IntoCbor, Key, Tag, TAG_STRINGREF,
+        TAG_STRINGREF_NAMESPACE,
+    },
+    Error, Result,
+};
+
+impl Cbor {
+    /// Enclose this value in a stringref namespace, replacing repeated
+    /// strings with references. Map entries are sorted and strings are made
+    /// definite, as per [Cbor::encode_canonical], hence the returned value
+    /// can be encoded using either [Cbor::encode] or [Cbor::encode_canonical].
+    pub fn into_stringref(self) -> Result<Cbor> {
+        let mut val = self;
+        Namespace::default().replace(&mut val)?;
+        Ok(Tag::from_value(TAG_STRINGREF_NAMESPACE, val).into())
+    }
+}
+
+impl Ctx {
+    // add string to the innermost namespace, if it is long enough.
+    pub(crate) fn add_string(&mut self, val: &Cbor) {
+        if let Some(table) = self.strings.last_mut() {
+            match val {
+                Cbor::Major2(_, data) | Cbor::Major3(_, data)
+                    if data.len() >= min_len(table.len()) =>
+                {
+                    table.push(val.clone())
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub(crate) fn stringref(&self, val: Cbor) -> Result<Cbor> {
+        let index = match val {
+            Cbor::Major0(_, index) => index,
+            _ => err_at!(FailConvert, msg: "bad stringref")?,
+        };
+        let table = self.strings.last();
+        let val = usize::try_from(index).ok().and_then(|i| table?.get(i));
+        match val {
+            Some(val) => Ok(val.clone()),
+            None => err_at!(FailConvert, msg: "missing stringref {}", index),
+        }
+    }
+}
+
+// String table, while replacing strings with references.
+#[derive(Default)]
+struct Namespace {
+    bytes: HashMap<Vec<u8>, u64>,
+    texts: HashMap<Vec<u8>, u64>,
+    // number of strings in the table, including duplicates.
+    n: usize,
+}
+
+impl Namespace {
+    fn replace(&mut self, val: &mut Cbor) -> Result<()> {
+        match val {
+            // indefinite strings are not added to the table, while decoding.
+            Cbor::Major2(info, data) => {
+                *info = Info::try_from(data.len())?;
+                match self.bytes.get(data.as_slice()) {
+                    Some(index) => *val = stringref(*index)?,
+                    None => Self::add(&mut self.bytes, &mut self.n, data),
+                }
+            }
+            Cbor::Major3(info, text) => {
+                *info = Info::try_from(text.len())?;
+                match self.texts.get(text.as_slice()) {
+                    Some(index) => *val = stringref(*index)?,
+                    None => Self::add(&mut self.texts, &mut self.n, text),
+                }
+            }
+            Cbor::Major4(_, items) => {
+                for item in items.iter_mut() {
+                    self.replace(item)?;
+                }
+            }
+            Cbor::Major5(_, map) => {
+                sort_canonical(map)?;
+                for (key, item) in map.iter_mut() {
+                    match key {
+                        Key::Bytes(data) => Self::add(&mut self.bytes, &mut self.n, data),
+                        Key::Text(text) => {
+                            Self::add(&mut self.texts, &mut self.n, text.as_bytes())
+                        }
+                        _ => (),
+                    }
+                    self.replace(item)?;
+                }
+            }
+            // nested namespace has its own string table.
+            Cbor::Major6(_, Tag::Value(TAG_STRINGREF_NAMESPACE, _)) => (),
+            Cbor::Major6(_, Tag::Value(TAG_STRINGREF, _)) => {
+                err_at!(FailConvert, msg: "stringref within the value")?
+            }
+            Cbor::Major6(_, Tag::Identifier(item) | Tag::Value(_, item)) => {
+                self.replace(item)?
+            }
+            Cbor::Binary(_) => {
+                *val = mem::replace(val, Cbor::Binary(vec![])).expand()?;
+                self.replace(val)?
+            }
+            Cbor::Major0(_, _) | Cbor::Major1(_, _) | Cbor::Major7(_, _) => (),
+        }
+        Ok(())
+    }
+
+    // literal strings, if long enough, are added even if already present.
+    fn add(table: &mut HashMap<Vec<u8>, u64>, n: &mut usize, data: &[u8]) {
+        if data.len() >= min_len(*n) {
+            if !table.contains_key(data) {
+                table.insert(data.to_vec(), *n as u64);
+            }
+            *n += 1;
+        }
+    }
+}
+
+fn stringref(index: u64) -> Result<Cbor> {
+    Ok(Tag::from_value(TAG_STRINGREF, index.into_cbor()?).into())
+}
+
+// minimum length of string, to be added as the nth string in the table.
+fn min_len(n: usize) -> usize {
+    match n {
+        0..=23 => 3,
+        24..=255 => 4,
+        256..=65535 => 5,
+        65536..=0xFFFF_FFFF => 7,
+        _ => 11,
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_stringref_test.rs"]
+mod cbor_stringref_test;
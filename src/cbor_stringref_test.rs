@@ -0,0 +1,187 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+
+use crate::{
+    cbor::{DecodeOptions, FromCbor},
+    db::{Delta, Entry, Value},
+};
+
+fn encode(val: &Cbor) -> Vec<u8> {
+    let mut buf: Vec<u8> = vec![];
+    val.encode(&mut buf).unwrap();
+    buf
+}
+
+fn options() -> DecodeOptions {
+    DecodeOptions {
+        stringref: true,
+        ..DecodeOptions::default()
+    }
+}
+
+fn decode(buf: &[u8]) -> Cbor {
+    let (val, n) = Cbor::decode_with(&mut &buf[..], options()).unwrap();
+    assert_eq!(n, buf.len());
+    val
+}
+
+#[test]
+fn test_stringref_table() {
+    // "1" and "4" are short, "ttt" is short once the table has 24 strings.
+    let mut strings: Vec<String> = vec!["1", "222", "333", "4", "555", "666", "777"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+    strings.extend(('a'..='r').map(|ch| ch.to_string().repeat(3)));
+    for s in ["333", "ssss", "qqq", "rrr", "ssss", "ttt", "ttt"].iter() {
+        strings.push(s.to_string());
+    }
+
+    let val = strings
+        .clone()
+        .into_cbor()
+        .unwrap()
+        .into_stringref()
+        .unwrap();
+    let text = val.to_diagnostic();
+    let tail =
+        r#""ppp", "qqq", "rrr", 25(1), "ssss", 25(21), 25(22), 25(23), "ttt", "ttt"])"#;
+    assert!(
+        text.starts_with(r#"256(["1", "222", "333", "4", "555""#),
+        "{}",
+        text
+    );
+    assert!(text.ends_with(tail), "{}", text);
+
+    let out = decode(&encode(&val));
+    assert_eq!(Vec::<String>::from_cbor(out).unwrap(), strings);
+
+    // bytes and text are looked up separately, in the same table.
+    let items = vec![
+        Cbor::bytes_into_cbor(b"abc".to_vec()).unwrap(),
+        "abc".into_cbor().unwrap(),
+        Cbor::bytes_into_cbor(b"abc".to_vec()).unwrap(),
+        "abc".into_cbor().unwrap(),
+    ];
+    let val = items.clone().into_cbor().unwrap().into_stringref().unwrap();
+    assert_eq!(
+        val.to_diagnostic(),
+        r#"256([h'616263', "abc", 25(0), 25(1)])"#
+    );
+    assert_eq!(decode(&encode(&val)), items.into_cbor().unwrap());
+}
+
+#[test]
+fn test_stringref_nested() {
+    // map keys are added to the table, but not replaced.
+    let map = vec![
+        (Key::Text("name".to_string()), "name".into_cbor().unwrap()),
+        (Key::Text("kind".to_string()), "name".into_cbor().unwrap()),
+    ];
+    let inner: Cbor = vec!["kind", "kind"].into_cbor().unwrap();
+    let inner = inner.into_stringref().unwrap();
+    let val = vec![map.into_cbor().unwrap(), inner, "kind".into_cbor().unwrap()];
+    let val = val.into_cbor().unwrap().into_stringref().unwrap();
+    assert_eq!(
+        val.to_diagnostic(),
+        r#"256([{"kind": "name", "name": 25(1)}, 256(["kind", 25(0)]), 25(0)])"#
+    );
+
+    let text = r#"[{"kind": "name", "name": "name"}, ["kind", "kind"], "kind"]"#;
+    let buf = encode(&val);
+    assert_eq!(decode(&buf).to_diagnostic(), text);
+
+    // containers within a namespace are not decoded lazily.
+    let opts = DecodeOptions {
+        lazy: Some(1),
+        ..options()
+    };
+    let (out, _) = Cbor::decode_with(&mut buf.as_slice(), opts).unwrap();
+    assert_eq!(out.to_diagnostic(), text);
+
+    // references outside a namespace are left as is.
+    let val: Cbor = stringref(0).unwrap();
+    assert_eq!(decode(&encode(&val)), val);
+
+    // references beyond the table, and references in the value.
+    let bad: Cbor = vec![stringref(0).unwrap()].into_cbor().unwrap();
+    let buf = encode(&Tag::from_value(TAG_STRINGREF_NAMESPACE, bad.clone()).into());
+    assert!(Cbor::decode_with(&mut buf.as_slice(), options()).is_err());
+    assert!(bad.into_stringref().is_err());
+}
+
+#[test]
+fn test_stringref_options() {
+    let val: Cbor = vec![vec!["abc", "abc"], vec!["abc"]].into_cbor().unwrap();
+    let val = val.into_stringref().unwrap();
+    let buf = encode(&val);
+
+    // by default, tags are decoded as is and encode back to the same bytes.
+    let (out, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(out, val);
+    assert_eq!(encode(&out), buf);
+
+    // and containers within a namespace are decoded lazily.
+    let (out, _) = Cbor::decode_lazy(&mut buf.as_slice(), 2).unwrap();
+    match out {
+        Cbor::Major6(_, Tag::Value(TAG_STRINGREF_NAMESPACE, item)) => match *item {
+            Cbor::Major4(_, items) => {
+                assert!(items.iter().all(|item| matches!(item, Cbor::Binary(_))))
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+
+    let text = r#"[["abc", "abc"], ["abc"]]"#;
+    assert_eq!(decode(&buf).to_diagnostic(), text);
+}
+
+#[test]
+fn test_stringref_canonical() {
+    // map entries are sorted, and indefinite strings are made definite.
+    let map: Vec<(Key, Cbor)> = vec![("bbb", "xxx"), ("aaa", "yyy"), ("ccc", "xxx")]
+        .into_iter()
+        .map(|(k, v)| (Key::Text(k.to_string()), v.into_cbor().unwrap()))
+        .collect();
+    let val = vec![
+        map.into_cbor().unwrap(),
+        Cbor::Major3(Info::Indefinite, b"abcd".to_vec()),
+        "abcd".into_cbor().unwrap(),
+    ];
+    let val = val.into_cbor().unwrap().into_stringref().unwrap();
+
+    let text = r#"[{"aaa": "yyy", "bbb": "xxx", "ccc": "xxx"}, "abcd", "abcd"]"#;
+    let mut buf: Vec<u8> = vec![];
+    val.encode_canonical(&mut buf).unwrap();
+    assert_eq!(buf, encode(&val));
+    assert_eq!(decode(&buf).to_diagnostic(), text);
+}
+
+#[test]
+fn test_stringref_entries() {
+    let seed: u128 = random();
+    println!("test_stringref_entries {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    // variant names, like "U" and "D", are too short to be referred.
+    let entries: Vec<Entry<String, u64, i64>> = (0..100)
+        .map(|i| Entry {
+            key: format!("users/profile/{}", i % 10),
+            value: Value::U {
+                value: rng.gen(),
+                seqno: rng.gen(),
+            },
+            deltas: vec![Delta::D { seqno: rng.gen() }],
+        })
+        .collect();
+
+    let plain = entries.clone().into_cbor().unwrap();
+    let val = plain.clone().into_stringref().unwrap();
+    let buf = encode(&val);
+    assert!(buf.len() < encode(&plain).len() * 9 / 10, "{}", buf.len());
+
+    let out = Vec::<Entry<String, u64, i64>>::from_cbor(decode(&buf)).unwrap();
+    assert_eq!(out, entries);
+}
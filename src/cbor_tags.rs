@@ -0,0 +1,572 @@
+//! Module implement types for the commonly used, registered CBOR tags.
+//!
+//! Each type wraps the data item enclosed by its tag and implements
+//! [IntoCbor] and [FromCbor], converting to and from [Tag::Value]. Refer
+//! to the [IANA registry][iana] for details.
+//!
+//! Standard library types for time and network addresses are converted
+//! using tag 1, and tags 52 and 54, respectively.
+//!
+//! [iana]: https://www.iana.org/assignments/cbor-tags/cbor-tags.xhtml
+
+use std::{
+    convert::TryFrom,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    cbor::{codec_by_tree, into_cbor_by_ref, Cbor, FromCbor, IntoCbor, Tag, ToCbor},
+    Error, Result,
+};
+
+/// Tag 0, standard date/time string, RFC 3339.
+pub const TAG_DATETIME: u64 = 0;
+/// Tag 1, epoch-based date/time, in seconds.
+pub const TAG_EPOCH_TIME: u64 = 1;
+/// Tag 2, unsigned bignum.
+pub const TAG_POS_BIGNUM: u64 = 2;
+/// Tag 3, negative bignum.
+pub const TAG_NEG_BIGNUM: u64 = 3;
+/// Tag 4, decimal fraction.
+pub const TAG_DECIMAL: u64 = 4;
+/// Tag 5, bigfloat.
+pub const TAG_BIGFLOAT: u64 = 5;
+/// Tag 24, encoded CBOR data item.
+pub const TAG_EMBEDDED: u64 = 24;
+/// Tag 25, reference to the nth string in [TAG_STRINGREF_NAMESPACE].
+pub const TAG_STRINGREF: u64 = 25;
+/// Tag 28, value that may be referred by [TAG_SHAREDREF].
+pub const TAG_SHAREABLE: u64 = 28;
+/// Tag 29, reference to the nth value marked by [TAG_SHAREABLE].
+pub const TAG_SHAREDREF: u64 = 29;
+/// Tag 32, URI, RFC 3986.
+pub const TAG_URI: u64 = 32;
+/// Tag 37, binary UUID, RFC 4122.
+pub const TAG_UUID: u64 = 37;
+/// Tag 52, IPv4 address, RFC 9164.
+pub const TAG_IPV4: u64 = 52;
+/// Tag 54, IPv6 address, RFC 9164.
+pub const TAG_IPV6: u64 = 54;
+/// Tag 256, scope for string references, [TAG_STRINGREF].
+pub const TAG_STRINGREF_NAMESPACE: u64 = 256;
+/// Tag 55799, self-described CBOR.
+pub const TAG_SELF_DESCRIBE: u64 = 55799;
+
+// Return the data item enclosed by tag `num`.
+fn untag(val: Cbor, num: u64) -> Result<Cbor> {
+    match val {
+        Cbor::Major6(_, tag) if tag.to_tag_value() == num => Ok(tag.into_item()),
+        _ => err_at!(FailConvert, msg: "expected tag {}", num),
+    }
+}
+
+/// Standard date/time string, like `2013-03-21T20:04:00Z`, tag 0.
+///
+/// Text is treated as opaque, it is not validated against RFC 3339.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DateTime(String);
+
+impl DateTime {
+    /// Construct from RFC 3339 date/time string.
+    pub fn new(val: &str) -> DateTime {
+        DateTime(val.to_string())
+    }
+
+    /// Return the date/time string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl IntoCbor for DateTime {
+    fn into_cbor(self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_DATETIME, self.0.into_cbor()?).into())
+    }
+}
+
+impl ToCbor for DateTime {
+    fn to_cbor(&self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_DATETIME, self.0.to_cbor()?).into())
+    }
+}
+
+impl FromCbor for DateTime {
+    fn from_cbor(val: Cbor) -> Result<DateTime> {
+        Ok(DateTime(String::from_cbor(untag(val, TAG_DATETIME)?)?))
+    }
+}
+
+/// Epoch-based date/time, seconds relative to 1970-01-01T00:00Z, tag 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpochTime {
+    /// Integral number of seconds.
+    Secs(i64),
+    /// Fractional number of seconds.
+    Float(f64),
+}
+
+impl EpochTime {
+    /// Return the number of seconds since epoch.
+    pub fn to_secs_f64(&self) -> f64 {
+        match self {
+            EpochTime::Secs(secs) => *secs as f64,
+            EpochTime::Float(secs) => *secs,
+        }
+    }
+}
+
+impl ToCbor for EpochTime {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = match *self {
+            EpochTime::Secs(secs) => secs.into_cbor()?,
+            EpochTime::Float(secs) => secs.into_cbor()?,
+        };
+        Ok(Tag::from_value(TAG_EPOCH_TIME, val).into())
+    }
+}
+
+impl FromCbor for EpochTime {
+    fn from_cbor(val: Cbor) -> Result<EpochTime> {
+        let val = match untag(val, TAG_EPOCH_TIME)? {
+            val @ Cbor::Major0(_, _) | val @ Cbor::Major1(_, _) => {
+                EpochTime::Secs(i64::from_cbor(val)?)
+            }
+            val @ Cbor::Major7(_, _) => EpochTime::Float(f64::from_cbor(val)?),
+            _ => err_at!(FailConvert, msg: "epoch time not a number")?,
+        };
+        Ok(val)
+    }
+}
+
+/// Arbitrary precision integer, tag 2 and tag 3.
+///
+/// Magnitude is in big-endian byte order. As with major type 1, value of
+/// a negative bignum is `-1 - n`, where `n` is the magnitude.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BigNum {
+    /// Positive bignum, tag 2.
+    Pos(Vec<u8>),
+    /// Negative bignum, tag 3.
+    Neg(Vec<u8>),
+}
+
+impl BigNum {
+    /// Return whether this is a negative bignum.
+    pub fn is_negative(&self) -> bool {
+        matches!(self, BigNum::Neg(_))
+    }
+
+    /// Return the magnitude in big-endian byte order.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            BigNum::Pos(bytes) => bytes,
+            BigNum::Neg(bytes) => bytes,
+        }
+    }
+}
+
+impl IntoCbor for BigNum {
+    fn into_cbor(self) -> Result<Cbor> {
+        let val = match self {
+            BigNum::Pos(bytes) => {
+                Tag::from_value(TAG_POS_BIGNUM, Cbor::bytes_into_cbor(bytes)?)
+            }
+            BigNum::Neg(bytes) => {
+                Tag::from_value(TAG_NEG_BIGNUM, Cbor::bytes_into_cbor(bytes)?)
+            }
+        };
+        Ok(val.into())
+    }
+}
+
+impl ToCbor for BigNum {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.clone().into_cbor()
+    }
+}
+
+impl FromCbor for BigNum {
+    fn from_cbor(val: Cbor) -> Result<BigNum> {
+        let val = match val {
+            Cbor::Major6(_, Tag::Value(TAG_POS_BIGNUM, val)) => {
+                BigNum::Pos(val.into_bytes()?)
+            }
+            Cbor::Major6(_, Tag::Value(TAG_NEG_BIGNUM, val)) => {
+                BigNum::Neg(val.into_bytes()?)
+            }
+            _ => err_at!(FailConvert, msg: "not a bignum")?,
+        };
+        Ok(val)
+    }
+}
+
+/// Mantissa for [Decimal] and [BigFloat], either an integer or a bignum.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Mantissa {
+    Int(i64),
+    Big(BigNum),
+}
+
+impl ToCbor for Mantissa {
+    fn to_cbor(&self) -> Result<Cbor> {
+        match self {
+            Mantissa::Int(val) => val.to_cbor(),
+            Mantissa::Big(val) => val.to_cbor(),
+        }
+    }
+}
+
+impl FromCbor for Mantissa {
+    fn from_cbor(val: Cbor) -> Result<Mantissa> {
+        let val = match val {
+            Cbor::Major0(_, num) => match i64::try_from(num) {
+                Ok(num) => Mantissa::Int(num),
+                Err(_) => Mantissa::Big(BigNum::Pos(num.to_be_bytes().to_vec())),
+            },
+            Cbor::Major1(_, num) => match i64::try_from(num) {
+                Ok(num) => Mantissa::Int(-1 - num),
+                Err(_) => Mantissa::Big(BigNum::Neg(num.to_be_bytes().to_vec())),
+            },
+            val => Mantissa::Big(BigNum::from_cbor(val)?),
+        };
+        Ok(val)
+    }
+}
+
+// encode [exponent, mantissa] as tagged list.
+fn exp_to_cbor(num: u64, exponent: i64, mantissa: &Mantissa) -> Result<Cbor> {
+    let items = vec![exponent.to_cbor()?, mantissa.to_cbor()?];
+    Ok(Tag::from_value(num, items.into_cbor()?).into())
+}
+
+fn exp_from_cbor(val: Cbor, num: u64) -> Result<(i64, Mantissa)> {
+    match untag(val, num)? {
+        Cbor::Major4(_, items) if items.len() == 2 => {
+            let mut items = items.into_iter();
+            let exponent = i64::from_cbor(items.next().unwrap())?;
+            let mantissa = Mantissa::from_cbor(items.next().unwrap())?;
+            Ok((exponent, mantissa))
+        }
+        _ => err_at!(FailConvert, msg: "expected [exponent, mantissa]"),
+    }
+}
+
+/// Decimal fraction, value is `mantissa * 10^exponent`, tag 4.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Decimal {
+    pub exponent: i64,
+    pub mantissa: Mantissa,
+}
+
+impl ToCbor for Decimal {
+    fn to_cbor(&self) -> Result<Cbor> {
+        exp_to_cbor(TAG_DECIMAL, self.exponent, &self.mantissa)
+    }
+}
+
+impl FromCbor for Decimal {
+    fn from_cbor(val: Cbor) -> Result<Decimal> {
+        let (exponent, mantissa) = exp_from_cbor(val, TAG_DECIMAL)?;
+        Ok(Decimal { exponent, mantissa })
+    }
+}
+
+/// Binary floating point, value is `mantissa * 2^exponent`, tag 5.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BigFloat {
+    pub exponent: i64,
+    pub mantissa: Mantissa,
+}
+
+impl ToCbor for BigFloat {
+    fn to_cbor(&self) -> Result<Cbor> {
+        exp_to_cbor(TAG_BIGFLOAT, self.exponent, &self.mantissa)
+    }
+}
+
+impl FromCbor for BigFloat {
+    fn from_cbor(val: Cbor) -> Result<BigFloat> {
+        let (exponent, mantissa) = exp_from_cbor(val, TAG_BIGFLOAT)?;
+        Ok(BigFloat { exponent, mantissa })
+    }
+}
+
+/// Encoded CBOR data item, carried as byte-string, tag 24.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Embedded(Vec<u8>);
+
+impl Embedded {
+    /// Construct by encoding the data item `val`.
+    pub fn new(val: &Cbor) -> Result<Embedded> {
+        let mut data: Vec<u8> = vec![];
+        val.encode(&mut data)?;
+        Ok(Embedded(data))
+    }
+
+    /// Construct from already encoded data item.
+    pub fn from_bytes(data: Vec<u8>) -> Embedded {
+        Embedded(data)
+    }
+
+    /// Return the encoded data item.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Decode the embedded data item.
+    pub fn to_item(&self) -> Result<Cbor> {
+        Cbor::Binary(self.0.clone()).expand()
+    }
+}
+
+impl IntoCbor for Embedded {
+    fn into_cbor(self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_EMBEDDED, Cbor::bytes_into_cbor(self.0)?).into())
+    }
+}
+
+impl ToCbor for Embedded {
+    fn to_cbor(&self) -> Result<Cbor> {
+        self.clone().into_cbor()
+    }
+}
+
+impl FromCbor for Embedded {
+    fn from_cbor(val: Cbor) -> Result<Embedded> {
+        Ok(Embedded(untag(val, TAG_EMBEDDED)?.into_bytes()?))
+    }
+}
+
+/// Uniform resource identifier, tag 32.
+///
+/// Text is treated as opaque, it is not validated against RFC 3986.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Uri(String);
+
+impl Uri {
+    /// Construct from URI string.
+    pub fn new(val: &str) -> Uri {
+        Uri(val.to_string())
+    }
+
+    /// Return the URI string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl IntoCbor for Uri {
+    fn into_cbor(self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_URI, self.0.into_cbor()?).into())
+    }
+}
+
+impl ToCbor for Uri {
+    fn to_cbor(&self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_URI, self.0.to_cbor()?).into())
+    }
+}
+
+impl FromCbor for Uri {
+    fn from_cbor(val: Cbor) -> Result<Uri> {
+        Ok(Uri(String::from_cbor(untag(val, TAG_URI)?)?))
+    }
+}
+
+/// Binary UUID, 16 bytes, tag 37.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    /// Construct from UUID bytes, in network byte order.
+    pub fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid(bytes)
+    }
+
+    /// Return the UUID bytes.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+impl ToCbor for Uuid {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = Cbor::bytes_into_cbor(self.0.to_vec())?;
+        Ok(Tag::from_value(TAG_UUID, val).into())
+    }
+}
+
+impl FromCbor for Uuid {
+    fn from_cbor(val: Cbor) -> Result<Uuid> {
+        let bytes = untag(val, TAG_UUID)?.into_bytes()?;
+        match <[u8; 16]>::try_from(bytes.as_slice()) {
+            Ok(bytes) => Ok(Uuid(bytes)),
+            Err(_) => err_at!(FailConvert, msg: "uuid of {} bytes", bytes.len()),
+        }
+    }
+}
+
+/// SystemTime is encoded as [EpochTime], integral seconds are encoded as
+/// integer, others as floating point with sub-microsecond precision lost.
+impl ToCbor for SystemTime {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let (dur, neg) = match self.duration_since(UNIX_EPOCH) {
+            Ok(dur) => (dur, false),
+            Err(err) => (err.duration(), true),
+        };
+        let val = match dur.subsec_nanos() {
+            0 => {
+                let secs = err_at!(FailConvert, i64::try_from(dur.as_secs()))?;
+                EpochTime::Secs(if neg { -secs } else { secs })
+            }
+            _ if neg => EpochTime::Float(-dur.as_secs_f64()),
+            _ => EpochTime::Float(dur.as_secs_f64()),
+        };
+        val.into_cbor()
+    }
+}
+
+impl FromCbor for SystemTime {
+    fn from_cbor(val: Cbor) -> Result<SystemTime> {
+        let (dur, neg) = match EpochTime::from_cbor(val)? {
+            EpochTime::Secs(secs) => (Duration::from_secs(secs.unsigned_abs()), secs < 0),
+            EpochTime::Float(secs) => {
+                let dur = err_at!(FailConvert, Duration::try_from_secs_f64(secs.abs()))?;
+                (dur, secs < 0.0)
+            }
+        };
+        let val = match neg {
+            true => UNIX_EPOCH.checked_sub(dur),
+            false => UNIX_EPOCH.checked_add(dur),
+        };
+        match val {
+            Some(val) => Ok(val),
+            None => err_at!(FailConvert, msg: "epoch time {:?} out of range", dur),
+        }
+    }
+}
+
+impl ToCbor for Ipv4Addr {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = Cbor::bytes_into_cbor(self.octets().to_vec())?;
+        Ok(Tag::from_value(TAG_IPV4, val).into())
+    }
+}
+
+impl FromCbor for Ipv4Addr {
+    fn from_cbor(val: Cbor) -> Result<Ipv4Addr> {
+        let bytes = untag(val, TAG_IPV4)?.into_bytes()?;
+        match <[u8; 4]>::try_from(bytes.as_slice()) {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(_) => err_at!(FailConvert, msg: "ipv4 address of {} bytes", bytes.len()),
+        }
+    }
+}
+
+impl ToCbor for Ipv6Addr {
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = Cbor::bytes_into_cbor(self.octets().to_vec())?;
+        Ok(Tag::from_value(TAG_IPV6, val).into())
+    }
+}
+
+impl FromCbor for Ipv6Addr {
+    fn from_cbor(val: Cbor) -> Result<Ipv6Addr> {
+        let bytes = untag(val, TAG_IPV6)?.into_bytes()?;
+        match <[u8; 16]>::try_from(bytes.as_slice()) {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(_) => err_at!(FailConvert, msg: "ipv6 address of {} bytes", bytes.len()),
+        }
+    }
+}
+
+impl ToCbor for IpAddr {
+    fn to_cbor(&self) -> Result<Cbor> {
+        match self {
+            IpAddr::V4(addr) => addr.to_cbor(),
+            IpAddr::V6(addr) => addr.to_cbor(),
+        }
+    }
+}
+
+impl FromCbor for IpAddr {
+    fn from_cbor(val: Cbor) -> Result<IpAddr> {
+        match &val {
+            Cbor::Major6(_, tag) if tag.to_tag_value() == TAG_IPV6 => {
+                Ok(IpAddr::V6(Ipv6Addr::from_cbor(val)?))
+            }
+            _ => Ok(IpAddr::V4(Ipv4Addr::from_cbor(val)?)),
+        }
+    }
+}
+
+/// SocketAddr is encoded as two item list, `[ip-address, port]`. Flow
+/// info and scope id of IPv6 socket addresses are not encoded.
+impl ToCbor for SocketAddr {
+    fn to_cbor(&self) -> Result<Cbor> {
+        (self.ip(), self.port()).into_cbor()
+    }
+}
+
+impl FromCbor for SocketAddr {
+    fn from_cbor(val: Cbor) -> Result<SocketAddr> {
+        let (ip, port) = <(IpAddr, u16)>::from_cbor(val)?;
+        Ok(SocketAddr::new(ip, port))
+    }
+}
+
+/// Self-described CBOR, tag 55799, to mark the data as CBOR.
+///
+/// Encodes as `0xd9d9f7` followed by the enclosed value.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SelfDescribe<T>(pub T);
+
+impl<T> SelfDescribe<T> {
+    /// Unwrap the enclosed value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> IntoCbor for SelfDescribe<T>
+where
+    T: IntoCbor,
+{
+    fn into_cbor(self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_SELF_DESCRIBE, self.0.into_cbor()?).into())
+    }
+}
+
+impl<T> ToCbor for SelfDescribe<T>
+where
+    T: ToCbor,
+{
+    fn to_cbor(&self) -> Result<Cbor> {
+        Ok(Tag::from_value(TAG_SELF_DESCRIBE, self.0.to_cbor()?).into())
+    }
+}
+
+impl<T> FromCbor for SelfDescribe<T>
+where
+    T: FromCbor,
+{
+    fn from_cbor(val: Cbor) -> Result<SelfDescribe<T>> {
+        Ok(SelfDescribe(T::from_cbor(untag(val, TAG_SELF_DESCRIBE)?)?))
+    }
+}
+
+into_cbor_by_ref! {
+    EpochTime, Mantissa, Decimal, BigFloat, Uuid, SystemTime, Ipv4Addr, Ipv6Addr,
+    IpAddr, SocketAddr,
+}
+
+codec_by_tree! {
+    [] DateTime, [] EpochTime, [] BigNum, [] Mantissa, [] Decimal, [] BigFloat,
+    [] Embedded, [] Uri, [] Uuid, [] SystemTime, [] Ipv4Addr, [] Ipv6Addr, [] IpAddr,
+    [] SocketAddr, [T] SelfDescribe<T>,
+}
+
+#[cfg(test)]
+#[path = "cbor_tags_test.rs"]
+mod cbor_tags_test;
@@ -0,0 +1,224 @@
+use super::*;
+
+fn encode<T: IntoCbor>(val: T) -> Vec<u8> {
+    let mut buf: Vec<u8> = vec![];
+    val.into_cbor().unwrap().encode(&mut buf).unwrap();
+    buf
+}
+
+fn decode<T: FromCbor>(buf: &[u8]) -> T {
+    let (val, n) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert_eq!(n, buf.len());
+    T::from_cbor(val).unwrap()
+}
+
+#[test]
+fn test_datetime() {
+    // examples from RFC 8949, appendix A.
+    let buf = [
+        0xc0, 0x74, 0x32, 0x30, 0x31, 0x33, 0x2d, 0x30, 0x33, 0x2d, 0x32, 0x31, 0x54,
+        0x32, 0x30, 0x3a, 0x30, 0x34, 0x3a, 0x30, 0x30, 0x5a,
+    ];
+    let val = DateTime::new("2013-03-21T20:04:00Z");
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<DateTime>(&buf), val);
+    assert_eq!(val.as_str(), "2013-03-21T20:04:00Z");
+
+    let buf = [0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0];
+    assert_eq!(encode(EpochTime::Secs(1363896240)), buf);
+    assert_eq!(decode::<EpochTime>(&buf), EpochTime::Secs(1363896240));
+
+    let buf = [0xc1, 0xfb, 0x41, 0xd4, 0x52, 0xd9, 0xec, 0x20, 0x00, 0x00];
+    assert_eq!(encode(EpochTime::Float(1363896240.5)), buf);
+    let val = decode::<EpochTime>(&buf);
+    assert_eq!(val, EpochTime::Float(1363896240.5));
+    assert_eq!(val.to_secs_f64(), 1363896240.5);
+
+    // tag mismatch.
+    let (val, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert!(DateTime::from_cbor(val).is_err());
+}
+
+#[test]
+fn test_bignum() {
+    let bytes = vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    let buf = [0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+    let val = BigNum::Pos(bytes.clone());
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<BigNum>(&buf), val);
+    assert!(!val.is_negative());
+
+    let buf = [0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+    let val = BigNum::Neg(bytes.clone());
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<BigNum>(&buf), val);
+    assert!(val.is_negative());
+    assert_eq!(val.as_bytes(), bytes.as_slice());
+}
+
+#[test]
+fn test_decimal_bigfloat() {
+    // 273.15
+    let buf = [0xc4, 0x82, 0x21, 0x19, 0x6a, 0xb3];
+    let val = Decimal {
+        exponent: -2,
+        mantissa: Mantissa::Int(27315),
+    };
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<Decimal>(&buf), val);
+
+    // 1.5
+    let buf = [0xc5, 0x82, 0x20, 0x03];
+    let val = BigFloat {
+        exponent: -1,
+        mantissa: Mantissa::Int(3),
+    };
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<BigFloat>(&buf), val);
+
+    let val = Decimal {
+        exponent: 10,
+        mantissa: Mantissa::Big(BigNum::Neg(vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0])),
+    };
+    assert_eq!(decode::<Decimal>(&encode(val.clone())), val);
+
+    // mantissa beyond i64 range, as major type 1.
+    let buf = [
+        0xc4, 0x82, 0x00, 0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    ];
+    let val = decode::<Decimal>(&buf);
+    assert_eq!(val.mantissa, Mantissa::Big(BigNum::Neg(vec![0xff; 8])));
+
+    // list of arity 3.
+    let buf = [0xc4, 0x83, 0x21, 0x01, 0x02];
+    let (val, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert!(Decimal::from_cbor(val).is_err());
+}
+
+#[test]
+fn test_embedded_uri_uuid() {
+    let buf = [0xd8, 0x18, 0x45, 0x64, 0x49, 0x45, 0x54, 0x46];
+    let val = Embedded::new(&"IETF".into_cbor().unwrap()).unwrap();
+    assert_eq!(encode(val.clone()), buf);
+    let val = decode::<Embedded>(&buf);
+    assert_eq!(val.as_bytes(), &buf[3..]);
+    assert_eq!(val.to_item().unwrap(), "IETF".into_cbor().unwrap());
+    assert!(Embedded::from_bytes(vec![0x82, 0x01]).to_item().is_err());
+
+    let buf = [
+        0xd8, 0x20, 0x76, 0x68, 0x74, 0x74, 0x70, 0x3a, 0x2f, 0x2f, 0x77, 0x77, 0x77,
+        0x2e, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x63, 0x6f, 0x6d,
+    ];
+    let val = Uri::new("http://www.example.com");
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<Uri>(&buf).as_str(), "http://www.example.com");
+
+    let bytes: [u8; 16] = [
+        0x8c, 0x8a, 0x1d, 0x5b, 0x2a, 0x9e, 0x4c, 0x3a, 0x9b, 0x1e, 0x4f, 0x6a, 0x10,
+        0x2d, 0x3c, 0x11,
+    ];
+    let val = Uuid::from_bytes(bytes);
+    let buf = encode(val);
+    assert_eq!(&buf[..3], &[0xd8, 0x25, 0x50]);
+    assert_eq!(&buf[3..], &bytes);
+    assert_eq!(decode::<Uuid>(&buf), val);
+
+    let buf = [0xd8, 0x25, 0x41, 0x00];
+    let (val, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert!(Uuid::from_cbor(val).is_err());
+}
+
+#[test]
+fn test_self_describe() {
+    let buf = [0xd9, 0xd9, 0xf7, 0x82, 0x01, 0x02];
+    let val = SelfDescribe(vec![1_u64, 2]);
+    assert_eq!(encode(val.clone()), buf);
+    assert_eq!(decode::<SelfDescribe<Vec<u64>>>(&buf), val);
+    assert_eq!(val.into_inner(), vec![1, 2]);
+
+    let (val, _) = Cbor::decode(&mut &buf[3..]).unwrap();
+    assert!(SelfDescribe::<Vec<u64>>::from_cbor(val).is_err());
+}
+
+#[test]
+fn test_systemtime() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    let val = UNIX_EPOCH + Duration::from_secs(1363896240);
+    let buf = [0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0];
+    assert_eq!(encode(val), buf);
+    assert_eq!(decode::<SystemTime>(&buf), val);
+
+    let val = UNIX_EPOCH + Duration::from_millis(1363896240500);
+    let buf = [0xc1, 0xfb, 0x41, 0xd4, 0x52, 0xd9, 0xec, 0x20, 0x00, 0x00];
+    assert_eq!(encode(val), buf);
+    assert_eq!(decode::<SystemTime>(&buf), val);
+
+    let val = UNIX_EPOCH - Duration::from_secs(10);
+    assert_eq!(encode(val), [0xc1, 0x29]);
+    assert_eq!(decode::<SystemTime>(&[0xc1, 0x29]), val);
+    let val = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(decode::<SystemTime>(&encode(val)), val);
+
+    // canonical encoding shortens floats to f32.
+    let val = UNIX_EPOCH + Duration::from_millis(100000500);
+    let mut buf: Vec<u8> = vec![];
+    val.into_cbor().unwrap().encode_canonical(&mut buf).unwrap();
+    assert_eq!(buf, [0xc1, 0xfa, 0x47, 0xc3, 0x50, 0x40]);
+    assert_eq!(decode::<SystemTime>(&buf), val);
+    let buf = [0xc1, 0xfa, 0x47, 0xc3, 0x50, 0x00];
+    assert_eq!(decode::<EpochTime>(&buf), EpochTime::Float(100000.0));
+
+    let now = SystemTime::now();
+    let val = decode::<SystemTime>(&encode(now));
+    let diff = match val.duration_since(now) {
+        Ok(diff) => diff,
+        Err(err) => err.duration(),
+    };
+    assert!(diff < Duration::from_micros(1), "{:?}", diff);
+
+    let val = EpochTime::Float(f64::NAN).into_cbor().unwrap();
+    assert!(SystemTime::from_cbor(val).is_err());
+    let val = DateTime::new("2013-03-21T20:04:00Z").into_cbor().unwrap();
+    assert!(SystemTime::from_cbor(val).is_err());
+}
+
+#[test]
+fn test_ip_socket_addr() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let val = Ipv4Addr::new(192, 0, 2, 1);
+    let buf = [0xd8, 0x34, 0x44, 0xc0, 0x00, 0x02, 0x01];
+    assert_eq!(encode(val), buf);
+    assert_eq!(decode::<Ipv4Addr>(&buf), val);
+    assert_eq!(decode::<IpAddr>(&buf), IpAddr::V4(val));
+
+    let val: Ipv6Addr = "2001:db8:1234:deed:beef:cafe:face:feed".parse().unwrap();
+    let buf = [
+        0xd8, 0x36, 0x50, 0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34, 0xde, 0xed, 0xbe, 0xef,
+        0xca, 0xfe, 0xfa, 0xce, 0xfe, 0xed,
+    ];
+    assert_eq!(encode(val), buf);
+    assert_eq!(decode::<Ipv6Addr>(&buf), val);
+    assert_eq!(decode::<IpAddr>(&buf), IpAddr::V6(val));
+    let (cval, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert!(Ipv4Addr::from_cbor(cval).is_err());
+
+    let addrs: Vec<SocketAddr> = vec![
+        "127.0.0.1:8080".parse().unwrap(),
+        "[::1]:9000".parse().unwrap(),
+    ];
+    for addr in addrs.into_iter() {
+        assert_eq!(decode::<SocketAddr>(&encode(addr)), addr);
+    }
+    let buf = encode(("127.0.0.1".parse::<IpAddr>().unwrap(), 80_u16));
+    assert_eq!(decode::<SocketAddr>(&buf), "127.0.0.1:80".parse().unwrap());
+
+    // wrong length and wrong tag.
+    let buf = [0xd8, 0x34, 0x43, 0xc0, 0x00, 0x02];
+    let (val, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    assert!(IpAddr::from_cbor(val).is_err());
+    let val = Uuid::from_bytes([0; 16]).into_cbor().unwrap();
+    assert!(IpAddr::from_cbor(val).is_err());
+}
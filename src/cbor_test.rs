@@ -3,6 +3,8 @@ use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
 
 use super::*;
 
+use crate::LocalCborize;
+
 #[test]
 fn test_simple_value() {
     use SimpleValue::*;
@@ -36,7 +38,6 @@ fn test_simple_value() {
             (Unassigned, Err(_)) => continue,
             (Undefined, Err(_)) => continue,
             (Reserved24(_), Err(_)) => continue,
-            (F16(_), Err(_)) => continue,
             (Break, Err(_)) => continue,
             (_, val) => val.unwrap(),
         };
@@ -74,3 +75,774 @@ fn test_cbor() {
         assert_eq!(val, nval);
     }
 }
+
+#[derive(Clone, Debug, PartialEq, LocalCborize)]
+enum Op {
+    Noop,
+    Set { key: String },
+    Halt,
+}
+
+impl Op {
+    const ID: u32 = 1;
+}
+
+#[test]
+fn test_derive_unit_variant() {
+    let ops = vec![
+        Op::Noop,
+        Op::Set {
+            key: "k".to_string(),
+        },
+        Op::Halt,
+    ];
+    for op in ops.into_iter() {
+        let val = op.clone().into_cbor().unwrap();
+        assert_eq!(Op::from_cbor(val).unwrap(), op);
+    }
+
+    let val = Op::Halt.into_cbor().unwrap();
+    let items = vec![
+        Tag::from_identifier(1_u32.into_cbor().unwrap()).into(),
+        "Halt".into_cbor().unwrap(),
+    ];
+    assert_eq!(val, items.into_cbor().unwrap());
+}
+
+#[test]
+fn test_f16() {
+    let refs: Vec<(u16, f64)> = vec![
+        (0x0000, 0.0),
+        (0x8000, -0.0),
+        (0x3c00, 1.0),
+        (0x3e00, 1.5),
+        (0x7bff, 65504.0),
+        (0x0001, 5.960464477539063e-8),
+        (0x0400, 0.00006103515625),
+        (0xc400, -4.0),
+        (0x7c00, f64::INFINITY),
+        (0xfc00, f64::NEG_INFINITY),
+    ];
+    for (half, val) in refs.into_iter() {
+        assert_eq!(f16_to_f64(half).to_bits(), val.to_bits(), "{:x}", half);
+        assert_eq!(
+            f16_to_f32(half).to_bits(),
+            (val as f32).to_bits(),
+            "{:x}",
+            half
+        );
+        assert_eq!(f64_to_f16(val), half, "{}", val);
+        assert_eq!(f32_to_f16(val as f32), half, "{}", val);
+
+        let buf = [0xf9, (half >> 8) as u8, (half & 0xff) as u8];
+        let (cval, n) = Cbor::decode(&mut &buf[..]).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(
+            f64::from_cbor(cval.clone()).unwrap().to_bits(),
+            val.to_bits()
+        );
+        assert_eq!(f32::from_cbor(cval.clone()).unwrap(), val as f32);
+
+        let mut out: Vec<u8> = vec![];
+        cval.encode(&mut out).unwrap();
+        assert_eq!(out, buf);
+    }
+
+    assert!(f16_to_f64(0x7e00).is_nan());
+    assert!(f16_to_f32(0x7e01).is_nan());
+    assert_eq!(f64_to_f16(f64::NAN) & 0x7e00, 0x7e00);
+
+    // lossless for all non-NaN values.
+    for half in 0..=u16::MAX {
+        if f16_to_f32(half).is_nan() {
+            continue;
+        }
+        assert_eq!(f32_to_f16(f16_to_f32(half)), half);
+        assert_eq!(f64_to_f16(f16_to_f64(half)), half);
+    }
+
+    // rounding, to nearest even and overflow.
+    assert_eq!(f64_to_f16(1.0 + 2_f64.powi(-11)), 0x3c00);
+    assert_eq!(f64_to_f16(1.0 + 3.0 * 2_f64.powi(-11)), 0x3c02);
+    assert_eq!(f64_to_f16(65519.0), 0x7bff);
+    assert_eq!(f64_to_f16(65520.0), 0x7c00);
+    assert_eq!(f64_to_f16(1e-10), 0x0000);
+    assert_eq!(f64_to_f16(-2_f64.powi(-25)), 0x8000);
+    assert_eq!(f64_to_f16(3.0 * 2_f64.powi(-26)), 0x0001);
+}
+
+#[test]
+fn test_canonical() {
+    let seed: u128 = random();
+    println!("test_canonical {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    for _i in 0..10000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+
+        let mut buf: Vec<u8> = vec![];
+        let n = match val.encode_canonical(&mut buf) {
+            Ok(n) => n,
+            // map keys, like 1.0 as F32 and F64, can collide after encoding.
+            Err(Error::FailCbor(_, _)) => continue,
+            Err(err) => panic!("{}", err),
+        };
+        assert_eq!(n, buf.len());
+        let (nval, m) = Cbor::decode_strict(&mut buf.as_slice()).unwrap();
+        assert_eq!(n, m);
+
+        let mut out: Vec<u8> = vec![];
+        nval.encode_canonical(&mut out).unwrap();
+        assert_eq!(out, buf);
+    }
+}
+
+#[test]
+fn test_canonical_refs() {
+    let encode = |val: Cbor| -> Vec<u8> {
+        let mut buf: Vec<u8> = vec![];
+        val.encode_canonical(&mut buf).unwrap();
+        buf
+    };
+
+    assert_eq!(encode(Cbor::Major0(Info::U64, 10)), vec![0x0a]);
+    assert_eq!(encode(Cbor::Major1(Info::U32, 500)), vec![0x39, 0x01, 0xf4]);
+    assert_eq!(
+        encode(Cbor::Major3(Info::Indefinite, b"ab".to_vec())),
+        vec![0x62, 0x61, 0x62]
+    );
+    assert_eq!(
+        encode(Cbor::Major4(
+            Info::Indefinite,
+            vec![1_u64.into_cbor().unwrap()]
+        )),
+        vec![0x81, 0x01]
+    );
+    assert_eq!(encode(1.5_f64.into_cbor().unwrap()), vec![0xf9, 0x3e, 0x00]);
+    assert_eq!(
+        encode(f64::NAN.into_cbor().unwrap()),
+        vec![0xf9, 0x7e, 0x00]
+    );
+    assert_eq!(
+        encode(f32::NAN.into_cbor().unwrap()),
+        vec![0xf9, 0x7e, 0x00]
+    );
+    assert_eq!(
+        encode(100000.0_f64.into_cbor().unwrap()),
+        vec![0xfa, 0x47, 0xc3, 0x50, 0x00]
+    );
+    assert_eq!(
+        encode(1.1_f64.into_cbor().unwrap()),
+        vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]
+    );
+
+    // floats in shorter form convert back to f64.
+    for val in [100000.0_f64, 1.5, -0.0, f64::INFINITY, 1.1].iter() {
+        let buf = encode(val.into_cbor().unwrap());
+        let (out, _) = Cbor::decode_strict(&mut buf.as_slice()).unwrap();
+        assert_eq!(f64::from_cbor(out).unwrap().to_bits(), val.to_bits());
+    }
+
+    // {"b": 2, 10: 1, "a": 3} => {10: 1, "a": 3, "b": 2}
+    let map = vec![
+        (Key::Text("b".to_string()), 2_u64.into_cbor().unwrap()),
+        (Key::U64(10), 1_u64.into_cbor().unwrap()),
+        (Key::Text("a".to_string()), 3_u64.into_cbor().unwrap()),
+    ];
+    assert_eq!(
+        encode(Cbor::Major5(Info::Indefinite, map)),
+        vec![0xa3, 0x0a, 0x01, 0x61, 0x61, 0x03, 0x61, 0x62, 0x02]
+    );
+
+    let map = vec![
+        (Key::U64(1), 2_u64.into_cbor().unwrap()),
+        (Key::U64(1), 3_u64.into_cbor().unwrap()),
+    ];
+    let mut buf: Vec<u8> = vec![];
+    assert!(Cbor::Major5(Info::Tiny(2), map)
+        .encode_canonical(&mut buf)
+        .is_err());
+}
+
+#[test]
+fn test_decode_strict() {
+    let valid: Vec<Vec<u8>> = vec![
+        vec![0x17],
+        vec![0x18, 0x18],
+        vec![0xa2, 0x01, 0x02, 0x61, 0x61, 0x03],
+        vec![0xf9, 0x7e, 0x00],
+        vec![0xfa, 0x47, 0xc3, 0x50, 0x00],
+    ];
+    for buf in valid.into_iter() {
+        let (_, n) = Cbor::decode_strict(&mut buf.as_slice()).unwrap();
+        assert_eq!(n, buf.len());
+    }
+
+    let invalid: Vec<Vec<u8>> = vec![
+        // non-shortest integer, length and tag.
+        vec![0x18, 0x17],
+        vec![0x19, 0x00, 0xff],
+        vec![0x58, 0x01, 0x00],
+        vec![0xd8, 0x01, 0x00],
+        // indefinite string and list.
+        vec![0x5f, 0x41, 0x00, 0xff],
+        vec![0x9f, 0x01, 0xff],
+        // unsorted and duplicate keys.
+        vec![0xa2, 0x61, 0x61, 0x03, 0x01, 0x02],
+        vec![0xa2, 0x01, 0x02, 0x01, 0x03],
+        // non-shortest floats and non-canonical NaN.
+        vec![0xfa, 0x3f, 0xc0, 0x00, 0x00],
+        vec![0xfb, 0x40, 0xf8, 0x6a, 0x00, 0x00, 0x00, 0x00, 0x00],
+        vec![0xf9, 0x7e, 0x01],
+        // invalid utf8.
+        vec![0x62, 0xc3, 0x28],
+    ];
+    for buf in invalid.into_iter() {
+        assert!(Cbor::decode(&mut buf.as_slice()).is_ok(), "{:x?}", buf);
+        assert!(
+            Cbor::decode_strict(&mut buf.as_slice()).is_err(),
+            "{:x?}",
+            buf
+        );
+    }
+}
+
+// expand all the lazy decoded values in the tree.
+fn expand_all(val: Cbor) -> Cbor {
+    match val.expand().unwrap() {
+        Cbor::Major4(info, list) => {
+            Cbor::Major4(info, list.into_iter().map(expand_all).collect())
+        }
+        Cbor::Major5(info, map) => {
+            let map = map.into_iter().map(|(k, v)| (k, expand_all(v))).collect();
+            Cbor::Major5(info, map)
+        }
+        Cbor::Major6(info, tag) => {
+            let num = tag.to_tag_value();
+            Cbor::Major6(info, Tag::from_value(num, expand_all(tag.into_item())))
+        }
+        val => val,
+    }
+}
+
+#[test]
+fn test_decode_lazy() {
+    let seed: u128 = random();
+    println!("test_decode_lazy {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    for _i in 0..1000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+        let mut buf: Vec<u8> = vec![];
+        let n = val.encode(&mut buf).unwrap();
+
+        let depth = rng.gen::<u32>() % 4;
+        let (lval, m) = Cbor::decode_lazy(&mut buf.as_slice(), depth).unwrap();
+        assert_eq!(n, m);
+
+        let mut out: Vec<u8> = vec![];
+        assert_eq!(lval.encode(&mut out).unwrap(), n);
+        assert_eq!(out, buf);
+        assert_eq!(expand_all(lval), val);
+    }
+}
+
+#[test]
+fn test_decode_lazy_envelope() {
+    // ["route", {"a": [1, 2]}, [_ 3]]
+    let buf = vec![
+        0x83, 0x65, 0x72, 0x6f, 0x75, 0x74, 0x65, 0xa1, 0x61, 0x61, 0x82, 0x01, 0x02,
+        0x9f, 0x03, 0xff,
+    ];
+    let (val, n) = Cbor::decode_lazy(&mut buf.as_slice(), 1).unwrap();
+    assert_eq!(n, buf.len());
+    let items = match val {
+        Cbor::Major4(_, items) => items,
+        val => panic!("{:?}", val),
+    };
+    assert_eq!(String::from_cbor(items[0].clone()).unwrap(), "route");
+    assert_eq!(items[1], Cbor::Binary(buf[7..13].to_vec()));
+    assert_eq!(items[2], Cbor::Binary(buf[13..].to_vec()));
+
+    let val = items[2].clone().expand().unwrap();
+    assert_eq!(Vec::<u64>::from_cbor(val).unwrap(), vec![3]);
+
+    let (val, _) = Cbor::decode_lazy(&mut buf.as_slice(), 0).unwrap();
+    assert_eq!(val, Cbor::Binary(buf.clone()));
+
+    assert!(Cbor::Binary(vec![0x01, 0x02]).expand().is_err());
+    assert!(Cbor::decode_lazy(&mut &buf[..10], 1).is_err());
+}
+
+#[test]
+fn test_skip_encoded_len() {
+    let seed: u128 = random();
+    println!("test_skip_encoded_len {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let mut buf: Vec<u8> = vec![];
+    let mut offsets: Vec<usize> = vec![];
+    for _i in 0..1000 {
+        let val: Cbor = {
+            let bytes: Vec<u8> = (0..100)
+                .flat_map(|_| rng.gen::<[u8; 32]>().to_vec())
+                .collect();
+            let mut uns = Unstructured::new(&bytes);
+            uns.arbitrary().unwrap()
+        };
+        offsets.push(buf.len());
+        let n = val.encode(&mut buf).unwrap();
+        assert_eq!(val.encoded_len().unwrap(), n);
+    }
+
+    let mut r = buf.as_slice();
+    let mut offset = 0;
+    for off in offsets.into_iter() {
+        assert_eq!(offset, off);
+        offset += Cbor::skip(&mut r).unwrap();
+    }
+    assert_eq!(offset, buf.len());
+    assert!(r.is_empty());
+}
+
+#[test]
+fn test_skip_indefinite() {
+    // [_ 1, [2, 3], (_ h'0102', h'03'), {_ "a": 39("b")}], 10
+    let buf = [
+        0x9f, 0x01, 0x82, 0x02, 0x03, 0x5f, 0x42, 0x01, 0x02, 0x41, 0x03, 0xff, 0xbf,
+        0x61, 0x61, 0xd8, 0x27, 0x61, 0x62, 0xff, 0xff, 0x0a,
+    ];
+    let mut r = &buf[..];
+    assert_eq!(Cbor::skip(&mut r).unwrap(), buf.len() - 1);
+    assert_eq!(r, &[0x0a]);
+
+    let (val, _) = Cbor::decode(&mut &buf[..]).unwrap();
+    let mut out: Vec<u8> = vec![];
+    // indefinite byte-string is re-encoded as single chunk.
+    assert_eq!(val.encode(&mut out).unwrap(), buf.len() - 2);
+    assert_eq!(val.encoded_len().unwrap(), out.len());
+
+    // truncated string, break in key position and stray break.
+    assert!(Cbor::skip(&mut &[0x43, 0x01, 0x02][..]).is_err());
+    assert!(Cbor::skip(&mut &[0xbf, 0x61, 0x61, 0xff][..]).is_err());
+    assert!(Cbor::skip(&mut &[0xff][..]).is_err());
+    assert!(Cbor::skip(&mut &[0x82, 0x01, 0xff][..]).is_err());
+
+    let mut buf = vec![0x81; 100];
+    buf.push(0x01);
+    assert_eq!(Cbor::skip(&mut buf.as_slice()).unwrap(), buf.len());
+}
+
+#[test]
+fn test_decode_options() {
+    let check = |buf: &[u8], opts: DecodeOptions, msg: &str| match Cbor::decode_with(
+        &mut &buf[..],
+        opts,
+    ) {
+        Err(Error::FailCbor(_, err)) => assert!(err.contains(msg), "{}", err),
+        res => panic!("{:?}", res),
+    };
+
+    // byte-string claiming 2^40 bytes, must fail without allocating.
+    let buf = [0x5b, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    assert!(Cbor::decode(&mut &buf[..]).is_err());
+    let opts = DecodeOptions {
+        max_string: 1024,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "decode max_string 1024 exceeded: 1099511627776");
+    let opts = DecodeOptions {
+        max_size: 1024,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "max_size");
+
+    // indefinite text-string, in aggregate.
+    let buf = [0x7f, 0x62, 0x61, 0x62, 0x62, 0x63, 0x64, 0xff];
+    let opts = DecodeOptions {
+        max_string: 3,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "decode max_string 3 exceeded: 4");
+
+    // list claiming u64::MAX items.
+    let buf = [0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let opts = DecodeOptions {
+        max_items: 1000,
+        ..DecodeOptions::default()
+    };
+    check(
+        &buf,
+        opts,
+        "decode max_items 1000 exceeded: 18446744073709551615",
+    );
+    // {_ 1: 2, 3: 4}
+    let buf = [0xbf, 0x01, 0x02, 0x03, 0x04, 0xff];
+    let opts = DecodeOptions {
+        max_items: 1,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "max_items");
+
+    let buf = [0x81, 0x81, 0x81, 0x01];
+    let opts = DecodeOptions {
+        max_depth: 3,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "decode max_depth 3 exceeded: 4");
+    let opts = DecodeOptions {
+        max_depth: 4,
+        ..DecodeOptions::default()
+    };
+    assert_eq!(Cbor::decode_with(&mut &buf[..], opts).unwrap().1, 4);
+
+    let opts = DecodeOptions {
+        max_size: 3,
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "decode max_size 3 exceeded: 4");
+    let opts = DecodeOptions {
+        max_size: 3,
+        lazy: Some(1),
+        ..DecodeOptions::default()
+    };
+    check(&buf, opts, "decode max_size 3 exceeded: 4");
+    let opts = DecodeOptions {
+        max_size: 4,
+        lazy: Some(1),
+        ..DecodeOptions::default()
+    };
+    let (val, n) = Cbor::decode_with(&mut &buf[..], opts).unwrap();
+    assert_eq!(n, 4);
+    assert_eq!(
+        val,
+        Cbor::Major4(Info::Tiny(1), vec![Cbor::Binary(buf[1..].to_vec())])
+    );
+}
+
+#[test]
+fn test_tag_value() {
+    // 1(1363896240), 32("a"), 55799([0(""), 39(1)]), 10
+    let buf = [
+        0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0, 0xd8, 0x20, 0x61, 0x61, 0xd9, 0xd9, 0xf7,
+        0x82, 0xc0, 0x60, 0xd8, 0x27, 0x01, 0x0a,
+    ];
+    let mut r = &buf[..];
+    let mut vals = vec![];
+    while !r.is_empty() {
+        let (val, _) = Cbor::decode(&mut r).unwrap();
+        vals.push(val);
+    }
+    assert_eq!(vals.len(), 4);
+
+    match &vals[0] {
+        Cbor::Major6(Info::Tiny(1), tag) => {
+            assert_eq!(tag.to_tag_value(), 1);
+            assert_eq!(u64::from_cbor(tag.as_item().clone()).unwrap(), 1363896240);
+        }
+        val => panic!("{:?}", val),
+    }
+    match &vals[2] {
+        Cbor::Major6(Info::U16, Tag::Value(55799, val)) => match val.as_ref() {
+            Cbor::Major4(_, items) => {
+                assert_eq!(items[0], Tag::from_value(0, "".into_cbor().unwrap()).into());
+                assert_eq!(
+                    items[1],
+                    Tag::from_identifier(1_u64.into_cbor().unwrap()).into()
+                );
+            }
+            val => panic!("{:?}", val),
+        },
+        val => panic!("{:?}", val),
+    }
+    assert_eq!(vals[3], 10_u64.into_cbor().unwrap());
+
+    let mut out: Vec<u8> = vec![];
+    for val in vals.iter() {
+        val.encode(&mut out).unwrap();
+    }
+    assert_eq!(out, buf);
+
+    let mut r = &buf[..];
+    assert_eq!(Cbor::skip(&mut r).unwrap(), 6);
+    assert_eq!(Cbor::skip(&mut r).unwrap(), 4);
+
+    // tag without its data item.
+    assert!(Cbor::decode(&mut &[0xc1][..]).is_err());
+    assert!(Cbor::decode(&mut &[0x9f, 0xc1, 0xff][..]).is_err());
+}
+
+#[test]
+fn test_int128() {
+    let refs: Vec<(u128, Vec<u8>)> = vec![
+        (0, vec![0x00]),
+        (
+            u128::from(u64::MAX),
+            vec![0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ),
+        (1 << 64, vec![0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]),
+        (u128::MAX, [vec![0xc2, 0x50], vec![0xff; 16]].concat()),
+    ];
+    for (val, buf) in refs.into_iter() {
+        let mut out: Vec<u8> = vec![];
+        val.into_cbor().unwrap().encode(&mut out).unwrap();
+        assert_eq!(out, buf, "{}", val);
+        let (cval, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(u128::from_cbor(cval).unwrap(), val);
+    }
+
+    let refs: Vec<(i128, Vec<u8>)> = vec![
+        (-1, vec![0x20]),
+        (
+            -(1 << 64),
+            vec![0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ),
+        (
+            -(1 << 64) - 1,
+            vec![0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0],
+        ),
+        (i128::MAX, [vec![0xc2, 0x50, 0x7f], vec![0xff; 15]].concat()),
+        (i128::MIN, [vec![0xc3, 0x50, 0x7f], vec![0xff; 15]].concat()),
+    ];
+    for (val, buf) in refs.into_iter() {
+        let mut out: Vec<u8> = vec![];
+        val.into_cbor().unwrap().encode(&mut out).unwrap();
+        assert_eq!(out, buf, "{}", val);
+        let (cval, _) = Cbor::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(i128::from_cbor(cval).unwrap(), val);
+    }
+
+    let seed: u128 = random();
+    println!("test_int128 {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+    for _i in 0..1000 {
+        let (a, b) = (
+            rng.gen::<u128>() >> (rng.gen::<u32>() % 128),
+            rng.gen::<i128>(),
+        );
+        assert_eq!(u128::from_cbor(a.into_cbor().unwrap()).unwrap(), a);
+        assert_eq!(i128::from_cbor(b.into_cbor().unwrap()).unwrap(), b);
+    }
+
+    // bignums beyond range, negative for unsigned, and leading zeros.
+    let val = BigNum::Pos(vec![0x01; 17]).into_cbor().unwrap();
+    assert!(u128::from_cbor(val).is_err());
+    let val = BigNum::Neg(vec![0x80; 16]).into_cbor().unwrap();
+    assert!(i128::from_cbor(val).is_err());
+    assert!(u128::from_cbor((-1_i64).into_cbor().unwrap()).is_err());
+    let val = BigNum::Pos([vec![0; 4], vec![0x01; 16]].concat())
+        .into_cbor()
+        .unwrap();
+    assert_eq!(
+        u128::from_cbor(val).unwrap(),
+        u128::from_be_bytes([0x01; 16])
+    );
+}
+
+#[test]
+fn test_collections() {
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+        rc::Rc,
+    };
+
+    let mut hmap: HashMap<String, u64> = HashMap::new();
+    for (i, key) in ["b", "a", "cc", "d"].iter().enumerate() {
+        hmap.insert(key.to_string(), i as u64);
+    }
+    let val = hmap.clone().into_cbor().unwrap();
+    let keys: Vec<Key> = match &val {
+        Cbor::Major5(Info::Tiny(4), map) => map.iter().map(|(k, _)| k.clone()).collect(),
+        val => panic!("{:?}", val),
+    };
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+    assert_eq!(HashMap::<String, u64>::from_cbor(val).unwrap(), hmap);
+
+    let bmap: BTreeMap<i64, Vec<u8>> =
+        vec![(-1, vec![1]), (10, vec![])].into_iter().collect();
+    let val = bmap.clone().into_cbor().unwrap();
+    assert_eq!(BTreeMap::<i64, Vec<u8>>::from_cbor(val).unwrap(), bmap);
+
+    let hset: HashSet<String> = ["x", "yy", "z"].iter().map(|s| s.to_string()).collect();
+    let (mut a, mut b) = (vec![], vec![]);
+    hset.clone().into_cbor().unwrap().encode(&mut a).unwrap();
+    let other: HashSet<String> = ["z", "yy", "x"].iter().map(|s| s.to_string()).collect();
+    other.into_cbor().unwrap().encode(&mut b).unwrap();
+    assert_eq!(a, b);
+    let (val, _) = Cbor::decode(&mut a.as_slice()).unwrap();
+    assert_eq!(HashSet::<String>::from_cbor(val).unwrap(), hset);
+
+    let bset: BTreeSet<u32> = vec![3, 1, 2].into_iter().collect();
+    let val = bset.clone().into_cbor().unwrap();
+    assert_eq!(val, vec![1_u32, 2, 3].into_cbor().unwrap());
+    assert_eq!(BTreeSet::<u32>::from_cbor(val).unwrap(), bset);
+
+    let deque: VecDeque<u8> = vec![1, 2, 3].into_iter().collect();
+    let val = deque.clone().into_cbor().unwrap();
+    assert_eq!(VecDeque::<u8>::from_cbor(val).unwrap(), deque);
+
+    // duplicates, and keys that do not convert.
+    let val = vec![1_u32, 1].into_cbor().unwrap();
+    assert!(BTreeSet::<u32>::from_cbor(val.clone()).is_err());
+    assert!(HashSet::<u32>::from_cbor(val).is_err());
+    let map = vec![
+        (Key::U64(1), true.into_cbor().unwrap()),
+        (Key::U64(1), false.into_cbor().unwrap()),
+    ];
+    let val = map.into_cbor().unwrap();
+    assert!(HashMap::<u64, bool>::from_cbor(val.clone()).is_err());
+    assert!(BTreeMap::<u64, bool>::from_cbor(val).is_err());
+    let mut hmap: HashMap<Vec<u64>, bool> = HashMap::new();
+    hmap.insert(vec![1], true);
+    assert!(hmap.into_cbor().is_err());
+
+    // smart pointers and cow.
+    let val = Box::new(10_u64).into_cbor().unwrap();
+    assert_eq!(*Box::<u64>::from_cbor(val).unwrap(), 10);
+    let rc = Rc::new("hello".to_string());
+    let val = Rc::clone(&rc).into_cbor().unwrap();
+    assert_eq!(Rc::<String>::from_cbor(val).unwrap(), rc);
+    let val = Cow::Borrowed("hello").into_cbor().unwrap();
+    assert_eq!(val, "hello".into_cbor().unwrap());
+    assert_eq!(Cow::<str>::from_cbor(val).unwrap(), "hello");
+}
+
+#[test]
+fn test_tuple_unit_char_result() {
+    let val = (1_u64, "a".to_string(), true).into_cbor().unwrap();
+    assert!(matches!(&val, Cbor::Major4(Info::Tiny(3), _)));
+    let tup = <(u64, String, bool)>::from_cbor(val.clone()).unwrap();
+    assert_eq!(tup, (1, "a".to_string(), true));
+    assert!(<(u64, String)>::from_cbor(val.clone()).is_err());
+    assert!(<(u64, String, bool, u8)>::from_cbor(val).is_err());
+
+    let tup = (1_u8, 2_u16, 3_u32, 4_u64, -5_i8, -6_i16, -7_i32, -8_i64);
+    let tup = (
+        tup.0,
+        tup.1,
+        tup.2,
+        tup.3,
+        tup.4,
+        tup.5,
+        tup.6,
+        tup.7,
+        'x',
+        (),
+        1.5_f64,
+        (9_u8,),
+    );
+    let val = tup.into_cbor().unwrap();
+    assert_eq!(FromCbor::from_cbor(val).ok(), Some(tup));
+
+    assert_eq!(
+        ().into_cbor().unwrap(),
+        SimpleValue::Null.into_cbor().unwrap()
+    );
+    assert!(<()>::from_cbor(0_u64.into_cbor().unwrap()).is_err());
+
+    let val = 'ह'.into_cbor().unwrap();
+    assert_eq!(val, "ह".into_cbor().unwrap());
+    assert_eq!(char::from_cbor(val).unwrap(), 'ह');
+    assert!(char::from_cbor("ab".into_cbor().unwrap()).is_err());
+    assert!(char::from_cbor("".into_cbor().unwrap()).is_err());
+
+    let res: std::result::Result<u64, String> = Ok(10);
+    let val = res.clone().into_cbor().unwrap();
+    assert_eq!(val, ("Ok", 10_u64).into_cbor().unwrap());
+    assert_eq!(FromCbor::from_cbor(val).ok(), Some(res));
+    let res: std::result::Result<u64, String> = Err("bad".to_string());
+    let val = res.clone().into_cbor().unwrap();
+    assert_eq!(FromCbor::from_cbor(val).ok(), Some(res));
+    let val = ("Fail", 10_u64).into_cbor().unwrap();
+    assert!(std::result::Result::<u64, u64>::from_cbor(val).is_err());
+
+    let res: Result<u64> = err_at!(IPCFail, msg: "disconnected");
+    let val = res.into_cbor().unwrap();
+    match Result::<u64>::from_cbor(val).unwrap() {
+        Err(Error::IPCFail(_, msg)) => assert_eq!(msg, "disconnected"),
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn test_duration_path() {
+    use std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    let val = Duration::new(10, 500);
+    let cval = val.into_cbor().unwrap();
+    assert_eq!(cval, (10_u64, 500_u32).into_cbor().unwrap());
+    assert_eq!(Duration::from_cbor(cval).unwrap(), val);
+    let cval = (10_u64, 1_000_000_000_u32).into_cbor().unwrap();
+    assert!(Duration::from_cbor(cval).is_err());
+
+    let val = PathBuf::from("/var/lib/mkit/data.log");
+    let cval = Path::new("/var/lib/mkit/data.log").into_cbor().unwrap();
+    assert_eq!(cval, "/var/lib/mkit/data.log".into_cbor().unwrap());
+    assert_eq!(PathBuf::from_cbor(cval).unwrap(), val);
+}
+
+#[test]
+fn test_to_cbor() {
+    use std::{collections::BTreeMap, net::SocketAddr, path::Path, time::Duration};
+
+    fn check<T: ToCbor + IntoCbor + Clone>(val: T) {
+        let cval = val.to_cbor().unwrap();
+        assert_eq!((&val).into_cbor().unwrap(), cval);
+        assert_eq!(val.into_cbor().unwrap(), cval);
+    }
+
+    check(true);
+    check(-10_i8);
+    check(u64::MAX);
+    check(i128::MIN);
+    check(1.5_f32);
+    check('x');
+    check(());
+    check("hello".to_string());
+    check(vec![Some(1_u32), None]);
+    check([[1_u8, 2], [3, 4]]);
+    check((1_u8, "a".to_string(), vec![2.5_f64]));
+    check(std::sync::Arc::new(vec![1_u16, 2]));
+    check(Box::new(Key::Text("k".to_string())));
+    check(
+        vec![(1_u64, -1_i64)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    );
+    check(Duration::new(1, 2));
+    check("127.0.0.1:80".parse::<SocketAddr>().unwrap());
+    check(BigNum::Pos(vec![1; 20]));
+    check(std::result::Result::<u8, String>::Err("bad".to_string()));
+
+    // unsized and borrowed values.
+    assert_eq!(
+        "abc".to_cbor().unwrap(),
+        "abc".to_string().into_cbor().unwrap()
+    );
+    let path = Path::new("/tmp/a");
+    assert_eq!(
+        path.to_cbor().unwrap(),
+        path.to_path_buf().into_cbor().unwrap()
+    );
+    let vals = vec!["a".to_string(), "b".to_string()];
+    let refs: Vec<&String> = vals.iter().collect();
+    assert_eq!(refs.to_cbor().unwrap(), vals.into_cbor().unwrap());
+}
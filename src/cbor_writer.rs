@@ -0,0 +1,170 @@
+//! Module implement an incremental writer for CBOR data.
+//!
+//! [Cbor::encode] requires the entire value tree to be in memory before
+//! writing a single byte. [CborWriter] can open list and map containers,
+//! stream items into them and close them, without buffering the items.
+
+use std::io;
+
+use crate::{
+    cbor::{encode_addnl, encode_hdr, Cbor, Info, IntoCbor, RECURSION_LIMIT},
+    Error, Result,
+};
+
+// Open container, whose items are being written.
+struct Frame {
+    map: bool,
+    // number of entries for definite length container.
+    len: Option<u64>,
+    // number of items written so far, for map, both key and value are counted.
+    n: u64,
+}
+
+impl Frame {
+    fn to_arity(&self) -> Option<u64> {
+        match (self.map, self.len) {
+            (true, Some(len)) => len.checked_mul(2),
+            (false, Some(len)) => Some(len),
+            (_, None) => None,
+        }
+    }
+}
+
+/// Incremental writer, to stream CBOR data items into `W`.
+///
+/// Use `begin_array` and `begin_map` to open a container, `write_item`
+/// to add items into the innermost open container and `end` to close it.
+/// Indefinite length containers are closed with a break stop. For map
+/// containers, items are written as key, value, key, value ...
+///
+/// Nesting is limited by [RECURSION_LIMIT] and the number of items written
+/// into definite length containers is validated against its length.
+pub struct CborWriter<W> {
+    w: W,
+    stack: Vec<Frame>,
+    n: usize,
+}
+
+impl<W> CborWriter<W>
+where
+    W: io::Write,
+{
+    /// Create a new writer, to encode CBOR data into `w`.
+    pub fn new(w: W) -> CborWriter<W> {
+        CborWriter {
+            w,
+            stack: Vec::default(),
+            n: 0,
+        }
+    }
+
+    /// Return the number of bytes written so far.
+    pub fn to_offset(&self) -> usize {
+        self.n
+    }
+
+    /// Return the number of open containers.
+    pub fn to_depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Open a list container. Supply `len` as None for indefinite encoding.
+    pub fn begin_array(&mut self, len: Option<u64>) -> Result<usize> {
+        self.begin(4, false, len)
+    }
+
+    /// Open a map container, `len` is the number of key-value entries.
+    /// Supply `len` as None for indefinite encoding.
+    pub fn begin_map(&mut self, len: Option<u64>) -> Result<usize> {
+        self.begin(5, true, len)
+    }
+
+    /// Write a complete data item into the innermost open container, or
+    /// as top-level item if no container is open.
+    pub fn write_item<T>(&mut self, item: T) -> Result<usize>
+    where
+        T: IntoCbor,
+    {
+        let val: Cbor = item.into_cbor()?;
+        self.add_item()?;
+        let n = val.encode(&mut self.w)?;
+        self.n += n;
+        Ok(n)
+    }
+
+    /// Close the innermost open container.
+    pub fn end(&mut self) -> Result<usize> {
+        let n = match self.stack.pop() {
+            Some(frame) => match frame.to_arity() {
+                Some(arity) if arity == frame.n => 0,
+                Some(arity) => {
+                    err_at!(FailCbor, msg: "container arity {} != {}", arity, frame.n)?
+                }
+                None if frame.map && (frame.n % 2) == 1 => {
+                    err_at!(FailCbor, msg: "map missing value for key")?
+                }
+                None => encode_hdr(7, Info::Indefinite, &mut self.w)?,
+            },
+            None => err_at!(FailCbor, msg: "no open container to end")?,
+        };
+        self.n += n;
+        Ok(n)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        err_at!(IOError, self.w.flush())
+    }
+
+    /// Finish writing and return the underlying writer. It is an error to
+    /// finish with open containers.
+    pub fn into_inner(mut self) -> Result<W> {
+        match self.stack.len() {
+            0 => {
+                self.flush()?;
+                Ok(self.w)
+            }
+            n => err_at!(FailCbor, msg: "{} containers not ended", n),
+        }
+    }
+
+    fn begin(&mut self, major: u8, map: bool, len: Option<u64>) -> Result<usize> {
+        if self.stack.len() >= (RECURSION_LIMIT as usize) {
+            err_at!(FailCbor, msg: "encode recursion limit exceeded")?
+        }
+        if let (true, Some(len)) = (map, len) {
+            if len.checked_mul(2).is_none() {
+                err_at!(FailCbor, msg: "map length {} overflow", len)?
+            }
+        }
+        self.add_item()?;
+
+        let n = match len {
+            Some(len) => {
+                encode_hdr(major, len.into(), &mut self.w)?
+                    + encode_addnl(len, &mut self.w)?
+            }
+            None => encode_hdr(major, Info::Indefinite, &mut self.w)?,
+        };
+        self.stack.push(Frame { map, len, n: 0 });
+        self.n += n;
+        Ok(n)
+    }
+
+    // account for a new item in the innermost open container.
+    fn add_item(&mut self) -> Result<()> {
+        if let Some(frame) = self.stack.last_mut() {
+            match frame.to_arity() {
+                Some(arity) if frame.n >= arity => {
+                    err_at!(FailCbor, msg: "container full with {} items", arity)?
+                }
+                _ => frame.n += 1,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_writer_test.rs"]
+mod cbor_writer_test;
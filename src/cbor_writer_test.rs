@@ -0,0 +1,113 @@
+use rand::{prelude::random, rngs::SmallRng, Rng, SeedableRng};
+
+use super::*;
+use crate::{
+    cbor::{FromCbor, Key},
+    db::Entry,
+};
+
+#[test]
+fn test_cbor_writer_entries() {
+    let seed: u128 = random();
+    println!("test_cbor_writer_entries {}", seed);
+    let mut rng = SmallRng::from_seed(seed.to_le_bytes());
+
+    let entries: Vec<Entry<u64, u64, u64>> = (0..1000)
+        .map(|i| {
+            let mut entry = Entry::new(rng.gen::<u64>(), rng.gen::<u64>(), i * 3);
+            if rng.gen::<bool>() {
+                entry.delete(i * 3 + 1);
+            }
+            entry
+        })
+        .collect();
+
+    for len in [None, Some(1000)].iter() {
+        let mut writer = CborWriter::new(vec![]);
+        writer.begin_array(*len).unwrap();
+        for entry in entries.iter() {
+            writer.write_item(entry.clone()).unwrap();
+        }
+        writer.end().unwrap();
+        let n = writer.to_offset();
+        let buf = writer.into_inner().unwrap();
+        assert_eq!(n, buf.len());
+
+        let (val, m) = Cbor::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(m, n);
+        match (&val, len) {
+            (Cbor::Major4(Info::Indefinite, _), None) => (),
+            (Cbor::Major4(Info::U16, _), Some(_)) => (),
+            (val, _) => panic!("{:?}", val),
+        }
+        let items = Vec::<Entry<u64, u64, u64>>::from_cbor(val).unwrap();
+        assert_eq!(items, entries);
+    }
+}
+
+#[test]
+fn test_cbor_writer_nested() {
+    // {_ "a": [_ 1, 2], "b": {"c": null}}
+    let mut writer = CborWriter::new(vec![]);
+    writer.begin_map(None).unwrap();
+    writer.write_item("a").unwrap();
+    writer.begin_array(None).unwrap();
+    writer.write_item(1_u64).unwrap();
+    writer.write_item(2_u64).unwrap();
+    writer.end().unwrap();
+    writer.write_item("b").unwrap();
+    writer.begin_map(Some(1)).unwrap();
+    writer.write_item("c").unwrap();
+    writer.write_item(Option::<u64>::None).unwrap();
+    writer.end().unwrap();
+    writer.end().unwrap();
+    let buf = writer.into_inner().unwrap();
+    assert_eq!(
+        buf,
+        vec![
+            0xbf, 0x61, 0x61, 0x9f, 0x01, 0x02, 0xff, 0x61, 0x62, 0xa1, 0x61, 0x63, 0xf6,
+            0xff
+        ]
+    );
+
+    let (val, n) = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(n, buf.len());
+    let map = Vec::<(Key, Cbor)>::from_cbor(val).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[0].0, Key::Text("a".to_string()));
+    assert_eq!(Vec::<u64>::from_cbor(map[0].1.clone()).unwrap(), vec![1, 2]);
+
+    let mut buf: Vec<u8> = vec![];
+    let n = map[0].1.encode(&mut buf).unwrap();
+    assert_eq!(buf, vec![0x9f, 0x01, 0x02, 0xff]);
+    assert_eq!(n, buf.len());
+}
+
+#[test]
+fn test_cbor_writer_arity() {
+    let mut writer = CborWriter::new(vec![]);
+    writer.begin_array(Some(1)).unwrap();
+    writer.write_item(1_u64).unwrap();
+    assert!(writer.write_item(2_u64).is_err());
+
+    let mut writer = CborWriter::new(vec![]);
+    writer.begin_array(Some(2)).unwrap();
+    writer.write_item(1_u64).unwrap();
+    assert!(writer.end().is_err());
+
+    let mut writer = CborWriter::new(vec![]);
+    writer.begin_map(None).unwrap();
+    writer.write_item("key").unwrap();
+    assert!(writer.end().is_err());
+
+    let mut writer = CborWriter::new(vec![]);
+    assert!(writer.end().is_err());
+    writer.begin_array(None).unwrap();
+    assert!(writer.into_inner().is_err());
+
+    let mut writer = CborWriter::new(vec![]);
+    for _ in 0..RECURSION_LIMIT {
+        writer.begin_array(None).unwrap();
+    }
+    assert!(writer.begin_array(None).is_err());
+}
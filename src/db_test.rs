@@ -317,3 +317,17 @@ fn test_purge_tombstone() {
         }
     }
 }
+
+#[test]
+fn test_entry_cbor_int128() {
+    use crate::cbor::{FromCbor, IntoCbor, ToCbor};
+
+    let mut entry: Entry<u128, i128, i128> = Entry::new(u128::MAX, i128::MIN, 1);
+    entry.insert(i128::MAX, 2);
+    entry.delete(3);
+    entry.insert(-1, 4);
+
+    let val = entry.clone().into_cbor().unwrap();
+    assert_eq!(entry.to_cbor().unwrap(), val);
+    assert_eq!(Entry::<u128, i128, i128>::from_cbor(val).unwrap(), entry);
+}
@@ -7,7 +7,7 @@ use std::{
 };
 
 use crate::{
-    cbor::{Cbor, FromCbor, IntoCbor},
+    cbor::{codec_by_tree, Cbor, FromCbor, IntoCbor, ToCbor},
     db::Bloom,
     Error, LocalCborize, Result,
 };
@@ -41,6 +41,25 @@ where
     }
 }
 
+impl<H> ToCbor for Xor8<H>
+where
+    H: BuildHasher + Clone + Into<Vec<u8>>,
+{
+    // finger prints are shared with `val`, hence converted by reference.
+    // `hash_builder` is still cloned, its bytes can only be had from an
+    // owned value via `Into<Vec<u8>>`, avoiding that needs a by-reference
+    // conversion from the xorfilter crate.
+    fn to_cbor(&self) -> Result<Cbor> {
+        let val = CborXor8 {
+            hash_builder: self.hash_builder.clone().into(),
+            seed: self.seed,
+            block_length: self.block_length,
+            finger_prints: Arc::clone(&self.finger_prints),
+        };
+        val.to_cbor()
+    }
+}
+
 impl<H> FromCbor for Xor8<H>
 where
     H: Default + BuildHasher + From<Vec<u8>>,
@@ -60,6 +79,8 @@ where
     }
 }
 
+codec_by_tree! {[H] Xor8<H>}
+
 impl<H> Bloom for Xor8<H>
 where
     H: Default + BuildHasher + From<Vec<u8>> + Into<Vec<u8>> + Clone,
@@ -83,13 +104,7 @@ where
     }
 
     fn to_bytes(&self) -> result::Result<Vec<u8>, Self::Err> {
-        let val = CborXor8 {
-            hash_builder: self.hash_builder.clone().into(),
-            seed: self.seed,
-            block_length: self.block_length,
-            finger_prints: self.finger_prints.clone(),
-        };
-        let cbor_val = err_at!(IOError, val.into_cbor())?;
+        let cbor_val = err_at!(IOError, self.to_cbor())?;
 
         let mut buf: Vec<u8> = vec![];
         err_at!(IOError, cbor_val.encode(&mut buf))?;
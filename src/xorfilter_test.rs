@@ -23,6 +23,15 @@ fn test_basic7() {
         assert!(filter.contains(key), "key {} not present", key);
     }
 
+    // by reference and by value conversion are the same.
+    let val = CborXor8 {
+        hash_builder: filter.hash_builder.clone().into(),
+        seed: filter.seed,
+        block_length: filter.block_length,
+        finger_prints: Arc::new(filter.finger_prints.to_vec()),
+    };
+    assert_eq!(filter.to_cbor().unwrap(), val.into_cbor().unwrap());
+
     let filter = {
         let bytes = <Xor8 as Bloom>::to_bytes(&filter).unwrap();
         <Xor8 as Bloom>::from_bytes(&bytes).unwrap().0